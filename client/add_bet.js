@@ -9,6 +9,7 @@ import { decode } from "./decode.js";
 const WS_ENDPOINT = process.env.WS_ENDPOINT;
 const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
 const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
 const ALICE = process.env.ALICE;
 const BOB = process.env.BOB;
 const CHARLIE = process.env.CHARLIE;
@@ -80,11 +81,13 @@ if (success) {
   await new Promise(async (resolve, reject) => {
     const unsub = await contract.tx
       .addBet({ storageDepositLimit, gasLimit }, 
+        LOTTERY_ID,
         draw_number,
         bet_number,
         bettor,
         upline,
         txHash,
+        amount,
       ).signAndSend(bob, ({ status, events }) => {    
         console.log("Status:", status?.type);
         if(events?.length > 0) {
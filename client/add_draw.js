@@ -9,6 +9,7 @@ import { decode } from "./decode.js";
 const WS_ENDPOINT = process.env.WS_ENDPOINT;
 const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
 const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
 const ALICE = process.env.ALICE;
 const BOB = process.env.BOB;
 
@@ -43,10 +44,12 @@ await new Promise(async (resolve, reject) => {
 
   const unsub = await contract.tx
     .addDraw({ storageDepositLimit, gasLimit }, 
+      LOTTERY_ID,
       opening_blocks,
       processing_blocks,
       closing_blocks,
       bet_amount,
+      "PariMutuel",     // Settlement mode, e.g., { FixedOdds: { multiplier: 700 } }
     ).signAndSend(bob, ({ status, events, dispatchError }) => {    
       console.log("Status:", status?.type);
       if(events?.length > 0) {
@@ -10,6 +10,7 @@ export async function closeDraw(api, draw_number) {
 
   const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
   const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+  const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
   const ALICE = process.env.ALICE;
   const BOB = process.env.BOB;
 
@@ -30,7 +31,7 @@ export async function closeDraw(api, draw_number) {
 
   await new Promise(async (resolve, reject) => {
     const unsub = await contract.tx
-      .closeDraw({storageDepositLimit, gasLimit}, draw_number)
+      .closeDraw({storageDepositLimit, gasLimit}, LOTTERY_ID, draw_number)
       .signAndSend(bob, ({ status, events, dispatchError }) => {
         console.log(colors.darkGray(`Closing Draw Status: ${status?.type}`)); 
         if(events?.length > 0) {
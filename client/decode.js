@@ -6,23 +6,26 @@ export function decode(data) {
     const [address, raw] = data;
 
     const bytes = raw.toU8a();
-    const operatorHash = bytes.slice(0, 32);    // topic in payload
-    const payload = bytes.slice(32);            // event payload
-
-    const errorMap = [
-        "Error::AlreadyStarted",
-        "Error::InvalidBlock",
-        "Error::NoRecords",
-        "Error::BadOrigin",
-        "Error::TooManyDraws",
-        "Error::DrawNotFound",
-        "Error::DrawClosed",
-        "Error::DrawOpen",
-        "Error::DrawProcessing",
-        "Error::DrawNotProcessing",
-        "Error::InvalidBetAmount",
-        "Error::InvalidBlocksHierarchy",
-    ]; 
+    const operatorHash = bytes.slice(1, 33);    // topic in payload
+    const lotteryId = readU32(bytes, 33);       // lottery of the event
+    const callerHash = bytes.slice(37, 69);     // caller of the message
+
+    // Option<u32> draw number followed by the u32 block number, u64 timestamp and the
+    // u16 code of the status
+    let offset = 69;
+    const hasDraw = bytes[offset] === 1;
+    offset += 1;
+    const drawNumber = hasDraw ? readU32(bytes, offset) : null;
+    if (hasDraw) {
+        offset += 4;
+    }
+    const blockNumber = readU32(bytes, offset);
+    offset += 4;
+    offset += 8;                                // u64 block timestamp
+    const code = readU16(bytes, offset);        // stable code of the status
+    offset += 2;
+
+    const payload = bytes.slice(offset - 1);    // event payload
 
     const successMap = [
         "Success::LotterySetup",
@@ -34,13 +37,84 @@ export function decode(data) {
         "Success::DrawProcessed",
         "Success::DrawClosed",
         "Success::BetAdded",
+        "Success::JackpotAdded",
+        "Success::PayoutsRetried",
+        "Success::BetTransferred",
+        "Success::EscrowClaimed",
+        "Success::OracleSet",
+        "Success::LotteryCreated",
+        "Success::PayoutStrategySet",
+        "Success::VerifiedBetsSet",
+        "Success::VaultSet",
+        "Success::NftCollectionSet",
+        "Success::AssetMetadataSet",
+        "Success::DrawAffiliatePercentageSet",
+        "Success::ProgressivePercentageSet",
+        "Success::ProgressiveJackpotWon",
+        "Success::DivisionSharesSet",
+        "Success::BulkDiscountSet",
+        "Success::StreakBonusSet",
+        "Success::StreakBonusPaid",
+        "Success::LiabilityCapSet",
+        "Success::InsurancePercentageSet",
+        "Success::InsuranceToppedUp",
+        "Success::InsuranceWithdrawn",
+        "Success::InsuranceCovered",
+        "Success::RebateToppedUp",
+        "Success::RebateTiersSet",
+        "Success::BetRangeSet",
+        "Success::EarlyBirdSet",
+        "Success::BundleBought",
+        "Success::VoucherCreated",
+        "Success::VoucherRedeemed",
+        "Success::ReferralBonusSet",
+        "Success::ReferralBonusPaid",
+        "Success::ImportCompleted",
+        "Success::PayoutPoliciesSet",
+        "Success::PayoutHeld",
+        "Success::PayoutsApproved",
+        "Success::PayoutsReleased",
+        "Success::BetCutoffSet",
+        "Success::DrawRolledOver",
+        "Success::MinParticipationSet",
+        "Success::StakesRefunded",
+        "Success::CarryForwardSet",
+        "Success::BetsCarriedForward",
+        "Success::DrawDelegateSet",
+        "Success::ResultCommitted",
+        "Success::ResultPublished",
+        "Success::DrawSettled",
+        "Success::StoragePruned",
+        "Success::CompactBetsSet",
+        "Success::ClaimPayoutsSet",
+        "Success::ClaimsRecorded",
+        "Success::PrizeClaimed",
+        "Success::RebateClaimed",
+        "Success::SaltCommitted",
+        "Success::SaltRevealed",
+        "Success::PrizeTiersSet",
+        "Success::SharesSet",
+        "Success::CompanionSet",
+        "Success::DepositBudgetSet",
+        "Success::VoucherClaimed",
+        "Success::SchedulingFailed",
     ];     
 
     if (payload[1] === 0) {
-        return successMap[payload[2]];
-    } else if (payload[1] === 1) {
-        return errorMap[payload[2]];
+        const draw = drawNumber === null ? "" : ` draw #${drawNumber}`;
+        return `${successMap[payload[2]]} (${code}) lottery #${lotteryId}${draw} @ block ${blockNumber}`;
     } else {
         throw new Error("Invalid event payload");
     }    
-}
\ No newline at end of file
+}
+
+function readU16(bytes, offset) {
+    return bytes[offset] | (bytes[offset + 1] << 8);
+}
+
+function readU32(bytes, offset) {
+    return (bytes[offset] |
+        (bytes[offset + 1] << 8) |
+        (bytes[offset + 2] << 16) |
+        (bytes[offset + 3] << 24)) >>> 0;
+}
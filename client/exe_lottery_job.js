@@ -102,7 +102,7 @@ async function main () {
                             let jackpot = Number(d.jackpot.replace(/,/g, '')) / 1000000; // Token decimal
                             
                             return `[Draw: #${d.drawNumber} (${d.status}, ${d.isOpen}, O:${opening_blocks}, P:${processing_blocks}, C:${closing_blocks}): ` +
-                                `Pot:${jackpot}USDT Bets:${d.bets.length} Win#:${d.winningNumber} Winners:${d.winners.length}]`;
+                                `Pot:${jackpot}USDT Bets:${d.betCount} Win#:${d.winningNumber} Winners:${d.winners.length}]`;
                         }).join(", ")
                     ));
                 }); 
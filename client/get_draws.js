@@ -5,6 +5,7 @@ import 'dotenv/config';
 
 const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
 const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
 const ALICE = process.env.ALICE;
 
 export async function getDraws(api) {
@@ -23,7 +24,8 @@ export async function getDraws(api) {
     /// Get draws
     const { result, output } = await contract.query.getDraws(alice.address, { 
         gasLimit: gasLimit,
-        storageDepositLimit: storageDepositLimit,}
+        storageDepositLimit: storageDepositLimit,},
+        LOTTERY_ID
     );
     if (result.isOk) {
         return output.toHuman();
@@ -7,6 +7,7 @@ import 'dotenv/config';
 export async function getLottery(api) {
     const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
     const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+    const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
     const ALICE = process.env.ALICE;
 
     const abiJSON = JSON.parse(fs.readFileSync(CONTRACT_ABI_PATH, "utf8"));
@@ -24,7 +25,8 @@ export async function getLottery(api) {
     /// Get the lottery setup
     const { result, output } = await contract.query.getLotterySetup(alice.address, { 
         gasLimit: gasLimit,
-        storageDepositLimit: storageDepositLimit,}
+        storageDepositLimit: storageDepositLimit,},
+        LOTTERY_ID
     );
     if (result.isOk) {
         return output.toHuman();
@@ -0,0 +1,43 @@
+import { ContractPromise } from "@polkadot/api-contract";
+import { Keyring } from "@polkadot/keyring";
+import fs from "fs";
+import 'dotenv/config';
+
+// Versions decode.js is written for
+const STORAGE_VERSION = 5;
+const EVENT_SCHEMA_VERSION = 6;
+
+export async function getVersion(api) {
+    const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
+    const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+    const ALICE = process.env.ALICE;
+
+    const abiJSON = JSON.parse(fs.readFileSync(CONTRACT_ABI_PATH, "utf8"));
+    const contract = new ContractPromise(api, abiJSON, CONTRACT_ADDRESS);
+
+    const keyring = new Keyring({ type: "sr25519" });
+    const alice = keyring.addFromUri(ALICE);
+
+    const gasLimit = api.registry.createType('WeightV2', {
+            refTime: 300000000000,
+            proofSize: 500000,
+    });
+    const storageDepositLimit = null;
+
+    /// Get the version of the deployed contract
+    const { result, output } = await contract.query.getVersion(alice.address, {
+        gasLimit: gasLimit,
+        storageDepositLimit: storageDepositLimit,}
+    );
+    if (result.isOk) {
+        const version = output.toJSON().ok;
+        if (version.storageVersion !== STORAGE_VERSION || version.eventSchemaVersion !== EVENT_SCHEMA_VERSION) {
+            console.warn(`Contract ${version.crateVersion} (${version.gitHash}) is not supported by this client, ` +
+                `storage version ${version.storageVersion} and event schema version ${version.eventSchemaVersion}`);
+        }
+        return version;
+    } else {
+        console.error(result.asErr.toHuman());
+        return null;
+    }
+}
@@ -9,6 +9,7 @@ import { decode } from "./decode.js";
 const WS_ENDPOINT = process.env.WS_ENDPOINT;
 const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
 const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
 const ALICE = process.env.ALICE;
 const BOB = process.env.BOB;
 
@@ -38,6 +39,7 @@ await new Promise(async (resolve, reject) => {
 
   const unsub = await contract.tx
     .overrideDraw({ storageDepositLimit, gasLimit }, 
+      LOTTERY_ID,
       draw_number,
       winning_number,
     ).signAndSend(bob, ({ status, events, dispatchError }) => {    
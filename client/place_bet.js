@@ -0,0 +1,100 @@
+import { ApiPromise, WsProvider } from "@polkadot/api";
+import { ContractPromise } from "@polkadot/api-contract";
+import { Keyring } from "@polkadot/keyring";
+import fs from "fs";
+import 'dotenv/config';
+
+import { decode } from "./decode.js";
+
+const WS_ENDPOINT = process.env.WS_ENDPOINT;
+const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
+const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
+const BOB = process.env.BOB;
+const CHARLIE = process.env.CHARLIE;
+
+/// Test the blockchain connection
+console.log("Connecting to blockchain...");
+const wsProvider = new WsProvider(WS_ENDPOINT);
+const api = await ApiPromise.create({ provider: wsProvider });
+console.log("Connected to:", (await api.rpc.system.chain()).toHuman());
+
+const abiJSON = JSON.parse(fs.readFileSync(CONTRACT_ABI_PATH, "utf8"));
+const contract = new ContractPromise(api, abiJSON, CONTRACT_ADDRESS);
+
+const keyring = new Keyring({ type: "sr25519" });
+const bob = keyring.addFromUri(BOB);
+const charlie = keyring.addFromUri(CHARLIE);
+
+const gasLimit = api.registry.createType('WeightV2', {
+          refTime: 300000000000,
+          proofSize: 500000,
+});
+const storageDepositLimit = null;
+
+const amount = 500000; // 0.5 (1_000_000)
+const asset_id = 1984
+
+const formattedAmount = api.createType(
+      "Compact<u128>",
+      amount
+);
+
+let success = false;
+
+/// Approve the lottery contract to collect the bet amount, no transfer is sent to
+/// the operator and no tx hash has to be verified
+await new Promise(async (resolve, reject) => {
+
+  const tx = api.tx.assets.approveTransfer(
+    asset_id,
+    CONTRACT_ADDRESS,
+    formattedAmount
+  );
+
+  const unsub = await tx.signAndSend(charlie, ({ status, events }) => {
+    console.log("Status:", status?.type);
+    if(events?.length > 0) {
+      events.forEach(({ event }) => {
+        if (event.section === "assets" && event.method === "ApprovedTransfer") {
+          success = true;
+          console.log("Approval successful.");
+          unsub();
+          resolve();
+        }
+      });
+    }
+  });
+});
+
+/// If approved, the bettor places the bet, the contract pulls the approved amount
+const draw_number = 1;
+const bet_number = 555;
+const upline = bob.address;
+
+if (success) {
+  await new Promise(async (resolve, reject) => {
+    const unsub = await contract.tx
+      .placeBet({ storageDepositLimit, gasLimit },
+        LOTTERY_ID,
+        draw_number,
+        bet_number,
+        upline,
+      ).signAndSend(charlie, ({ status, events }) => {
+        console.log("Status:", status?.type);
+        if(events?.length > 0) {
+          events.forEach(({ event }) => {
+            if (event.section === "contracts" && event.method === "ContractEmitted") {
+              console.log(decode(event.data));
+              unsub();
+              resolve();
+            }
+          });
+        }
+    });
+  });
+}
+
+
+
+process.exit(0);
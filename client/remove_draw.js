@@ -9,6 +9,7 @@ import { decode } from "./decode.js";
 const WS_ENDPOINT = process.env.WS_ENDPOINT;
 const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
 const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
 const ALICE = process.env.ALICE;
 const BOB = process.env.BOB;
 
@@ -34,7 +35,7 @@ const storageDepositLimit = null;
 await new Promise(async (resolve, reject) => {
 
   const unsub = await contract.tx
-    .removeDraw({ storageDepositLimit, gasLimit })
+    .removeDraw({ storageDepositLimit, gasLimit }, LOTTERY_ID)
     .signAndSend(bob, ({ status, events, dispatchError }) => {    
       console.log("Status:", status?.type);
       if(events?.length > 0) {
@@ -9,6 +9,7 @@ import { decode } from "./decode.js";
 const WS_ENDPOINT = process.env.WS_ENDPOINT;
 const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
 const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
 const ALICE = process.env.ALICE;
 const BOB = process.env.BOB;
 
@@ -41,12 +42,16 @@ const maximumBets = 1000;
 await new Promise(async (resolve, reject) => {
   const unsub = await contract.tx
     .setup({ storageDepositLimit, gasLimit }, 
+      LOTTERY_ID,
       bob.address,      // Operator
       asset_id,
       startingBlock,
       dailyTotalBlocks,
       maximumDraws,
       maximumBets,
+      "Assets",         // Payment backend, e.g., { Psp22: tokenAddress }, "Native" or "Simulated"
+      "Internal",       // Randomness source, e.g., "Runtime", "Epoch", "SaltReveal", { Oracle: oracleAddress } or { Vrf: vrfAddress }
+      10,               // Affiliate percentage, at most 30
     )
     .signAndSend(alice, ({ status, events, data }) => {
       console.log("Status:", status?.type);
@@ -10,6 +10,7 @@ export async function stopLottery(api) {
 
   const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
   const CONTRACT_ABI_PATH = process.env.CONTRACT_ABI_PATH;
+  const LOTTERY_ID = Number(process.env.LOTTERY_ID ?? 0);
   const ALICE = process.env.ALICE;
   const BOB = process.env.BOB;
 
@@ -30,7 +31,7 @@ export async function stopLottery(api) {
 
   await new Promise(async (resolve, reject) => {
     const unsub = await contract.tx
-      .stop({ storageDepositLimit, gasLimit })
+      .stop({ storageDepositLimit, gasLimit }, LOTTERY_ID)
       .signAndSend(bob, ({ status, events, dispatchError }) => {    
         console.log(colors.darkGray(`Stopping Lottery Status: ${status?.type}`));
         if(events?.length > 0) {
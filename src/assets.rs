@@ -1,5 +1,9 @@
 use sp_runtime::MultiAddress;
 use ink::env::DefaultEnvironment;
+use ink::prelude::boxed::Box;
+use ink::prelude::vec::Vec;
+
+use crate::xcm::{VersionedAssets, VersionedLocation, WeightLimit};
 
 type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
 type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
@@ -9,6 +13,24 @@ pub enum RuntimeCall {
     /// Dispatches a call to the `Assets` pallet.
     #[codec(index = 50)]
     Assets(AssetsCall),
+    /// Dispatches a call to the `Balances` pallet.
+    #[codec(index = 10)]
+    Balances(BalancesCall),
+    /// Dispatches a call to the `PolkadotXcm` pallet.
+    #[codec(index = 31)]
+    PolkadotXcm(PolkadotXcmCall),
+    /// Dispatches a call to the `Scheduler` pallet.
+    #[codec(index = 15)]
+    Scheduler(SchedulerCall),
+    /// Dispatches a call to the `Contracts` pallet.
+    #[codec(index = 40)]
+    Contracts(ContractsCall),
+    /// Dispatches a call to the `Utility` pallet.
+    #[codec(index = 45)]
+    Utility(UtilityCall),
+    /// Dispatches a call to the `Nfts` pallet.
+    #[codec(index = 52)]
+    Nfts(NftsCall),
 }
 
 /// Defines relevant `Assets` pallet calls for web3 lottery.
@@ -25,4 +47,128 @@ pub enum AssetsCall {
         #[codec(compact)]
         amount: Balance,
     },
-}
\ No newline at end of file
+    /// Transfer some asset balance from a previously delegated account to some
+    /// third-party account.
+    ///
+    /// Used to collect the stake of a bet approved by the bettor.
+    #[codec(index = 25)]
+    TransferApproved {
+        #[codec(compact)]
+        id: u128,
+        owner: MultiAddress<AccountId, ()>,
+        destination: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        amount: Balance,
+    },
+}
+
+/// Defines relevant `Balances` pallet calls for web3 lottery.
+#[ink::scale_derive(Encode)]
+pub enum BalancesCall {
+    /// Transfer some native tokens to another account while keeping the sender alive.
+    ///
+    /// Used to pay out in the chain's native token.
+    #[codec(index = 3)]
+    TransferKeepAlive {
+        dest: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        value: Balance,
+    },
+}
+
+/// Pallet instance of the `Assets` pallet, used to locate local assets via XCM.
+pub const ASSETS_PALLET_INSTANCE: u8 = 50;
+
+/// Defines relevant `PolkadotXcm` pallet calls for web3 lottery.
+#[ink::scale_derive(Encode)]
+pub enum PolkadotXcmCall {
+    /// Transfer assets to another chain with the local chain as reserve.
+    ///
+    /// Used to deliver prizes to winners on sibling parachains.
+    #[codec(index = 8)]
+    LimitedReserveTransferAssets {
+        dest: VersionedLocation,
+        beneficiary: VersionedLocation,
+        assets: VersionedAssets,
+        fee_asset_item: u32,
+        weight_limit: WeightLimit,
+    },
+}
+
+/// Defines relevant `Scheduler` pallet calls for web3 lottery.
+#[ink::scale_derive(Encode)]
+pub enum SchedulerCall {
+    /// Schedule a call to be dispatched at a given block.
+    ///
+    /// Used to process and close draws at their deadline blocks.
+    #[codec(index = 0)]
+    Schedule {
+        when: u32,
+        maybe_periodic: Option<(u32, u32)>,
+        priority: u8,
+        call: Box<RuntimeCall>,
+    },
+}
+
+/// Weight of a dispatch
+#[ink::scale_derive(Encode)]
+pub struct Weight {
+    #[codec(compact)]
+    pub ref_time: u64,
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+/// Defines relevant `Contracts` pallet calls for web3 lottery.
+#[ink::scale_derive(Encode)]
+pub enum ContractsCall {
+    /// Call a contract message.
+    ///
+    /// Used by the scheduler to call the lottery back.
+    #[codec(index = 6)]
+    Call {
+        dest: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        value: Balance,
+        gas_limit: Weight,
+        storage_deposit_limit: Option<scale::Compact<Balance>>,
+        data: Vec<u8>,
+    },
+}
+
+/// Defines relevant `Utility` pallet calls for web3 lottery.
+#[ink::scale_derive(Encode)]
+pub enum UtilityCall {
+    /// Dispatch a batch of calls, reverting all of them if any fails.
+    ///
+    /// Used to settle all the payouts of a draw atomically.
+    #[codec(index = 2)]
+    BatchAll {
+        calls: Vec<RuntimeCall>,
+    },
+}
+
+/// Defines relevant `Nfts` pallet calls for web3 lottery.
+#[ink::scale_derive(Encode)]
+pub enum NftsCall {
+    /// Mint an item of a collection issued by the sender.
+    ///
+    /// Used to mint a commemorative NFT for every winner.
+    #[codec(index = 3)]
+    Mint {
+        collection: u32,
+        item: u32,
+        mint_to: MultiAddress<AccountId, ()>,
+        // Only needed to mint for holders of another collection, always `None`
+        witness_data: Option<()>,
+    },
+    /// Set the metadata of an item.
+    ///
+    /// Used to record the draw, the number and the prize of the winner.
+    #[codec(index = 24)]
+    SetMetadata {
+        collection: u32,
+        item: u32,
+        data: Vec<u8>,
+    },
+}
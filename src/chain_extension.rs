@@ -0,0 +1,93 @@
+use ink::env::chain_extension::{ChainExtensionMethod, FromStatusCode};
+
+use crate::errors::RuntimeError;
+
+#[cfg(feature = "assets-extension")]
+type AccountId = <ink::env::DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Id of the assets chain extension registered in the runtime.
+pub const ASSETS_EXTENSION_ID: u16 = 0x0a55;
+
+/// `pallet_assets::balance(asset_id, who)`
+pub const BALANCE_OF_FUNC_ID: u16 = 0x0001;
+
+/// `pallet_assets::decimals(asset_id)`
+pub const DECIMALS_FUNC_ID: u16 = 0x0002;
+
+/// Id of the randomness chain extension registered in the runtime.
+pub const RANDOMNESS_EXTENSION_ID: u16 = 0x0a56;
+
+/// `T::Randomness::random(subject)`, e.g., `pallet_insecure_randomness_collective_flip`
+pub const FETCH_RANDOM_FUNC_ID: u16 = 0x0001;
+
+/// `T::EpochRandomness::random(subject)`, e.g., the BABE randomness of one epoch ago
+pub const FETCH_EPOCH_RANDOM_FUNC_ID: u16 = 0x0002;
+
+/// Status code returned by the assets chain extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum AssetsExtensionError {
+    /// The asset does not exist.
+    UnknownAsset,
+    /// Any other status code returned by the runtime.
+    Unknown(u32),
+}
+
+impl FromStatusCode for AssetsExtensionError {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            1 => Err(Self::UnknownAsset),
+            code => Err(Self::Unknown(code)),
+        }
+    }
+}
+
+impl From<AssetsExtensionError> for RuntimeError {
+    fn from(_: AssetsExtensionError) -> Self {
+        RuntimeError::ChainExtensionFailed
+    }
+}
+
+/// Returns the `pallet_assets` balance of an account.
+#[cfg(feature = "assets-extension")]
+pub fn balance_of(asset_id: u128, owner: AccountId) -> Result<u128, RuntimeError> {
+    ChainExtensionMethod::build(ASSETS_EXTENSION_ID, BALANCE_OF_FUNC_ID)
+        .input::<(u128, AccountId)>()
+        .output::<u128, false>()
+        .handle_error_code::<AssetsExtensionError>()
+        .call(&(asset_id, owner))
+        .map_err(RuntimeError::from)
+}
+
+/// Returns the decimals of a `pallet_assets` asset.
+#[cfg(feature = "assets-extension")]
+pub fn decimals(asset_id: u128) -> Result<u8, RuntimeError> {
+    ChainExtensionMethod::build(ASSETS_EXTENSION_ID, DECIMALS_FUNC_ID)
+        .input::<u128>()
+        .output::<u8, false>()
+        .handle_error_code::<AssetsExtensionError>()
+        .call(&asset_id)
+        .map_err(RuntimeError::from)
+}
+
+/// Returns the runtime randomness for the subject.
+#[cfg(feature = "randomness-extension")]
+pub fn fetch_random(subject: [u8; 32]) -> [u8; 32] {
+    ChainExtensionMethod::build(RANDOMNESS_EXTENSION_ID, FETCH_RANDOM_FUNC_ID)
+        .input::<[u8; 32]>()
+        .output::<[u8; 32], false>()
+        .ignore_error_code()
+        .call(&subject)
+}
+
+/// Returns the epoch randomness of the runtime for the subject and the block number
+/// since which it is known.
+#[cfg(feature = "randomness-extension")]
+pub fn fetch_epoch_random(subject: [u8; 32]) -> ([u8; 32], u32) {
+    ChainExtensionMethod::build(RANDOMNESS_EXTENSION_ID, FETCH_EPOCH_RANDOM_FUNC_ID)
+        .input::<[u8; 32]>()
+        .output::<([u8; 32], u32), false>()
+        .ignore_error_code()
+        .call(&subject)
+}
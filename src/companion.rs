@@ -0,0 +1,49 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+use ink::prelude::vec::Vec;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Settlement listener contract interface, see the `stats` contract.
+#[ink::trait_definition]
+pub trait SettlementListener {
+    /// Called when a draw is closed with the owners of its bets and the prize of each
+    /// winner.
+    #[ink(message)]
+    fn on_settlement(&mut self,
+        lottery_id: u32,
+        draw_number: u32,
+        bet_amount: u128,
+        bettors: Vec<AccountId>,
+        winners: Vec<(AccountId, u128)>);
+}
+
+/// Notifies the companion contract of a settlement (`SettlementListener::on_settlement`).
+pub fn on_settlement(companion: AccountId,
+                     lottery_id: u32,
+                     draw_number: u32,
+                     bet_amount: u128,
+                     bettors: Vec<AccountId>,
+                     winners: Vec<(AccountId, u128)>) -> Result<(), RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(companion)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("SettlementListener::on_settlement")))
+                .push_arg(lottery_id)
+                .push_arg(draw_number)
+                .push_arg(bet_amount)
+                .push_arg(bettors)
+                .push_arg(winners),
+        )
+        .returns::<()>()
+        .try_invoke();
+
+    match result {
+        Ok(Ok(())) => Ok(()),
+        // The companion contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
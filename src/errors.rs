@@ -1,7 +1,19 @@
 
 use scale::{Decode, Encode};
+use ink::env::DefaultEnvironment;
 use ink::env::Error as EnvError;
 
+use crate::events::PayoutRole;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// First code of the errors, see `ContractError::code`
+pub const ERROR_CODE_BASE: u16 = 2000;
+/// First code of the runtime errors
+pub const RUNTIME_ERROR_CODE_BASE: u16 = 3000;
+/// First code of the failed transfers, by the runtime error that caused them
+pub const TRANSFER_FAILED_CODE_BASE: u16 = 4000;
+
 /// Lottery error messages
 #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -9,13 +21,21 @@ pub enum Error {
     /// Attempt to start the lottery when it is already started
     AlreadyStarted,
     /// Error when starting the lottery beyond the starting block
-    InvalidBlock,
+    InvalidBlock {
+        /// Block the lottery operation is allowed from
+        required_block: u32,
+        /// Block at which the call was made
+        current_block: u32,
+    },
     /// Standard error if it could not find the record
     NoRecords,    
     /// Standard error if the account is not was is expected
     BadOrigin,
     /// Total draws exceeded the set maximum draws
-    TooManyDraws,
+    TooManyDraws {
+        /// Maximum draws allowed by the lottery setup
+        limit: u8,
+    },
     /// Cannot find the draw number
     DrawNotFound,
     /// The draw is still close
@@ -27,22 +47,278 @@ pub enum Error {
     /// The draw is not anymore processing
     DrawNotProcessing,
     /// The bet must equal to the set bet amount
-    InvalidBetAmount,
-    /// Invalid blocks hierarchy
+    InvalidBetAmount {
+        /// Bet amount of the draw
+        expected: u128,
+        /// Amount that was paid
+        actual: u128,
+    },
+    /// Invalid blocks hierarchy, i.e., the draw closes after the daily cycle ends
     InvalidBlocksHierarchy,
     /// The draw is not yet closed
     DrawNotClosed,
+    /// The draw has not yet reached its opening, processing or closing block
+    DrawNotDue {
+        /// Block from which the draw operation is allowed
+        due_block: u32,
+        /// Block at which the call was made
+        current_block: u32,
+    },
+    /// The bet number is outside of the numbers that can be drawn
+    BetNumberOutOfRange {
+        /// Smallest valid number
+        min: u16,
+        /// Largest valid number
+        max: u16,
+        /// Number that was given
+        actual: u16,
+    },
+    /// The draw bet amount must not be zero
+    ZeroBetAmount,
+    /// The draw processing blocks must be greater than the opening blocks
+    EmptyBettingWindow,
+    /// The draw closing blocks must be greater than the processing blocks
+    EmptyProcessingWindow,
+    /// There is no bet at the given index of the draw
+    BetNotFound,
+    /// Only the owner of the bet can transfer it
+    NotBetOwner,
+    /// The bets of the bettor exceed the fiat value allowed per day
+    DailyLimitExceeded {
+        /// Fiat value allowed per bettor per day
+        limit: u128,
+        /// Fiat value of the bets including the rejected one
+        actual: u128,
+    },
+    /// There is no lottery with the given id
+    LotteryNotFound,
+    /// The lottery only accepts bets whose stake is collected by the contract
+    UnverifiedBet,
+    /// The code hash is not the announced upgrade
+    UpgradeNotAnnounced,
+    /// An account can't refer itself
+    SelfReferral,
+    /// The referrer of the account is already registered
+    ReferrerAlreadyRegistered,
+    /// The amount is not a whole number of cents of the asset
+    InvalidBetUnit {
+        /// Smallest amount the bet amount must be a multiple of
+        unit: u128,
+        /// Amount that was given
+        actual: u128,
+    },
+    /// The decimals differ from the metadata of the asset
+    AssetDecimalsMismatch {
+        /// Decimals of the asset
+        expected: u8,
+        /// Decimals that were given
+        actual: u8,
+    },
+    /// The affiliate percentage exceeds the combined share of the operator and the affiliate
+    InvalidAffiliatePercentage {
+        /// Largest affiliate percentage
+        maximum: u8,
+        /// Percentage that was given
+        actual: u8,
+    },
+    /// The progressive jackpot percentage exceeds `MAX_PROGRESSIVE_PERCENTAGE`
+    InvalidProgressivePercentage {
+        /// Largest progressive jackpot percentage
+        maximum: u8,
+        /// Percentage that was given
+        actual: u8,
+    },
+    /// The percentages of the prize divisions exceed the jackpot
+    InvalidDivisionShares {
+        /// Sum of the percentages that were given
+        total: u16,
+    },
+    /// Fixed odds must pay more than the bet amount
+    InvalidOdds {
+        /// Multiplier that was given
+        multiplier: u32,
+    },
+    /// The discount of a ticket exceeds the operator share of the ticket
+    DiscountExceedsOperatorShare,
+    /// The account already placed its bet in the promotional draw
+    AlreadyBet,
+    /// The bonus percentage exceeds the operator share left after the affiliate percentage
+    InvalidBonusPercentage {
+        /// Largest bonus percentage
+        maximum: u8,
+        /// Percentage that was given
+        actual: u8,
+    },
+    /// The bet would owe more on its number than the liability cap or the prize reserve
+    /// of the fixed-odds draw
+    LiabilityCapReached,
+    /// The insurance fund percentage exceeds `MAX_INSURANCE_PERCENTAGE`
+    InvalidInsurancePercentage {
+        /// Largest insurance fund percentage
+        maximum: u8,
+        /// Percentage that was given
+        actual: u8,
+    },
+    /// The insurance fund holds less than the requested amount
+    InsufficientInsurance {
+        /// Balance of the fund
+        available: u128,
+    },
+    /// The rebate tiers are not ascending, have a zero weight or are too many
+    InvalidRebateTiers,
+    /// The minimum stake is zero or above the maximum stake
+    InvalidBetRange {
+        /// Minimum stake that was given
+        min_bet: u128,
+        /// Maximum stake that was given
+        max_bet: u128,
+    },
+    /// The stake of the bet is outside of the stake range of the draw
+    StakeOutOfRange {
+        /// Minimum stake of the draw
+        min_bet: u128,
+        /// Maximum stake of the draw
+        max_bet: u128,
+        /// Stake of the bet
+        actual: u128,
+    },
+    /// A voucher with the code hash already exists
+    VoucherExists,
+    /// No voucher matches the code
+    VoucherNotFound,
+    /// The voucher was already redeemed
+    VoucherRedeemed,
+    /// The free-bet credits of the account are less than the bet amount
+    InsufficientCredits {
+        /// Credits of the account
+        available: u128,
+    },
+    /// A money path of the contract was re-entered, e.g., by a token callback
+    Reentrancy,
+    /// The import of a migration was completed, the state can't be imported anymore
+    ImportClosed,
+    /// The snapshot can't be decoded, is of another storage version or conflicts with
+    /// the imported state
+    InvalidSnapshot,
+    /// The payout policies are not ascending by amount or require more approvals than
+    /// there are approvers
+    InvalidPayoutPolicy,
+    /// The draw stopped accepting bets before its processing block
+    BetCutoffReached {
+        /// First block without bets
+        cutoff_block: u32,
+        /// Block at which the call was made
+        current_block: u32,
+    },
+    /// The draw holds bets carried forward to its next cycle
+    CarriedBetsPending,
+    /// The draw of a commit-reveal lottery has no committed result
+    ResultNotCommitted,
+    /// The committed result of the draw is not yet published
+    ResultNotPublished,
+    /// The result does not match the commitment of the draw
+    CommitmentMismatch,
+    /// Storing the bet would exceed the storage deposit budget of the contract
+    DepositBudgetExceeded {
+        /// Deposit budget of the contract
+        limit: u128,
+        /// Estimated deposit including the bet
+        required: u128,
+    },
+    /// The draw keeps its bets per number, the bet or the setting needs full bets
+    CompactBetsUnsupported,
+    /// The draw of a salt-reveal lottery has no committed salt, or the draw of another
+    /// lottery was opened with one
+    SaltNotCommitted,
+    /// The committed salt of the draw is not yet revealed
+    SaltNotRevealed,
+    /// A prize tier matches no digits or more than the draw format, or several tiers
+    /// match the same number of digits
+    InvalidPrizeTiers,
+    /// The stake shares don't sum up to 100%, leave no room for the progressive and
+    /// insurance slices of the jackpot, or the winner share exceeds 100%
+    InvalidShares,
+    /// The salt of the draw can only be revealed in its reveal block
+    SaltRevealMissed {
+        /// Block in which the salt had to be revealed
+        reveal_block: u32,
+        /// Block at which the call was made
+        current_block: u32,
+    },
+    /// The caller didn't claim the voucher code before redeeming it
+    VoucherNotClaimed,
+    /// The voucher code was claimed in the current block
+    VoucherClaimPending {
+        /// First block in which the code can be redeemed
+        redeemable_block: u32,
+    },
+    /// The voucher claim of the caller expired, the code must be claimed again
+    VoucherClaimExpired {
+        /// Last block in which the code could be redeemed
+        expiry_block: u32,
+    },
+    /// The contract holds less than the imported lotteries owe in one of their assets
+    ImportUnderfunded {
+        /// Jackpots, held payouts, escrow and credits owed in the asset
+        liabilities: u128,
+        /// Balance of the contract in the asset
+        available: u128,
+    },
 }
 
 /// Runtime call execution error
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
 pub enum RuntimeError {
     /// Failed to dispatch a runtime call.
     CallRuntimeFailed,
+    /// The runtime rejected a balance transfer.
+    TransferFailed,
+    /// The called code trapped.
+    CalleeTrapped,
+    /// The called code reverted.
+    CalleeReverted,
+    /// The runtime response could not be decoded.
+    DecodeFailed,
+    /// Any other error reported by the contracts environment.
+    Unknown,
+    /// The PSP22 token contract rejected the transfer.
+    TokenTransferFailed,
+    /// The assets chain extension failed or is not enabled.
+    ChainExtensionFailed,
+    /// The payment backend cannot be delivered via XCM.
+    XcmUnsupported,
+    /// The price oracle has no price for the asset.
+    PriceUnavailable,
+    /// The payout strategy returned shares that don't match the winners or exceed
+    /// the jackpot.
+    InvalidPayoutShares,
+    /// The randomness source has no random seed for the draw.
+    RandomnessUnavailable,
+    /// The prize vault refused the deposit or the withdrawal.
+    VaultRejected,
+    /// The payment backend cannot be held by the prize vault.
+    VaultUnsupported,
+}
+
+/// Asset transfer that could not be dispatched
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub struct TransferFailure {
+    /// Draw the transfer belongs to
+    pub draw_number: u32,
+    /// Intended recipient of the transfer
+    pub recipient: AccountId,
+    /// Reason the recipient was being paid
+    pub role: PayoutRole,
+    /// Amount of the transfer
+    pub amount: u128,
+    /// Error reported by the runtime
+    pub cause: RuntimeError,
 }
 
-/// Unified contract error type.
+/// Unified contract error type returned by every message.
 #[derive(Debug, PartialEq, Eq, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum ContractError {
@@ -50,6 +326,36 @@ pub enum ContractError {
     Internal(Error),
     /// Runtime call errors.
     Runtime(RuntimeError),
+    /// Asset transfer errors.
+    TransferFailed(TransferFailure),
+}
+
+impl Error {
+    /// Stable code of the error, `ERROR_CODE_BASE` plus the index of the variant.
+    /// Variants are only ever appended, so a code never changes meaning.
+    pub fn code(&self) -> u16 {
+        ERROR_CODE_BASE + self.using_encoded(|bytes| bytes[0]) as u16
+    }
+}
+
+impl RuntimeError {
+    /// Stable code of the runtime error, `RUNTIME_ERROR_CODE_BASE` plus the index of the
+    /// variant
+    pub fn code(&self) -> u16 {
+        RUNTIME_ERROR_CODE_BASE + self.using_encoded(|bytes| bytes[0]) as u16
+    }
+}
+
+impl ContractError {
+    /// Stable code of the error for front-ends, which map it to a localized message
+    /// without depending on the names of the variants
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Internal(err) => err.code(),
+            Self::Runtime(err) => err.code(),
+            Self::TransferFailed(failure) => TRANSFER_FAILED_CODE_BASE + failure.cause.using_encoded(|bytes| bytes[0]) as u16,
+        }
+    }
 }
 
 // Error conversions for convenience.
@@ -65,6 +371,12 @@ impl From<RuntimeError> for ContractError {
     }
 }
 
+impl From<TransferFailure> for ContractError {
+    fn from(failure: TransferFailure) -> Self {
+        Self::TransferFailed(failure)
+    }
+}
+
 impl From<EnvError> for RuntimeError {
     fn from(e: EnvError) -> Self {
         use ink::env::ReturnErrorCode;
@@ -72,7 +384,12 @@ impl From<EnvError> for RuntimeError {
             EnvError::ReturnError(ReturnErrorCode::CallRuntimeFailed) => {
                 Self::CallRuntimeFailed
             }
-            _ => panic!("Unexpected error from pallet_contracts environment"),
+            EnvError::ReturnError(ReturnErrorCode::TransferFailed) => Self::TransferFailed,
+            EnvError::ReturnError(ReturnErrorCode::CalleeTrapped) => Self::CalleeTrapped,
+            EnvError::ReturnError(ReturnErrorCode::CalleeReverted) => Self::CalleeReverted,
+            EnvError::Decode(_) => Self::DecodeFailed,
+            // Degrade gracefully instead of trapping the whole call
+            _ => Self::Unknown,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,549 @@
+use ink::env::DefaultEnvironment;
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+type Hash = <DefaultEnvironment as ink::env::Environment>::Hash;
+
+/// Version of the event schema.  Bumped whenever an event payload changes so
+/// indexers can decode events of multiple deployed versions side by side.
+pub const EVENT_SCHEMA_VERSION: u16 = 6;
+
+/// First code of the success messages, see `Success::code`
+pub const SUCCESS_CODE_BASE: u16 = 1000;
+
+/// Success messages
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub enum Success {
+    LotterySetup {
+        operator: AccountId,
+        asset_id: u128,
+        starting_block: u32,
+    },
+    LotteryStarted {
+        starting_block: u32,
+    },
+    LotteryStopped {
+        next_starting_block: u32,
+    },
+    DrawAdded {
+        draw_number: u32,
+        bet_amount: u128,
+    },
+    DrawRemoved {
+        draw_number: u32,
+    },
+    DrawOpened {
+        draw_number: u32,
+    },
+    DrawProcessed {
+        draw_number: u32,
+        winning_number: u16,
+    },
+    DrawClosed {
+        draw_number: u32,
+        winning_number: u16,
+        winners: u32,
+        jackpot_paid: u128,
+        rebate_paid: u128,
+    },
+    BetAdded {
+        draw_number: u32,
+        bet_index: u32,
+        bet_number: u16,
+        amount: u128,
+    },
+    JackpotAdded {
+        draw_number: u32,
+        amount: u128,
+        jackpot: u128,
+    },
+    PayoutsRetried {
+        draw_number: u32,
+        paid: u32,
+        failed: u32,
+    },
+    BetTransferred {
+        draw_number: u32,
+        bet_index: u32,
+        from: AccountId,
+        to: AccountId,
+    },
+    EscrowClaimed {
+        account: AccountId,
+        amount: u128,
+    },
+    OracleSet {
+        oracle: Option<AccountId>,
+        max_daily_value: u128,
+    },
+    LotteryCreated {
+        lottery_id: u32,
+        operator: AccountId,
+    },
+    PayoutStrategySet {
+        draw_number: u32,
+        strategy: Option<AccountId>,
+    },
+    VerifiedBetsSet {
+        verified_bets_only: bool,
+    },
+    VaultSet {
+        vault: Option<AccountId>,
+    },
+    NftCollectionSet {
+        collection: Option<u32>,
+    },
+    AssetMetadataSet {
+        decimals: u8,
+        symbol: String,
+    },
+    DrawAffiliatePercentageSet {
+        draw_number: u32,
+        affiliate_percentage: Option<u8>,
+    },
+    ProgressivePercentageSet {
+        percentage: u8,
+    },
+    ProgressiveJackpotWon {
+        draw_number: u32,
+        bonus_number: u16,
+        winners: u32,
+        amount: u128,
+    },
+    DivisionSharesSet {
+        draw_number: u32,
+        first: u8,
+        second: u8,
+        third: u8,
+    },
+    BulkDiscountSet {
+        min_tickets: u32,
+        percentage: u8,
+    },
+    StreakBonusSet {
+        threshold: u32,
+        percentage: u8,
+    },
+    StreakBonusPaid {
+        account: AccountId,
+        streak: u32,
+        amount: u128,
+    },
+    LiabilityCapSet {
+        draw_number: u32,
+        liability_cap: u128,
+    },
+    InsurancePercentageSet {
+        percentage: u8,
+    },
+    InsuranceToppedUp {
+        amount: u128,
+        balance: u128,
+    },
+    InsuranceWithdrawn {
+        recipient: AccountId,
+        amount: u128,
+        balance: u128,
+    },
+    InsuranceCovered {
+        draw_number: u32,
+        amount: u128,
+        balance: u128,
+    },
+    RebateToppedUp {
+        draw_number: u32,
+        amount: u128,
+        rebate: u128,
+    },
+    RebateTiersSet {
+        tiers: u8,
+    },
+    BetRangeSet {
+        draw_number: u32,
+        min_bet: u128,
+        max_bet: u128,
+    },
+    EarlyBirdSet {
+        draw_number: u32,
+        bets: u32,
+        percentage: u8,
+    },
+    BundleBought {
+        bundle_id: u64,
+        draws: u32,
+        tickets: u32,
+        price: u128,
+    },
+    VoucherCreated {
+        code_hash: [u8; 32],
+        value: u128,
+    },
+    VoucherRedeemed {
+        code_hash: [u8; 32],
+        account: AccountId,
+        value: u128,
+    },
+    ReferralBonusSet {
+        percentage: u8,
+    },
+    ReferralBonusPaid {
+        referee: AccountId,
+        referrer: AccountId,
+        amount: u128,
+    },
+    ImportCompleted {
+        lotteries: u32,
+    },
+    PayoutPoliciesSet {
+        policies: u32,
+        auditor: Option<AccountId>,
+    },
+    PayoutHeld {
+        draw_number: u32,
+        recipient: AccountId,
+        role: PayoutRole,
+        amount: u128,
+        required_approvals: u8,
+        release_block: u32,
+    },
+    PayoutsApproved {
+        draw_number: u32,
+        approver: AccountId,
+        payouts: u32,
+    },
+    PayoutsReleased {
+        draw_number: u32,
+        payouts: u32,
+        amount: u128,
+    },
+    BetCutoffSet {
+        draw_number: u32,
+        cutoff_blocks: u32,
+    },
+    DrawRolledOver {
+        draw_number: u32,
+        next_opening_block: u32,
+    },
+    MinParticipationSet {
+        draw_number: u32,
+        min_bets: u32,
+        min_pot: u128,
+        carry_forward: bool,
+    },
+    StakesRefunded {
+        draw_number: u32,
+        bets: u32,
+        amount: u128,
+        carried: u32,
+    },
+    CarryForwardSet {
+        account: AccountId,
+        carry_forward: Option<bool>,
+    },
+    BetsCarriedForward {
+        draw_number: u32,
+        placed: u32,
+        refunded: u128,
+    },
+    DrawDelegateSet {
+        draw_number: u32,
+        delegate: Option<AccountId>,
+    },
+    ResultCommitted {
+        draw_number: u32,
+        commitment: [u8; 32],
+    },
+    ResultPublished {
+        draw_number: u32,
+        winning_number: u16,
+        bonus_number: u16,
+    },
+    DrawSettled {
+        draw_number: u32,
+        winners: u32,
+        jackpot_paid: u128,
+        rebate_paid: u128,
+        dust: u128,
+        failed_payouts: u32,
+    },
+    StoragePruned {
+        winners: u32,
+        bytes: u32,
+    },
+    CompactBetsSet {
+        draw_number: u32,
+        compact_bets: bool,
+    },
+    ClaimPayoutsSet {
+        claim_payouts: bool,
+    },
+    ClaimsRecorded {
+        draw_number: u32,
+        claims: u32,
+        amount: u128,
+    },
+    PrizeClaimed {
+        account: AccountId,
+        amount: u128,
+    },
+    RebateClaimed {
+        account: AccountId,
+        amount: u128,
+    },
+    SaltCommitted {
+        draw_number: u32,
+        commitment: [u8; 32],
+    },
+    SaltRevealed {
+        draw_number: u32,
+        salt: [u8; 32],
+    },
+    PrizeTiersSet {
+        draw_number: u32,
+        tiers: u8,
+    },
+    SharesSet {
+        jackpot: u8,
+        dev: u8,
+        rebate: u8,
+        operator: u8,
+        winner: u8,
+    },
+    CompanionSet {
+        companion: Option<AccountId>,
+    },
+    DepositBudgetSet {
+        byte_deposit: u128,
+        // No limit once the budget is removed
+        limit: Option<u128>,
+    },
+    VoucherClaimed {
+        account: AccountId,
+        claim: [u8; 32],
+    },
+    SchedulingFailed {
+        draw_number: u32,
+        when: u32,
+        error: RuntimeError,
+    },
+}
+
+impl Success {
+    /// Stable code of the success message for front-ends, `SUCCESS_CODE_BASE` plus the
+    /// index of the variant.  Variants are only ever appended, so a code never changes
+    /// meaning.
+    pub fn code(&self) -> u16 {
+        SUCCESS_CODE_BASE + scale::Encode::using_encoded(self, |bytes| bytes[0]) as u16
+    }
+}
+
+/// Emit messages
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum LotteryStatus {
+    EmitSuccess(Success),
+}
+
+/// Payout role
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub enum PayoutRole {
+    // Bettor that matched the winning number
+    Winner,
+    // Actively betting upline of a winner
+    Upline,
+    // Operator receiving the share of an inactive upline
+    Operator,
+    // Bettor receiving a share of the rebate
+    Rebate,
+    // Operator's share of a bet
+    OperatorShare,
+    // Developer's share of a bet
+    DevShare,
+    // Affiliate share of a bet paid to the upline (or the operator)
+    Affiliate,
+    // Jackpot share of a bet deposited in the prize vault
+    Vault,
+    // Bettor that matched the bonus number, paid from the progressive jackpot
+    Progressive,
+    // Winner of a fixed-odds draw, paid by the house
+    House,
+    // Bettor reaching a streak threshold, paid from the operator share
+    StreakBonus,
+    // Withdrawal of the insurance fund
+    Insurance,
+    // One of the first bets of a draw, paid from the early-bird bonus
+    EarlyBird,
+    // Referee or referrer paid the one-time referral bonus, from the operator share
+    ReferralBonus,
+    // Owner of a bet refunded because the draw missed its minimum participation
+    Refund,
+}
+
+// Signature topics are pinned to the hash of `Name(FieldType,...)` so external
+// contracts and indexers can match events by a stable signature.  Recompute the
+// topic whenever the fields of an event change.
+
+/// Contract event emitter
+#[ink::event(signature_topic = "c7dc6692646fec2554f2a1bf209d0024a8e9afdd3f01ed1b0ea61b03635bc903")]
+pub struct LotteryEvent {
+    #[ink(topic)]
+    pub operator: AccountId,
+    // Lottery the event refers to
+    #[ink(topic)]
+    pub lottery_id: u32,
+    // Account that called the message
+    pub caller: AccountId,
+    // Draw the event refers to, if any
+    pub draw_number: Option<u32>,
+    // Block the event was emitted in
+    pub block_number: u32,
+    // Timestamp of the block the event was emitted in
+    pub block_timestamp: u64,
+    // Code of the status, see `Success::code`
+    pub code: u16,
+    pub status: LotteryStatus,
+}
+
+/// Contract deployed event
+///
+/// Emitted once on instantiation with the event schema version of the contract.
+#[ink::event(signature_topic = "82eb2415980e7bd6e7e25d8601c2b89243f143e65dde965454930ec78df848e3")]
+pub struct ContractDeployed {
+    #[ink(topic)]
+    pub dev: AccountId,
+    pub schema_version: u16,
+    pub block_number: u32,
+    pub block_timestamp: u64,
+}
+
+/// Next draw scheduled event
+///
+/// Emitted whenever a draw is scheduled for an upcoming cycle, either when it is
+/// added or when the lottery rolls over to the next cycle.
+#[ink::event(signature_topic = "0361e4d1f36542611ed748d479a78859fbf5431b7a32187225d197d6e0ebc480")]
+pub struct NextDrawScheduled {
+    #[ink(topic)]
+    pub draw_number: u32,
+    pub lottery_id: u32,
+    // Block from which the draw can be opened
+    pub opening_block: u32,
+    // Jackpot carried into the draw
+    pub pot_seed: u128,
+}
+
+/// Draw opened event
+///
+/// Emitted when a draw starts accepting bets.  The draw number is indexed so
+/// off-chain services can follow a single draw.
+#[ink::event(signature_topic = "6bd921a8d0a1839478bbd9191bd625a9a1f0866017c81b51acf247798d018c30")]
+pub struct DrawOpened {
+    #[ink(topic)]
+    pub draw_number: u32,
+    pub lottery_id: u32,
+    pub caller: AccountId,
+    pub block_number: u32,
+    pub block_timestamp: u64,
+}
+
+/// Draw removed event
+///
+/// Emitted when a draw is removed from the lottery.
+#[ink::event(signature_topic = "f83ac2b19aae22dbd91be437657b20689e77fbb1f415e6b1f4072e06aa4d314a")]
+pub struct DrawRemoved {
+    #[ink(topic)]
+    pub draw_number: u32,
+    pub lottery_id: u32,
+    pub caller: AccountId,
+    pub block_number: u32,
+    pub block_timestamp: u64,
+}
+
+/// Bet placed event
+///
+/// Emitted for every recorded bet.  The bettor is indexed so wallets can subscribe
+/// to their own bets.
+#[ink::event(signature_topic = "81fb7ced4b8275962f0ea9c6ee692ab8b66e1305239b2b85b40c094352f294a5")]
+pub struct BetPlaced {
+    #[ink(topic)]
+    pub bettor: AccountId,
+    pub lottery_id: u32,
+    pub draw_number: u32,
+    pub bet_number: u16,
+    pub amount: u128,
+    pub tx_hash: Vec<u8>,
+}
+
+/// Winner paid event
+///
+/// Emitted for every successful prize or rebate transfer during settlement.
+#[ink::event(signature_topic = "fd1cf12eb459371a970bafbe8b0db215ecc04cf334fb573fb471cb9052ee149c")]
+pub struct WinnerPaid {
+    #[ink(topic)]
+    pub recipient: AccountId,
+    pub lottery_id: u32,
+    pub draw_number: u32,
+    pub role: PayoutRole,
+    pub amount: u128,
+}
+
+/// Payout failed event
+///
+/// Emitted when an asset transfer (bet share, prize or rebate) could not be dispatched.
+#[ink::event(signature_topic = "0de41dbf8ad5046795778e869a68cdd5babca2b4be4ba034e8c5bf2b0e8951b1")]
+pub struct PayoutFailed {
+    #[ink(topic)]
+    pub recipient: AccountId,
+    pub lottery_id: u32,
+    pub draw_number: u32,
+    pub role: PayoutRole,
+    pub amount: u128,
+    pub error: RuntimeError,
+}
+
+/// Upgrade announced event
+///
+/// Emitted when the dev announces a new code hash.  The upgrade can be executed
+/// from the given block on.
+#[ink::event(signature_topic = "4d964e2b2034cfb6ec1052cd506da575a45af88d911347929f2351a9735aea71")]
+pub struct UpgradeAnnounced {
+    #[ink(topic)]
+    pub code_hash: Hash,
+    pub executable_block: u32,
+}
+
+/// Upgrade cancelled event
+///
+/// Emitted when the dev withdraws an announced upgrade.
+#[ink::event(signature_topic = "798d47fe6d5ab8a415d54db16d7aa2d667e2f97e3b3b834773637541640e70ea")]
+pub struct UpgradeCancelled {
+    #[ink(topic)]
+    pub code_hash: Hash,
+}
+
+/// Upgraded event
+///
+/// Emitted when the code of the contract is replaced.  The event schema version is
+/// the one of the contract before the upgrade.
+#[ink::event(signature_topic = "28bd2e6cb3f8c7d9802f949abf0099890ac6c34f8b425fcb39e3d278f85fa2d7")]
+pub struct Upgraded {
+    #[ink(topic)]
+    pub code_hash: Hash,
+    pub schema_version: u16,
+    pub block_number: u32,
+}
+
+/// Referrer registered event
+///
+/// Emitted when an account registers its referrer in the affiliate registry.
+#[ink::event(signature_topic = "0496cd2b1dcd6c4d96695ee884ef2332ff0a89f1f340316cf57291b4c92dfd80")]
+pub struct ReferrerRegistered {
+    #[ink(topic)]
+    pub account: AccountId,
+    #[ink(topic)]
+    pub referrer: AccountId,
+}
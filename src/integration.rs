@@ -0,0 +1,54 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Integrating contract interface.
+///
+/// Games and aggregators bet on behalf of their users with `place_bet_for`: the
+/// integrating contract pays the stake and owns the bet, its user is the bettor of the
+/// bet.  When the bet wins the prize is paid to the integrating contract, which is then
+/// notified with `on_win` to credit the prize to its user.  The prize is also reported
+/// by the `WinnerPaid` event with the integrating contract as recipient.
+#[ink::trait_definition]
+pub trait LotteryIntegrator {
+    /// Called after the prize of a winning bet owned by the integrating contract has been
+    /// paid to it.
+    #[ink(message)]
+    fn on_win(&mut self,
+        lottery_id: u32,
+        draw_number: u32,
+        beneficiary: AccountId,
+        bet_number: u16,
+        prize: u128);
+}
+
+/// Notifies the integrating contract of a win (`LotteryIntegrator::on_win`).
+pub fn on_win(integrator: AccountId,
+              lottery_id: u32,
+              draw_number: u32,
+              beneficiary: AccountId,
+              bet_number: u16,
+              prize: u128) -> Result<(), RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(integrator)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("LotteryIntegrator::on_win")))
+                .push_arg(lottery_id)
+                .push_arg(draw_number)
+                .push_arg(beneficiary)
+                .push_arg(bet_number)
+                .push_arg(prize),
+        )
+        .returns::<()>()
+        .try_invoke();
+
+    match result {
+        Ok(Ok(())) => Ok(()),
+        // The integrating contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
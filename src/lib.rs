@@ -8,51 +8,92 @@ mod tests;
 #[cfg(all(test, feature = "e2e-tests"))]
 mod e2e_tests;
 
+/// Sandbox test with pallet_assets
+#[cfg(all(test, feature = "sandbox-tests"))]
+mod sandbox_tests;
+
 /// pallet_assets runtime calls
 pub mod assets;
 
 /// Errors
 pub mod errors;
 
+/// Events
+pub mod events;
+
+/// PSP22 cross-contract calls
+pub mod psp22;
+
+/// XCM types for cross-chain transfers
+pub mod xcm;
+
+/// Price oracle cross-contract calls
+pub mod oracle;
+
+/// Payout strategy cross-contract calls
+pub mod payout_strategy;
+
+/// Randomness provider cross-contract calls
+pub mod randomness;
+
+/// Prize vault cross-contract calls
+pub mod vault;
+
+/// Settlement companion cross-contract calls
+pub mod companion;
+
+/// Integrating contract cross-contract calls
+pub mod integration;
+
+/// Runtime call dispatcher
+pub mod runtime;
+
+/// Chain extensions for pallet_assets queries and runtime randomness
+#[cfg(any(feature = "assets-extension", feature = "randomness-extension"))]
+pub mod chain_extension;
+
 #[ink::contract]
 mod lottery {
     use ink::env::hash;
     use ink::prelude::vec::Vec;
+    use ink::prelude::boxed::Box;
+    use ink::prelude::collections::BTreeMap;
+    use ink::prelude::format;
+    use ink::prelude::string::String;
+    use ink::storage::{Lazy, Mapping};
+    use scale::Encode;
+    use sp_runtime::{helpers_128bit, Rounding};
 
-    use crate::errors::{Error, RuntimeError, ContractError};
-    use crate::assets::{AssetsCall, RuntimeCall};
-
-    /// Success messages
-    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum Success {
-        LotterySetup,
-        LotteryStarted,
-        LotteryStopped,
-        DrawAdded,
-        DrawRemoved,
+    use crate::errors::{Error, RuntimeError, ContractError, TransferFailure};
+    use crate::assets::{AssetsCall, BalancesCall, ContractsCall, NftsCall, PolkadotXcmCall, RuntimeCall,
+        SchedulerCall, UtilityCall, Weight, ASSETS_PALLET_INSTANCE};
+    use crate::xcm::{self, WeightLimit};
+    use crate::psp22;
+    use crate::oracle;
+    use crate::payout_strategy;
+    use crate::randomness;
+    use crate::vault;
+    use crate::companion;
+    use crate::integration;
+    use crate::runtime;
+    use crate::events::{
+        EVENT_SCHEMA_VERSION,
+        Success,
+        LotteryStatus,
+        LotteryEvent,
+        ContractDeployed,
+        NextDrawScheduled,
         DrawOpened,
-        DrawProcessed,
-        DrawClosed,
-        BetAdded,
-        JackpotAdded,
-    }
-    
-    /// Emit messages
-    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum LotteryStatus {
-        EmitSuccess(Success),
-        EmitError(Error),
-    }
-
-    /// Contract event emitter
-    #[ink(event)]
-    pub struct LotteryEvent {
-        #[ink(topic)]
-        operator: AccountId,
-        status: LotteryStatus,
-    } 
+        DrawRemoved,
+        BetPlaced,
+        PayoutRole,
+        WinnerPaid,
+        PayoutFailed,
+        UpgradeAnnounced,
+        UpgradeCancelled,
+        Upgraded,
+        ReferrerRegistered,
+    };
 
     /// Draw status
     #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
@@ -69,6 +110,48 @@ mod lottery {
         }
     }
 
+    /// Payment backend used for the asset transfers of the lottery
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum PaymentBackend {
+        // pallet_assets through runtime calls, using the setup asset id
+        Assets,
+        // PSP22 token contract through cross-contract calls
+        Psp22(AccountId),
+        // Native token of the chain through the balances pallet, the asset id is ignored
+        Native,
+        // Nothing is transferred, the transfers are recorded in the ledger of the contract,
+        // e.g., on development chains without the Assets pallet or for dry-runs
+        Simulated,
+    }
+
+    /// Source of the randomness of the winning numbers
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum RandomnessSource {
+        // Hash of the block timestamp and the salt, predictable by the block author
+        Internal,
+        // Randomness of the runtime through the chain extension, e.g., the collective flip
+        // of the recent block hashes
+        Runtime,
+        // Randomness oracle contract
+        Oracle(AccountId),
+        // Verifiable random function provider contract
+        Vrf(AccountId),
+        // Winning number committed by the operator before processing and published a
+        // few blocks after, see `commit_result`
+        CommitReveal,
+        // Epoch randomness of the runtime through the chain extension, e.g., BABE.  Only
+        // randomness that became known after the bets closed is used, the draw is
+        // processed once the next epoch started.
+        Epoch,
+        // Salt committed by the operator when opening the draw, revealed in the processing
+        // block and mixed with the runtime randomness of that block, see
+        // `open_draw_with_commitment`.  Without the randomness extension the timestamp of
+        // the block is mixed instead, which the operator can predict.
+        SaltReveal,
+    }
+
     /// Lottery Setup 
     #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
@@ -83,6 +166,21 @@ mod lottery {
         pub dev: AccountId,
         // Asset id of the token, e.g., USDT
         pub asset_id: u128,
+        // Backend used to transfer the asset
+        pub payment_backend: PaymentBackend,
+        // Only accept bets whose stake is collected by the contract (`place_bet`)
+        pub verified_bets_only: bool,
+        // Source of the randomness of the winning numbers
+        pub randomness_source: RandomnessSource,
+        // Vault contract holding the jackpots, `None` keeps them in the lottery contract
+        pub vault: Option<AccountId>,
+        // pallet_nfts collection of the commemorative winner NFTs, issued by the contract
+        pub nft_collection: Option<u32>,
+        // Decimals and symbol of the asset, bet amounts are validated against the decimals
+        pub asset_metadata: Option<AssetMetadata>,
+        // Percentage of every bet paid to the referrer of the bettor, taken from the
+        // share of the operator
+        pub affiliate_percentage: u8,
         // Used for off-chain lottery job:
         // Once this block has been reached the job will start the lottery at the same time
         // calculate the next starting block based on the daily (cycle) total blocks.
@@ -100,6 +198,87 @@ mod lottery {
         pub maximum_bets: u16,
         // Starts and stops the lottery
         pub is_started: bool,
+        // Price oracle contract used for fiat-denominated bet limits
+        pub oracle: Option<AccountId>,
+        // Fiat value of the bets allowed per bettor per day, zero for no limit
+        pub max_daily_value: u128,
+        // Tickets bought in one call from which the bulk discount applies, zero disables
+        // the discount
+        pub bulk_discount_tickets: u32,
+        // Discount of every ticket of a bulk purchase, taken from the operator share
+        pub bulk_discount_percentage: u8,
+        // Consecutive cycles with a bet that earn a streak bonus, zero disables the bonus
+        pub streak_threshold: u32,
+        // Streak bonus as a percentage of the bet amount, taken from the operator share
+        pub streak_bonus_percentage: u8,
+        // Weights of the rebate by bet volume, ascending by bets, empty for a flat split
+        pub rebate_tiers: Vec<RebateTier>,
+        // One-time bonus paid to a referee and its referrer with the first bet of the
+        // referee, as a percentage of the stake taken from the operator share
+        pub referral_bonus_percentage: u8,
+        // Approvals and delays of the prizes and withdrawals by amount band, ascending by
+        // amount, empty pays everything immediately
+        pub payout_policies: Vec<PayoutPolicy>,
+        // Second approver of the held payouts besides the dev
+        pub auditor: Option<AccountId>,
+        // Record the prizes and rebates of a settlement for their recipients to claim
+        // instead of transferring them, see `set_claim_payouts`
+        pub claim_payouts: bool,
+        // Revenue split of the stakes and the prizes, see `set_shares`
+        pub shares: Shares,
+    }
+
+    /// Rebate weight of the bets of an account placing at least `min_bets` bets in a
+    /// draw, e.g., 5 bets with a weight of 2 earn twice the rebate per bet.  Accounts
+    /// below the first tier have a weight of 1.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct RebateTier {
+        pub min_bets: u32,
+        pub weight: u8,
+    }
+
+    /// Metadata of the asset of the lottery
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct AssetMetadata {
+        pub decimals: u8,
+        pub symbol: String,
+    }
+
+    /// Consecutive cycles of a lottery in which an account placed at least one bet
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Streak {
+        // Starting block of the last cycle with a bet
+        pub last_cycle: u32,
+        // Consecutive cycles with a bet up to the last cycle
+        pub length: u32,
+        // Longest streak of the account
+        pub best: u32,
+    }
+
+    /// Free-bet credits of a redemption code
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Voucher {
+        // Lottery whose bets the credits pay for
+        pub lottery_id: u32,
+        // Credits of the code, deposited by the operator
+        pub value: u128,
+        // Account that redeemed the code, if any
+        pub redeemed_by: Option<AccountId>,
+    }
+
+    /// Pending claim of a voucher code, see `claim_voucher`
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct VoucherClaim {
+        // Hash of the code and the claimant
+        pub claim: [u8; 32],
+        // Block of the claim, the code can be redeemed in the `VOUCHER_CLAIM_BLOCKS`
+        // blocks after it
+        pub block_number: u32,
     }
 
     /// Bet
@@ -107,9 +286,27 @@ mod lottery {
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
     pub struct Bet {
         pub bettor: AccountId,
+        // Current beneficial owner of the bet, paid on settlement
+        pub owner: AccountId,
         pub upline: AccountId,
         pub bet_number: u16,
         pub tx_hash: Vec<u8>,
+        // Origin chain and account of bets delivered via XCM
+        pub origin: Option<XcmOrigin>,
+        // Stake of the bet before any discount, the shares are computed from it
+        pub stake: u128,
+        // Bundle the bet was bought with, if any
+        pub bundle_id: Option<u64>,
+    }
+
+    /// Origin of a bet submitted from a sibling parachain
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct XcmOrigin {
+        // Parachain id of the origin chain
+        pub para_id: u32,
+        // Account of the bettor at the origin chain
+        pub account: AccountId,
     }
 
     /// Winner
@@ -118,11 +315,242 @@ mod lottery {
     pub struct Winner {
         pub draw_number: u32,
         pub bettor: AccountId,
+        pub owner: AccountId,
+        // Foreign location the prize is delivered to via XCM
+        pub destination: Option<XcmOrigin>,
         pub upline: AccountId,
         pub bet_number: u16,
         pub tx_hash: Vec<u8>,
+        // Stake of the winning bet
+        pub stake: u128,
         pub bettor_share: u128,
         pub upline_share: u128,
+        // Division of the winner (0 for the exact match) or index of its prize tier in a
+        // draw with prize tiers
+        pub tier: u8,
+    }
+
+    /// Settlement model of a draw
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum SettlementMode {
+        // The winners share the jackpot of the draw
+        PariMutuel,
+        // Every winning bet is paid `multiplier` times the bet amount, the jackpot share
+        // of the bets is kept by the house which pays what the jackpot does not cover
+        FixedOdds { multiplier: u32 },
+    }
+
+    impl Default for SettlementMode {
+        fn default() -> Self {
+            Self::PariMutuel
+        }
+    }
+
+    /// Template of the draws of a daily cycle
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct DrawTemplate {
+        // Draws evenly spread over the cycle
+        pub draws: u8,
+        // Share of the slot of a draw in which it accepts bets, the rest of the slot is
+        // split evenly between processing and the next draw
+        pub betting_percentage: u8,
+        pub bet_amount: u128,
+        pub settlement_mode: SettlementMode,
+    }
+
+    /// Percentages of the jackpot allocated to the prize divisions of a draw.  What a
+    /// division without winners does not pay stays in the jackpot.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct DivisionShares {
+        // Bets matching the winning number exactly
+        pub first: u8,
+        // Bets one number above or below the winning number
+        pub second: u8,
+        // Bets matching the last two digits of the winning number
+        pub third: u8,
+    }
+
+    impl Default for DivisionShares {
+        fn default() -> Self {
+            Self {
+                first: 100,
+                second: 0,
+                third: 0,
+            }
+        }
+    }
+
+    /// Prize tier of a draw, the bets matching the last digits of the winning number
+    /// share a percentage of the jackpot
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PrizeTier {
+        // Trailing digits matched, `MAX_TIER_DIGITS` for an exact match
+        pub digits: u8,
+        pub percentage: u8,
+    }
+
+    /// Revenue split of a lottery.  The percentages of every stake sum up to 100%, the
+    /// affiliate percentage is taken from the operator share.  It has no field here as
+    /// draws can override it, it stays the `affiliate_percentage` of the setup.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Shares {
+        // Percentages of every stake
+        pub jackpot: u8,
+        pub dev: u8,
+        pub rebate: u8,
+        pub operator: u8,
+        // Percentage of a prize paid to the winner, the rest is paid to its upline
+        pub winner: u8,
+    }
+
+    impl Default for Shares {
+        fn default() -> Self {
+            Self {
+                jackpot: 50,
+                dev: 10,
+                rebate: 10,
+                operator: 30,
+                winner: 90,
+            }
+        }
+    }
+
+    /// Planned transfer of a settlement
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Payout {
+        pub recipient: AccountId,
+        pub role: PayoutRole,
+        pub amount: u128,
+        // Foreign location of the recipient, delivered via XCM
+        pub destination: Option<XcmOrigin>,
+    }
+
+    /// Approvals and delay of the payouts of an amount band, the band ends at the
+    /// `min_amount` of the next policy
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PayoutPolicy {
+        pub min_amount: u128,
+        // Approvals of the dev or the auditor required, at most `MAX_PAYOUT_APPROVALS`
+        pub approvals: u8,
+        // Blocks the payout waits before it can be released
+        pub delay_blocks: u32,
+    }
+
+    /// Prize or withdrawal waiting for the approvals and the delay of its payout policy
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct HeldPayout {
+        // Draw of the prize, zero for a withdrawal
+        pub draw_number: u32,
+        pub payout: Payout,
+        // Approvers that approved the payout so far
+        pub approvals: Vec<AccountId>,
+        pub required_approvals: u8,
+        // Block from which the payout can be released
+        pub release_block: u32,
+    }
+
+    /// Prize kept by the contract after a failed XCM delivery
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Escrow {
+        pub draw_number: u32,
+        // Local account that can claim the prize
+        pub account: AccountId,
+        pub amount: u128,
+    }
+
+    /// Prizes and rebates of an account waiting to be claimed, over all draws of a
+    /// lottery
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Claim {
+        pub prizes: u128,
+        pub rebates: u128,
+    }
+
+    /// Settlement of a draw as computed before closing
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SettlementPlan {
+        pub draw_number: u32,
+        pub winning_number: u16,
+        // Winners with their jackpot and upline shares, per division
+        pub winners: Vec<Winner>,
+        pub second_winners: Vec<Winner>,
+        pub third_winners: Vec<Winner>,
+        // Transfers in the order they are dispatched
+        pub payouts: Vec<Payout>,
+        pub jackpot_paid: u128,
+        pub rebate_paid: u128,
+        // Bonus number winners paid from the progressive jackpot
+        pub progressive_winners: Vec<AccountId>,
+        pub progressive_paid: u128,
+        // Prizes of a fixed-odds draw the jackpot does not cover, paid by the house
+        pub house_paid: u128,
+        // Early-bird bonus pool shared by the first bets
+        pub early_bird_paid: u128,
+    }
+
+    /// Transition of a draw as computed before processing
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct ProcessPreview {
+        pub draw_number: u32,
+        // Status of the draw once processed
+        pub status: DrawStatus,
+        // Success messages in the order they are emitted
+        pub events: Vec<Success>,
+    }
+
+    /// Bet checked by `validate_bet` with its shares, stored by `record_effects`
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct BetTerms {
+        // Draw of the bet as read by the checks
+        draw: Draw,
+        bet: Bet,
+        jackpot_share: u128,
+        dev_share: u128,
+        rebate_share: u128,
+        // Slices of the jackpot share
+        progressive_share: u128,
+        insurance_share: u128,
+        affiliate_share: u128,
+        // Operator share less the affiliate share and the discount, before the bonuses
+        operator_share: u128,
+        // Day and daily stake of the bettor if the lottery has a daily limit
+        daily_stake: Option<(u32, u128)>,
+    }
+
+    /// Transition of `process_draw` for the current cycle of a draw
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum ProcessOutcome {
+        // No bets, the draw rolls over to the next cycle
+        RollOver,
+        // The stakes of the cycle are refunded instead of drawing
+        Refund,
+        // The result committed by the operator is published later
+        Commit,
+        // The winning and the bonus number are drawn
+        Draw,
+    }
+
+    impl SettlementPlan {
+        /// Winners of all divisions
+        pub fn all_winners(&self) -> Vec<Winner> {
+            self.winners.iter()
+                .chain(self.second_winners.iter())
+                .chain(self.third_winners.iter())
+                .cloned()
+                .collect()
+        }
     }
 
     /// Draw meta data 
@@ -143,67 +571,659 @@ mod lottery {
         // blocks determines the time window during which the draw processed the
         // winners.
         pub closing_blocks: u32,
-        // Fixed amount for all bet in the draw.
+        // Fixed amount for all bet in the draw, the minimum stake if the draw has a
+        // stake range.
         pub bet_amount: u128,
+        // Maximum stake of a bet, zero if every bet stakes the bet amount
+        pub max_bet_amount: u128,
         // Total accumulated jackpot 
         pub jackpot: u128,
         // Total accumulated rebate. 10% of the jackpot share will go to the rebate
         pub rebate: u128,
-        // Bets
-        pub bets: Vec<Bet>,
+        // Bets of the current cycle, kept in `Lottery::bets` or per number in
+        // `Lottery::number_bets` if the draw has compact bets
+        pub bet_count: u32,
         // Winning number will be generated during the processed period of the draw.
         pub winning_number: u16,
+        // Bonus number generated with the winning number, bets matching it win the
+        // progressive jackpot
+        pub bonus_number: u16,
         // Winners are bets that matches the winning number.
         pub winners: Vec<Winner>,
+        // Winners of the second division, one number above or below the winning number
+        pub second_winners: Vec<Winner>,
+        // Winners of the third division, the last two digits of the winning number
+        pub third_winners: Vec<Winner>,
+        // Percentages of the jackpot allocated to each division
+        pub division_shares: DivisionShares,
+        // Settlement model of the draw
+        pub settlement_mode: SettlementMode,
+        // Largest prize owed on one number of a fixed-odds draw, zero only limits it to
+        // the prize reserve (the jackpot)
+        pub liability_cap: u128,
+        // Giveaway draw whose jackpot is seeded by the operator, the bet amount is the
+        // fee of a bet and may be zero
+        pub promotional: bool,
+        // Total staked and fees of the bets of the current cycle
+        pub handle: u128,
+        pub fees: u128,
+        // House edge of the last settlement of the draw
+        pub house_edge: HouseEdge,
         // Status of the draw, e.g., Open, Process, Close
         pub status: DrawStatus,
         // True (accepts bets otherwise bets are denied)
         pub is_open: bool,
+        // Settlement transfers that failed and are waiting to be retried
+        pub failed_payouts: Vec<TransferFailure>,
+        // Contract computing the shares of the winners, `None` uses the default split
+        pub payout_strategy: Option<AccountId>,
+        // Affiliate percentage of the draw, `None` uses the one of the lottery setup
+        pub affiliate_percentage: Option<u8>,
+        // First bets of the draw sharing the early-bird bonus, zero disables the bonus
+        pub early_bird_bets: u32,
+        // Percentage of the stake of the first bets sent to the early-bird bonus, taken
+        // from the operator share
+        pub early_bird_percentage: u8,
+        // Early-bird bonus accumulated, shared by the first bets on settlement
+        pub early_bird_pool: u128,
+        // Funds received and paid by the draw over all its cycles
+        pub reconciliation: Reconciliation,
+        // Blocks before the processing block in which no bets are accepted, zero accepts
+        // bets until the draw is processed
+        pub cutoff_blocks: u32,
+        // Minimum participation of a cycle and the shares withheld until it is met
+        pub participation: Participation,
+        // Account managing the draw besides the operator, see `set_draw_delegate`
+        pub delegate: Option<AccountId>,
+        // Commitment to the winning number of the cycle of a commit-reveal lottery
+        pub result: Option<ResultCommitment>,
+        // Keep the bets of the cycle per number, see `set_compact_bets`
+        pub compact_bets: bool,
+        // Numbers with compact bets in the current cycle
+        pub bet_numbers: Vec<u16>,
+        // Commitment to the salt of the cycle of a salt-reveal lottery
+        pub committed_salt: Option<SaltCommitment>,
+        // Prize tiers paid instead of the divisions, see `set_prize_tiers`
+        pub prize_tiers: Vec<PrizeTier>,
+    }
+
+    /// Commitment to the winning number of a draw, the Keccak256 hash of the SCALE
+    /// encoded `(number, salt)`
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct ResultCommitment {
+        pub commitment: [u8; 32],
+        // Block from which the result can be published, set when processing
+        pub reveal_block: u32,
+        // Salt of the published result
+        pub salt: Option<[u8; 32]>,
+    }
+
+    /// Commitment to the salt of a draw cycle of a salt-reveal lottery, the Keccak256
+    /// hash of the SCALE encoded salt
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct SaltCommitment {
+        pub commitment: [u8; 32],
+        // Only block in which the salt can be revealed, i.e., the processing block of the
+        // cycle, fixed when opening
+        pub reveal_block: u32,
+        // Salt revealed when processing
+        pub salt: Option<[u8; 32]>,
+        // Randomness of the reveal block mixed with the salt
+        pub entropy: [u8; 32],
+        // Salt of the contract when processing, the bonus number is drawn with the next
+        pub nonce: u64,
+    }
+
+    /// Minimum participation of a draw.  The shares of the bets of a cycle below the
+    /// minimum are withheld, if it is not met by the processing block all stakes are
+    /// refunded instead of drawing.
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Participation {
+        // Bets and total stakes of a cycle required to draw, zero for no minimum
+        pub min_bets: u32,
+        pub min_pot: u128,
+        // Transfers of the shares withheld until the minimum is met
+        pub withheld: Vec<Payout>,
+        // Progressive and insurance slices of the withheld bets
+        pub progressive: u128,
+        pub insurance: u128,
+        // Jackpot and statement of the draw before the first bet of the cycle
+        pub jackpot: u128,
+        pub reconciliation: Reconciliation,
+        // Carry the bets of a refunded cycle into the next cycle of the draw instead of
+        // refunding them, unless the owner of a bet chose otherwise
+        pub carry_forward: bool,
+        // Bets carried into the next cycle, their stake is held by the contract
+        pub carried_bets: Vec<Bet>,
     }    
 
+    /// Statement of the funds of a draw over all its cycles, every stake received is
+    /// either paid as fees, reserved or left as dust
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Reconciliation {
+        // Stakes received from the bettors, net of the discounts
+        pub stake_received: u128,
+        // Operator, dev and affiliate shares, including the bonuses paid from them
+        pub fees_paid: u128,
+        // Jackpot shares, including the progressive, insurance and early-bird slices
+        pub jackpot_reserved: u128,
+        pub rebate_reserved: u128,
+        // Prizes transferred at settlement or on retry
+        pub prizes_paid: u128,
+        // Rounding remainders of the shares and of the rebate and early-bird splits
+        pub dust: u128,
+        // Failed settlement transfers waiting to be retried and prizes held by their
+        // payout policy
+        pub pending_payouts: u128,
+        // Stakes refunded by cycles below the minimum participation, not part of the
+        // stakes received
+        pub refunded: u128,
+    }
+
+    /// Version of the storage layout.  Bumped whenever the layout of `Lottery` changes.
+    pub const STORAGE_VERSION: u16 = 5;
+
+    /// Version of the crate the contract was built from
+    pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+    /// Git hash of the build, embedded by `build.rs`
+    pub const GIT_HASH: &str = match option_env!("LOTTERY_GIT_HASH") {
+        Some(hash) => hash,
+        None => "unknown",
+    };
+
+    /// Smallest number that can be bet on or drawn
+    pub const MIN_BET_NUMBER: u16 = 1;
+
+    /// Largest number that can be bet on or drawn (3-digit draw format)
+    pub const MAX_BET_NUMBER: u16 = 999;
+
+    /// Digits of the draw format, matched by a bet on the winning number
+    pub const MAX_TIER_DIGITS: u8 = 3;
+
+    /// Affiliate percentage of a new lottery
+    pub const DEFAULT_AFFILIATE_PERCENTAGE: u8 = 10;
+
+    /// Largest affiliate percentage, i.e., the combined share of the operator and the
+    /// affiliate
+    pub const MAX_AFFILIATE_PERCENTAGE: u8 = 30;
+
+    /// Largest percentage of every bet sent to the progressive jackpot, taken from the
+    /// jackpot share
+    pub const MAX_PROGRESSIVE_PERCENTAGE: u8 = 10;
+
+    /// Largest percentage of every bet sent to the insurance fund, taken from the jackpot
+    /// share
+    pub const MAX_INSURANCE_PERCENTAGE: u8 = 5;
+
+    /// Largest number of rebate tiers of a lottery
+    pub const MAX_REBATE_TIERS: usize = 5;
+
+    /// Accounts kept in the leaderboard of a lottery
+    pub const LEADERBOARD_LENGTH: usize = 10;
+
+    /// Maximum number of state transitions kept in the journal
+    pub const JOURNAL_LENGTH: u64 = 64;
+
+    /// Largest page of the export messages
+    pub const MAX_EXPORT_LIMIT: u32 = 50;
+
+    /// Approvals a payout policy can require, one of the dev and one of the auditor
+    pub const MAX_PAYOUT_APPROVALS: u8 = 2;
+
+    /// Blocks after processing before a committed result can be published
+    pub const REVEAL_DELAY_BLOCKS: u32 = 2;
+
+    /// Gas limit of the draw settlement calls dispatched by the scheduler
+    pub const SCHEDULED_CALL_REF_TIME: u64 = 300_000_000_000;
+    pub const SCHEDULED_CALL_PROOF_SIZE: u64 = 500_000;
+
+    /// Blocks between the announcement of an upgrade and its execution, i.e., one day
+    /// of 12 second blocks
+    pub const UPGRADE_DELAY_BLOCKS: u32 = 7_200;
+
+    /// Blocks a voucher claim can be redeemed in, i.e., one hour of 12 second blocks
+    pub const VOUCHER_CLAIM_BLOCKS: u32 = 300;
+
+    /// Upgrade announced by the dev
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PendingUpgrade {
+        pub code_hash: Hash,
+        // First block the upgrade can be executed in
+        pub executable_block: u32,
+    }
+
+    /// Journal entry
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct JournalEntry {
+        // Sequence number of the state transition, starting at 1
+        pub seq: u64,
+        // Lottery of the state transition
+        pub lottery_id: u32,
+        pub block_number: u32,
+        pub caller: AccountId,
+        pub draw_number: Option<u32>,
+        pub status: Success,
+    }
+
+    /// Telemetry counters
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Metrics {
+        // Bets recorded in any draw
+        pub bets_accepted: u64,
+        // Draws that generated a winning number
+        pub draws_processed: u64,
+        // Draws that were settled and closed
+        pub draws_closed: u64,
+        // Asset transfers dispatched by the contract
+        pub transfers_attempted: u64,
+        // Asset transfers the runtime rejected
+        pub transfers_failed: u64,
+    }
+
+    /// Health report for monitoring
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Health {
+        pub is_started: bool,
+        pub open_draws: u32,
+        pub processing_draws: u32,
+        // Open draws past their processing block and processing draws past their
+        // closing block
+        pub overdue_draws: u32,
+        // Failed settlement transfers waiting to be retried
+        pub pending_failed_payouts: u32,
+        pub storage_version: u16,
+    }
+
+    /// Inconsistent state found by `check_invariants`
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Invariant {
+        // The draw accepts bets although its status is `Close`
+        OpenFlagOnClosedDraw,
+        // Winners are recorded on a draw that is still open
+        WinnersOnOpenDraw,
+        // An open or processing draw without bets holds a jackpot, e.g., a rolled over
+        // jackpot nobody bet on yet
+        JackpotWithoutBets,
+    }
+
+    /// Invariant violated by a draw
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Violation {
+        pub lottery_id: u32,
+        pub draw_number: u32,
+        pub invariant: Invariant,
+    }
+
+    /// Version and build metadata of the deployed contract
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Version {
+        pub crate_version: String,
+        pub git_hash: String,
+        // Front-ends decoding the storage or the events check these versions
+        pub storage_version: u16,
+        pub event_schema_version: u16,
+    }
+
+    /// Storage footprint of the contract, sizes are the SCALE-encoded bytes
+    ///
+    /// The registries kept in mappings, e.g., the affiliates and the vouchers, can't be
+    /// enumerated and are not included.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct StorageReport {
+        pub lotteries: u32,
+        pub draws: u32,
+        pub bets: u32,
+        // Winners of all prize divisions
+        pub winners: u32,
+        // Journal, leaderboard, held payout and failed payout entries
+        pub index_entries: u32,
+        // Draws including their bets and winners
+        pub draws_size: u32,
+        pub bets_size: u32,
+        pub winners_size: u32,
+        pub indexes_size: u32,
+        // Lotteries and the entries kept in mappings
+        pub total_size: u32,
+    }
+
+    /// Storage deposit budget of the contract
+    ///
+    /// The deposit is estimated from the encoded size of the lotteries and the mapped
+    /// entries, like the `total_size` of the storage report.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct DepositBudget {
+        // Deposit per byte of the chain, i.e., `DepositPerByte` of pallet_contracts
+        pub byte_deposit: u128,
+        // Deposit above which new bets are refused
+        pub limit: u128,
+    }
+
+    /// Storage deposit consumed by the contract
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct StorageDeposit {
+        // Encoded size of the lotteries and the mapped entries
+        pub bytes: u32,
+        // Estimated deposit of the bytes, zero without a budget
+        pub deposit: u128,
+        pub budget: Option<DepositBudget>,
+    }
+
+    /// Lottery hosted by the contract with its own setup, draws and accounting
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct LotteryState {
+        // Lottery Meta-data
+        pub lottery_setup: LotterySetup,
+        // Number of draws, their numbers are kept in `Lottery::draw_numbers`
+        pub draw_count: u32,
+        // Telemetry counters
+        pub metrics: Metrics,
+        // Commemorative NFTs minted, the id of the next item of the collection
+        pub nft_items: u32,
+        // Progressive jackpot carried across all draws of the lottery
+        pub progressive: ProgressivePot,
+        // House edge of all settled draws of the lottery
+        pub house_edge: HouseEdge,
+        // Fund covering the shortfalls of the settlements
+        pub insurance: InsuranceFund,
+    }
+
+    /// Insurance fund
+    ///
+    /// Receives a slice of every bet and covers what the jackpot of a fixed-odds draw
+    /// can't pay to its winners.  The dev can top it up and withdraw from it.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct InsuranceFund {
+        // Percentage of every bet taken from the jackpot share, zero stops the
+        // contributions
+        pub percentage: u8,
+        // Amount held by the contract for the next shortfall
+        pub balance: u128,
+        // Total received from the bets
+        pub contributed: u128,
+        // Total topped up by the dev
+        pub topped_up: u128,
+        // Total withdrawn by the dev
+        pub withdrawn: u128,
+        // Total used to cover shortfalls
+        pub covered: u128,
+    }
+
+    /// Realized house edge, i.e., what the bettors staked (handle) against the fees kept
+    /// by the house and the prizes paid back
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct HouseEdge {
+        // Total staked by the bettors
+        pub handle: u128,
+        // Operator, dev and affiliate shares of the bets
+        pub fees: u128,
+        // Jackpot, rebate, progressive and house prizes paid
+        pub prizes: u128,
+        // Handle less the prizes in basis points of the handle, negative if the prizes
+        // exceed the handle, e.g., when a carried jackpot is won
+        pub edge_bps: i64,
+    }
+
+    impl HouseEdge {
+        /// Add a settlement and recompute the edge
+        pub fn add(&mut self, handle: u128, fees: u128, prizes: u128) {
+            self.handle = self.handle.saturating_add(handle);
+            self.fees = self.fees.saturating_add(fees);
+            self.prizes = self.prizes.saturating_add(prizes);
+            self.edge_bps = if self.handle == 0 {
+                0
+            } else {
+                let kept = self.handle as i128 - self.prizes as i128;
+                (kept.saturating_mul(10_000) / self.handle as i128) as i64
+            };
+        }
+    }
+
+    /// Lifetime winnings of an account in a lottery
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct LeaderboardEntry {
+        pub account: AccountId,
+        pub winnings: u128,
+    }
+
+    /// Progressive jackpot
+    ///
+    /// Receives a slice of every bet and is paid out only to the bets matching the bonus
+    /// number of a draw, otherwise it keeps growing over the draws.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct ProgressivePot {
+        // Percentage of every bet taken from the jackpot share, zero disables the pot
+        pub percentage: u8,
+        // Amount held by the contract for the next hit
+        pub balance: u128,
+        // Total received since the lottery was created
+        pub contributed: u128,
+        // Total paid to the bonus number winners
+        pub paid: u128,
+        // Last draw whose bonus number was hit
+        pub last_hit_draw: Option<u32>,
+    }
+
+    /// Transfers of an account recorded by a simulated lottery
+    #[derive(scale::Encode, scale::Decode, Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct LedgerBalance {
+        // Total collected from the account, e.g., stakes and top-ups
+        pub collected: u128,
+        // Total paid to the account, e.g., prizes, shares and rebates
+        pub paid: u128,
+    }
+
     /// Lottery
     #[ink(storage)]
     pub struct Lottery {
-        // Lottery Meta-data
-        pub lottery_setup: LotterySetup,
-        // Multiple draws
-        pub draws: Vec<Draw>,
+        // The developer of the contract.  This account creates the lotteries.
+        pub dev: AccountId,
+        // Lotteries hosted by the contract per lottery id
+        pub lotteries: Mapping<u32, LotteryState>,
+        // Number of lotteries, i.e., the id of the next lottery
+        pub lottery_count: u32,
         // Randomizer salt
         pub salt: u64,
+        // Last state transitions, a ring buffer keyed by `seq % JOURNAL_LENGTH`
+        pub journal: Mapping<u64, JournalEntry>,
+        // Sequence number of the last journal entry
+        pub journal_seq: u64,
+        // Upgrade waiting for its delay to pass
+        pub pending_upgrade: Option<PendingUpgrade>,
+        // Affiliate registry, the referrer of every registered account
+        pub referrers: Mapping<AccountId, AccountId>,
+        // Accounts that placed a bet in any draw of any lottery, i.e., valid uplines
+        pub bettors: Mapping<AccountId, ()>,
+        // Affiliate shares received by every upline over all lotteries
+        pub affiliate_earnings: Mapping<AccountId, u128>,
+        // Affiliate shares received by every upline per (upline, lottery id, draw number)
+        pub draw_affiliate_earnings: Mapping<(AccountId, u32, u32), u128>,
+        // Accounts referred by every upline per (upline, index), in registration order
+        pub downlines: Mapping<(AccountId, u32), AccountId>,
+        // Number of accounts referred by every upline
+        pub downline_count: Mapping<AccountId, u32>,
+        // Betting streak per (account, lottery id)
+        pub streaks: Mapping<(AccountId, u32), Streak>,
+        // Prizes won per (account, lottery id)
+        pub winnings: Mapping<(AccountId, u32), u128>,
+        // Contract notified of every settlement, e.g., to aggregate account statistics
+        pub companion: Option<AccountId>,
+        // Id of the last bundle bought
+        pub bundle_seq: u64,
+        // Redemption codes by the Keccak256 hash of the code
+        pub vouchers: Mapping<[u8; 32], Voucher>,
+        // Free-bet credits per (account, lottery id)
+        pub credits: Mapping<(AccountId, u32), u128>,
+        // Pending voucher claim per account, see `claim_voucher`
+        pub voucher_claims: Mapping<AccountId, VoucherClaim>,
+        // Referees whose referral bonus was paid
+        pub referral_bonuses: Mapping<AccountId, ()>,
+        // Transfers recorded by simulated lotteries per (account, lottery id)
+        pub ledger: Mapping<(AccountId, u32), LedgerBalance>,
+        // Fiat value staked per (bettor, lottery id, day), the day is the block number
+        // divided by the daily total blocks of the lottery
+        pub daily_stakes: Mapping<(AccountId, u32, u32), u128>,
+        // True while a money path runs, see `guarded`.  Kept in its own cell, so it is
+        // stored before any external call and not only when the message returns.
+        pub locked: Lazy<bool>,
+        // False while the state of a migration can be imported, i.e., from the
+        // instantiation with `new_for_import` until `complete_import`
+        pub import_closed: bool,
+        // Choice of the bettors to carry their refunded bets forward per (account,
+        // lottery id), overrides the default of the draw
+        pub carry_forward: Mapping<(AccountId, u32), bool>,
+        // Storage deposit above which new bets are refused
+        pub deposit_budget: Option<DepositBudget>,
+        // Prizes and rebates to claim per (account, lottery id)
+        pub claims: Mapping<(AccountId, u32), Claim>,
+        // Draws per (lottery id, draw number)
+        pub draws: Mapping<(u32, u32), Draw>,
+        // Bets of the current cycle of the draws per (lottery id, draw number, index)
+        pub bets: Mapping<(u32, u32, u32), Bet>,
+        // Owners of the compact bets per (lottery id, draw number, bet number, index) in
+        // the order of the bets, the count is kept in `number_stakes`
+        pub number_bets: Mapping<(u32, u32, u16, u32), AccountId>,
+        // Number of bets and their total stake per (lottery id, draw number, bet number)
+        // in the current cycle of the draws
+        pub number_stakes: Mapping<(u32, u32, u16), (u32, u128)>,
+        // Bettors of the current cycle of the promotional draws per (lottery id, draw
+        // number, account), every account bets once
+        pub promotional_bettors: Mapping<(u32, u32, AccountId), ()>,
+        // Numbers of the draws per (lottery id, index) in the order they were added, the
+        // count is kept in `LotteryState::draw_count`
+        pub draw_numbers: Mapping<(u32, u32), u32>,
+        // Prizes of failed XCM deliveries per (account, lottery id), claimable locally
+        pub escrow: Mapping<(AccountId, u32), Vec<Escrow>>,
+        // Accounts with the highest lifetime winnings per (lottery id, rank), highest
+        // first
+        pub leaderboard: Mapping<(u32, u32), LeaderboardEntry>,
+        // Prizes and withdrawals held by the payout policies per (lottery id, draw
+        // number), zero for the withdrawals
+        pub held_payouts: Mapping<(u32, u32), Vec<HeldPayout>>,
+        // Escrow and credits imported per lottery id, `complete_import` checks that the
+        // contract holds them
+        pub imported_owed: Mapping<u32, u128>,
+        // Encoded size of the lotteries and the mapped entries, see `storage_bytes`
+        pub mapped_bytes: u32,
     }
 
     /// Implementation
     impl Lottery {
 
-        /// Lottery setup 
+        /// Lottery setup
         /// -------------
         /// Setup, start and stop the lottery
-        
+
         /// Constructor
+        ///
+        /// The lottery of the constructor parameters is created with id 0.
         #[ink(constructor)]
         pub fn new(asset_id: u128,
                    starting_block: u32,
                    daily_total_blocks: u32,
                    maximum_draws: u8,
                    maximum_bets: u16,
-                   init_start: bool) -> Self 
+                   init_start: bool) -> Self
         {
             let caller = Self::env().caller();
-            Self { 
-                lottery_setup: LotterySetup {
-                    operator: caller,
-                    dev: caller,
-                    asset_id: asset_id,
-                    starting_block: starting_block,
-                    daily_total_blocks: daily_total_blocks,
-                    next_starting_block: (starting_block + daily_total_blocks),
-                    maximum_draws: maximum_draws,
-                    maximum_bets: maximum_bets,
-                    is_started: init_start, 
-                },
-                draws: Vec::new(),
+            Self::env().emit_event(ContractDeployed {
+                dev: caller,
+                schema_version: EVENT_SCHEMA_VERSION,
+                block_number: Self::env().block_number(),
+                block_timestamp: Self::env().block_timestamp(),
+            });
+            let mut lottery_setup = Self::new_lottery_setup(caller,
+                caller,
+                asset_id,
+                starting_block,
+                daily_total_blocks,
+                maximum_draws,
+                maximum_bets);
+            lottery_setup.is_started = init_start;
+            let mut contract = Self {
+                dev: caller,
+                lotteries: Mapping::default(),
+                lottery_count: 0,
                 salt: 0,
-            }
+                journal: Mapping::default(),
+                journal_seq: 0,
+                pending_upgrade: None,
+                referrers: Mapping::default(),
+                bettors: Mapping::default(),
+                affiliate_earnings: Mapping::default(),
+                draw_affiliate_earnings: Mapping::default(),
+                downlines: Mapping::default(),
+                downline_count: Mapping::default(),
+                streaks: Mapping::default(),
+                winnings: Mapping::default(),
+                companion: None,
+                bundle_seq: 0,
+                vouchers: Mapping::default(),
+                credits: Mapping::default(),
+                voucher_claims: Mapping::default(),
+                referral_bonuses: Mapping::default(),
+                ledger: Mapping::default(),
+                daily_stakes: Mapping::default(),
+                locked: Lazy::new(),
+                import_closed: true,
+                carry_forward: Mapping::default(),
+                deposit_budget: None,
+                claims: Mapping::default(),
+                draws: Mapping::default(),
+                bets: Mapping::default(),
+                number_bets: Mapping::default(),
+                number_stakes: Mapping::default(),
+                promotional_bettors: Mapping::default(),
+                draw_numbers: Mapping::default(),
+                escrow: Mapping::default(),
+                leaderboard: Mapping::default(),
+                held_payouts: Mapping::default(),
+                imported_owed: Mapping::default(),
+                mapped_bytes: 0,
+            };
+            contract.add_lottery(Self::new_lottery_state(lottery_setup));
+            contract
+        }
+
+        /// Constructor of a migration
+        ///
+        /// Like `new` with a stopped lottery, but the dev can import the state exported by
+        /// the previous contract until `complete_import` is called.
+        #[ink(constructor)]
+        pub fn new_for_import(asset_id: u128,
+                   starting_block: u32,
+                   daily_total_blocks: u32,
+                   maximum_draws: u8,
+                   maximum_bets: u16) -> Self
+        {
+            let mut contract = Self::new(asset_id,
+                starting_block,
+                daily_total_blocks,
+                maximum_draws,
+                maximum_bets,
+                false);
+            contract.import_closed = false;
+            contract
         }
 
         /// Make a default instantiation
@@ -217,469 +1237,911 @@ mod lottery {
                       false)
         }
 
-        /// Only the dev can setup the lottery smart contract
+        /// Create a lottery
+        ///
+        /// 1. Only the dev can create a lottery.
+        /// 2. The lottery is independent of the other lotteries of the contract, i.e., it
+        ///    has its own operator, draws and accounting.
+        /// 3. Returns the id of the new lottery.
         #[ink(message)]
-        pub fn setup(&mut self, 
+        pub fn create_lottery(&mut self,
                      operator: AccountId,
                      asset_id: u128,
                      starting_block: u32,
                      daily_total_blocks: u32,
                      maximum_draws: u8,
-                     maximum_bets: u16) -> Result<(), Error> {
+                     maximum_bets: u16) -> Result<u32, ContractError> {
 
-            // Only the dev (the account that deployed the contract) can change the 
-            // lottery setup.  The operator handles the functional activities of the 
-            // lottery while the dev handles all technical issues.
-            if self.env().caller() != self.lottery_setup.dev {
-                self.env().emit_event(LotteryEvent {
-                    operator: self.lottery_setup.operator,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
 
-            self.lottery_setup.operator = operator;
-            self.lottery_setup.asset_id = asset_id;
-            self.lottery_setup.starting_block = starting_block;
-            self.lottery_setup.daily_total_blocks = daily_total_blocks;
-            self.lottery_setup.next_starting_block = starting_block + daily_total_blocks;
-            self.lottery_setup.maximum_draws = maximum_draws;
-            self.lottery_setup.maximum_bets = maximum_bets;
-            self.lottery_setup.is_started = false;
+            let lottery_setup = Self::new_lottery_setup(operator,
+                caller,
+                asset_id,
+                starting_block,
+                daily_total_blocks,
+                maximum_draws,
+                maximum_bets);
+            let lottery_id = self.add_lottery(Self::new_lottery_state(lottery_setup));
 
-            self.env().emit_event(LotteryEvent {
-                operator: self.lottery_setup.operator,
-                status: LotteryStatus::EmitSuccess(Success::LotterySetup),
+            self.emit_success(lottery_id, None, Success::LotteryCreated {
+                lottery_id: lottery_id,
+                operator: operator,
+            });
+            Ok(lottery_id)
+        }
+
+        /// Only the dev can setup the lottery smart contract
+        #[ink(message)]
+        pub fn setup(&mut self,
+                     lottery_id: u32,
+                     operator: AccountId,
+                     asset_id: u128,
+                     starting_block: u32,
+                     daily_total_blocks: u32,
+                     maximum_draws: u8,
+                     maximum_bets: u16,
+                     payment_backend: PaymentBackend,
+                     randomness_source: RandomnessSource,
+                     affiliate_percentage: u8) -> Result<(), ContractError> {
+
+            // Only the dev (the account that deployed the contract) can change the
+            // lottery setup.  The operator handles the functional activities of the
+            // lottery while the dev handles all technical issues.
+            let caller = self.env().caller();
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            if caller != lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            self.check_operator_shares(lottery_id, &LotterySetup {
+                affiliate_percentage: affiliate_percentage,
+                ..lottery_setup.clone()
+            })?;
+
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+
+            lottery_setup.operator = operator;
+            lottery_setup.asset_id = asset_id;
+            lottery_setup.payment_backend = payment_backend;
+            lottery_setup.randomness_source = randomness_source;
+            lottery_setup.affiliate_percentage = affiliate_percentage;
+            lottery_setup.starting_block = starting_block;
+            lottery_setup.daily_total_blocks = daily_total_blocks;
+            lottery_setup.next_starting_block = starting_block + daily_total_blocks;
+            lottery_setup.maximum_draws = maximum_draws;
+            lottery_setup.maximum_bets = maximum_bets;
+            lottery_setup.is_started = false;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::LotterySetup {
+                operator: operator,
+                asset_id: asset_id,
+                starting_block: starting_block,
+            });
+            Ok(())
+        }
+
+        /// Set the price oracle
+        ///
+        /// 1. Only the operator can set the price oracle.
+        /// 2. The fiat value of the bets of a bettor per day, i.e., per `daily_total_blocks`
+        ///    blocks, is limited to `max_daily_value` using the price of the asset from the
+        ///    oracle.  Zero disables the limit.
+        #[ink(message)]
+        pub fn set_oracle(&mut self,
+            lottery_id: u32,
+            oracle: Option<AccountId>,
+            max_daily_value: u128) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            lottery_setup.oracle = oracle;
+            lottery_setup.max_daily_value = max_daily_value;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::OracleSet {
+                oracle: oracle,
+                max_daily_value: max_daily_value,
+            });
+            Ok(())
+        }
+
+        /// Set the rebate tiers
+        ///
+        /// 1. Only the dev can set the rebate tiers.
+        /// 2. The lottery must be stopped so all bets of a cycle share the same rebate.
+        /// 3. The tiers must be ascending by bets, with a weight of at least 1 and no more
+        ///    than `MAX_REBATE_TIERS`.  No tiers restores the flat split per bet.
+        #[ink(message)]
+        pub fn set_rebate_tiers(&mut self,
+            lottery_id: u32,
+            rebate_tiers: Vec<RebateTier>) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
+            }
+
+            let ascending = rebate_tiers.windows(2).all(|t| t[0].min_bets < t[1].min_bets);
+            if rebate_tiers.len() > MAX_REBATE_TIERS || !ascending || rebate_tiers.iter().any(|t| t.weight == 0) {
+                return Err(Error::InvalidRebateTiers.into());
+            }
+
+            let tiers = rebate_tiers.len() as u8;
+            lottery_setup.rebate_tiers = rebate_tiers;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::RebateTiersSet {
+                tiers: tiers,
+            });
+            Ok(())
+        }
+
+        /// Set the bulk discount
+        ///
+        /// 1. Only the operator can set the bulk discount, it is funded from its share.
+        /// 2. Every ticket of a `place_bets` call buying at least `min_tickets` tickets is
+        ///    discounted by `percentage` of the bet amount, e.g., 10 tickets for the price
+        ///    of 9 is 10 tickets at 10%.  Zero tickets disables the discount.
+        /// 3. The jackpot, rebate and affiliate shares of the tickets are unaffected, so the
+        ///    discount can't exceed the operator share of a ticket.
+        #[ink(message)]
+        pub fn set_bulk_discount(&mut self,
+            lottery_id: u32,
+            min_tickets: u32,
+            percentage: u8) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            if percentage > Self::operator_margin(&lottery_setup.shares, lottery_setup.affiliate_percentage) {
+                return Err(Error::DiscountExceedsOperatorShare.into());
+            }
+
+            lottery_setup.bulk_discount_tickets = min_tickets;
+            lottery_setup.bulk_discount_percentage = percentage;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::BulkDiscountSet {
+                min_tickets: min_tickets,
+                percentage: percentage,
+            });
+            Ok(())
+        }
+
+        /// Set the streak bonus
+        ///
+        /// 1. Only the operator can set the streak bonus, it is funded from its share.
+        /// 2. A bettor betting in `threshold` consecutive cycles (and every multiple) is
+        ///    paid `percentage` of the bet amount with the bet that reaches it.  Zero
+        ///    cycles disables the bonus.
+        /// 3. The bonus can't exceed the operator share left after the affiliate
+        ///    percentage.
+        #[ink(message)]
+        pub fn set_streak_bonus(&mut self,
+            lottery_id: u32,
+            threshold: u32,
+            percentage: u8) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            Self::check_bonus_percentage(percentage,
+                Self::operator_margin(&lottery_setup.shares, lottery_setup.affiliate_percentage))?;
+
+            lottery_setup.streak_threshold = threshold;
+            lottery_setup.streak_bonus_percentage = percentage;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::StreakBonusSet {
+                threshold: threshold,
+                percentage: percentage,
+            });
+            Ok(())
+        }
+
+        /// Set the referral bonus
+        ///
+        /// 1. Only the operator can set the referral bonus, it is funded from its share.
+        /// 2. The first bet of a referee with a registered referrer pays `percentage` of
+        ///    the stake to both the referee and the referrer, once per referee.  Zero
+        ///    disables the bonus.
+        /// 3. The bonus can't exceed half of the operator share left after the affiliate
+        ///    percentage each.
+        #[ink(message)]
+        pub fn set_referral_bonus(&mut self,
+            lottery_id: u32,
+            percentage: u8) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            Self::check_bonus_percentage(percentage,
+                Self::operator_margin(&lottery_setup.shares, lottery_setup.affiliate_percentage) / 2)?;
+
+            lottery_setup.referral_bonus_percentage = percentage;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::ReferralBonusSet {
+                percentage: percentage,
+            });
+            Ok(())
+        }
+
+        /// Set the NFT collection
+        ///
+        /// 1. Only the operator can set the collection of the commemorative winner NFTs.
+        /// 2. The contract must be the issuer of the pallet_nfts collection, `None` stops
+        ///    minting.
+        #[ink(message)]
+        pub fn set_nft_collection(&mut self,
+            lottery_id: u32,
+            collection: Option<u32>) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            lottery_setup.nft_collection = collection;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::NftCollectionSet {
+                collection: collection,
+            });
+            Ok(())
+        }
+
+        /// Set the asset metadata
+        ///
+        /// 1. Only the dev can set the metadata of the asset.
+        /// 2. With the `assets-extension` feature the decimals must match the metadata of
+        ///    the asset in pallet_assets.
+        /// 3. The bet amounts of the draws must be whole cents of the asset, i.e., multiples
+        ///    of `10^(decimals - 2)`, which rejects amounts configured for other decimals.
+        #[ink(message)]
+        pub fn set_asset_metadata(&mut self,
+            lottery_id: u32,
+            decimals: u8,
+            symbol: String) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            #[cfg(feature = "assets-extension")]
+            {
+                let actual = crate::chain_extension::decimals(lottery.lottery_setup.asset_id)?;
+                if actual != decimals {
+                    return Err(Error::AssetDecimalsMismatch {
+                        expected: actual,
+                        actual: decimals,
+                    }.into());
+                }
+            }
+
+            lottery.lottery_setup.asset_metadata = Some(AssetMetadata {
+                decimals: decimals,
+                symbol: symbol.clone(),
+            });
+            self.save_lottery(lottery_id, &lottery);
+
+            // The configured draws must be sane for the decimals
+            for draw in self.lottery_draws(lottery_id) {
+                Self::check_bet_unit(&lottery.lottery_setup, draw.bet_amount)?;
+            }
+
+            self.emit_success(lottery_id, None, Success::AssetMetadataSet {
+                decimals: decimals,
+                symbol: symbol,
             });
             Ok(())
         }
 
         /// Start the lottery
-        /// 
+        ///
         /// 1. Only the operator can start the lottery
         /// 2. The current block must be greater than the starting block
         #[ink(message)]
-        pub fn start(&mut self) -> Result<(), Error>  {
-            
+        pub fn start(&mut self, lottery_id: u32) -> Result<(), ContractError>  {
+
             // The caller must be the operator
             let caller = self.env().caller();
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            let current_block: u32 = self.env().block_number();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
 
             // Check of already started
-            if self.lottery_setup.is_started {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::AlreadyStarted),
-                });
-                return Ok(());
+            if lottery.lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
             }
 
             // Check block
-            let current_block: u32 = self.env().block_number();
-            if current_block < self.lottery_setup.starting_block {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::InvalidBlock),
-                });
-                return Ok(());
+            if current_block < lottery.lottery_setup.starting_block {
+                return Err(Error::InvalidBlock {
+                    required_block: lottery.lottery_setup.starting_block,
+                    current_block: current_block,
+                }.into());
             }
 
-            self.lottery_setup.is_started = true;
+            lottery.lottery_setup.is_started = true;
+            self.save_lottery(lottery_id, &lottery);
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::LotteryStarted),
+            let starting_block = lottery.lottery_setup.starting_block;
+            self.emit_success(lottery_id, None, Success::LotteryStarted {
+                starting_block: starting_block,
             });
             Ok(())
         }
 
         /// Stop the lottery
-        /// 
+        ///
         /// 1. The lottery can be stop only if all draws are closed
         /// 2. Stopping the lottery if the block passes the starting block is also invalid.
         ///    You must correct the setup of the lottery before stopping.
         /// 3. Only the operator can stop the lottery.
-        /// 4. 
+        /// 4.
         #[ink(message)]
-        pub fn stop(&mut self) -> Result<(), Error> {
+        pub fn stop(&mut self, lottery_id: u32) -> Result<(), ContractError> {
 
             // Check operator
             let caller = self.env().caller();
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            let current_block: u32 = self.env().block_number();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
 
             // Check if all draws are closed
-            for draw in self.draws.clone() {
+            for draw in self.lottery_draws(lottery_id) {
                 if draw.is_open || draw.status == DrawStatus::Open {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawOpen),
-                    });
-                    return Ok(());
+                    return Err(Error::DrawOpen.into());
                 }
             }
 
             // Check if the current block did not pass the next lottery starting block
-            let current_block: u32 = self.env().block_number();
-            let next_lottery_starting_block: u32 = self.lottery_setup.next_starting_block;
+            let next_lottery_starting_block: u32 = lottery.lottery_setup.next_starting_block;
             if next_lottery_starting_block > current_block  {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::InvalidBlock),
-                });
-                return Ok(());
+                return Err(Error::InvalidBlock {
+                    required_block: next_lottery_starting_block,
+                    current_block: current_block,
+                }.into());
             }
 
-            self.lottery_setup.is_started = false;
-            self.lottery_setup.starting_block = self.lottery_setup.next_starting_block;
-            self.lottery_setup.next_starting_block = self.lottery_setup.next_starting_block + self.lottery_setup.daily_total_blocks;
+            lottery.lottery_setup.is_started = false;
+            lottery.lottery_setup.starting_block = lottery.lottery_setup.next_starting_block;
+            lottery.lottery_setup.next_starting_block = lottery.lottery_setup.next_starting_block + lottery.lottery_setup.daily_total_blocks;
+            self.save_lottery(lottery_id, &lottery);
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::LotteryStopped),
+            let starting_block = lottery.lottery_setup.starting_block;
+            self.emit_success(lottery_id, None, Success::LotteryStopped {
+                next_starting_block: starting_block,
             });
+
+            // Every draw recurs in the next cycle with its unclaimed jackpot
+            for draw in self.lottery_draws(lottery_id).iter() {
+                self.emit_draw_scheduled(lottery_id, &lottery.lottery_setup, draw);
+            }
             Ok(())
         }
 
         /// Lottery draws
         /// -------------
         /// All functions related to draws
-        
+
         /// Add draw:
-        /// 
+        ///
         /// 1. Only the operator can add a draw.
         /// 2. The draw can only be added if the lottery is stopped.
         /// 3. It must be important that the following hierarchy of value must be followed.
         ///    lottery.daily_total_blocks > closing_blocks > processing_blocks > opening_blocks
+        /// 4. The bet amount must not be zero.
         #[ink(message)]
-        pub fn add_draw(&mut self, 
+        pub fn add_draw(&mut self,
+            lottery_id: u32,
             opening_blocks: u32,
             processing_blocks: u32,
             closing_blocks: u32,
-            bet_amount: u128) -> Result<(), Error>  {
-            
-            // Only the operator can add a draw
-            let caller = self.env().caller();      
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            bet_amount: u128,
+            settlement_mode: SettlementMode) -> Result<(), ContractError>  {
 
-            // Must not exceed the maximum number of draws setup in the lottery
-            if self.draws.len() >= self.lottery_setup.maximum_draws.into() {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::TooManyDraws),
-                });
-                return Ok(());
-            }
+            self.insert_draw(lottery_id, opening_blocks, processing_blocks, closing_blocks,
+                bet_amount, settlement_mode, false)
+        }
 
-            // Blocks must follow hierarchy order.
-            if self.lottery_setup.daily_total_blocks > closing_blocks && 
-               closing_blocks > processing_blocks && 
-               processing_blocks > opening_blocks {
-                // Do nothing and continue
-            } else {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::InvalidBlocksHierarchy),
-                });
-                return Ok(());
+        /// Add the draws of a daily cycle
+        ///
+        /// 1. The cycle (`daily_total_blocks`) is split into one slot per draw of the
+        ///    template and every draw is added like `add_draw`.
+        /// 2. A draw opens at the start of its slot, processes once the betting share of
+        ///    the slot has passed and closes halfway through the rest of the slot.
+        /// 3. Either all draws are added or none.
+        #[ink(message)]
+        pub fn add_daily_draws(&mut self, lottery_id: u32, template: DrawTemplate) -> Result<(), ContractError> {
+            if template.draws == 0 || template.betting_percentage >= 100 {
+                return Err(Error::InvalidBlocksHierarchy.into());
             }
 
-            // Check if the lottery is stopped
-            if self.lottery_setup.is_started == true {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::AlreadyStarted),
-                });
-                return Ok(());
-            }
+            let daily_total_blocks = self.lottery(lottery_id)?.lottery_setup.daily_total_blocks;
+            let slot = daily_total_blocks / template.draws as u32;
 
-            let next_draw_number = self.draws
-                                            .iter()
-                                            .map(|d| d.draw_number)
-                                            .max()
-                                            .unwrap_or(0)
-                                            .saturating_add(1);
+            for index in 0..template.draws as u32 {
+                let opening_blocks = index * slot;
+                let betting_blocks = slot * template.betting_percentage as u32 / 100;
+                let processing_blocks = opening_blocks + betting_blocks;
+                let closing_blocks = processing_blocks + (slot - betting_blocks) / 2;
 
-            let new_draw = Draw {
-                draw_number: next_draw_number,
-                opening_blocks: opening_blocks,
-                processing_blocks: processing_blocks,
-                closing_blocks: closing_blocks,
-                bet_amount: bet_amount,
-                jackpot: 0,
-                rebate: 0,
-                bets: Vec::new(),
-                winning_number: 0,
-                winners: Vec::new(),
-                status: DrawStatus::Close,
-                is_open: false,
-            };
+                self.insert_draw(lottery_id, opening_blocks, processing_blocks, closing_blocks,
+                    template.bet_amount, template.settlement_mode.clone(), false)?;
+            }
+            Ok(())
+        }
 
-            self.draws.push(new_draw);
+        /// Add a promotional draw
+        ///
+        /// 1. The same rules of `add_draw` apply and the draw is settled like any other
+        ///    draw, except that the fee may be zero.
+        /// 2. The operator seeds the jackpot with `add_draw_jackpot`.
+        /// 3. Bettors pay the fee and it is added to the jackpot in full, no operator, dev,
+        ///    affiliate or rebate shares are paid.
+        /// 4. Every account can place one bet in the draw.
+        #[ink(message)]
+        pub fn add_promotional_draw(&mut self,
+            lottery_id: u32,
+            opening_blocks: u32,
+            processing_blocks: u32,
+            closing_blocks: u32,
+            fee: u128) -> Result<(), ContractError>  {
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::DrawAdded),
-            });
-            Ok(())
+            self.insert_draw(lottery_id, opening_blocks, processing_blocks, closing_blocks,
+                fee, SettlementMode::PariMutuel, true)
         }
 
         /// Remove draw:
-        /// 
+        ///
         /// 1. Only the operator can remove a draw.
         /// 2. The lottery must be stopped before removing a draw.
         /// 3. The removal is last-in-first-out sequence
         #[ink(message)]
-        pub fn remove_draw(&mut self) -> Result<(), Error> {
+        pub fn remove_draw(&mut self, lottery_id: u32) -> Result<(), ContractError> {
             // Only the operator can add a draw
-            let caller = self.env().caller();      
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
 
             // No more draw record
-            if self.draws.len() == 0 {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::NoRecords),
-                });
-                return Ok(());
+            if lottery.draw_count == 0 {
+                return Err(Error::NoRecords.into());
             }
 
             // Check if the lottery is stopped
-            if self.lottery_setup.is_started == true {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::AlreadyStarted),
-                });
-                return Ok(());
+            if lottery.lottery_setup.is_started == true {
+                return Err(Error::AlreadyStarted.into());
             }
 
-            self.draws.pop();
+            // The stakes of carried bets are owed to their owners
+            let draw_number = self.draw_numbers
+                .get((lottery_id, lottery.draw_count - 1))
+                .ok_or(Error::NoRecords)?;
+            let removed_draw = self.draw(lottery_id, draw_number)?;
+            if !removed_draw.participation.carried_bets.is_empty() {
+                return Err(Error::CarriedBetsPending.into());
+            }
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::DrawRemoved),
+            self.pop_draw_number(lottery_id)?;
+            self.delete_draw(lottery_id, draw_number);
+
+            self.emit_success(lottery_id, Some(removed_draw.draw_number), Success::DrawRemoved {
+                draw_number: removed_draw.draw_number,
+            });
+
+            self.env().emit_event(DrawRemoved {
+                draw_number: removed_draw.draw_number,
+                lottery_id: lottery_id,
+                caller: caller,
+                block_number: self.env().block_number(),
+                block_timestamp: self.env().block_timestamp(),
             });
             Ok(())
         }
 
         /// Open draw
-        /// 
-        /// 1. Only the operator can open a draw
+        ///
+        /// 1. Only the operator or the delegate of the draw can open a draw
         /// 2. The draw status must be close and the is_open flag must be false before
         ///    you can open a draw.
         /// 3. The block number must be greater than the lottery starting block plus the
         ///    draw blocks opening.
+        /// 4. Processing and closing of the draw are scheduled at their deadline blocks
+        ///    through the scheduler pallet.  The runtime must let contracts dispatch
+        ///    `Scheduler::schedule`, i.e., its `CallFilter` allows the call and its
+        ///    `ScheduleOrigin` accepts a signed origin; standard runtimes require Root.
+        ///    A rejected scheduling is reported with `SchedulingFailed` and the operator
+        ///    must then process and close the draw itself.
+        /// 5. The bets carried forward from a refunded cycle are placed again, a bet the
+        ///    draw no longer accepts, e.g., after a change of the bet amount, is refunded.
+        /// 6. The draws of a salt-reveal lottery are opened with
+        ///    `open_draw_with_commitment` instead.
         #[ink(message)]
-        pub fn open_draw(&mut self, draw_number: u32) -> Result<(), Error> {
-            // Only the operator can add a draw
-            let caller = self.env().caller();      
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+        pub fn open_draw(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            self.open_cycle(lottery_id, draw_number, None)
+        }
+
+        /// Open draw of a salt-reveal lottery
+        ///
+        /// 1. The draw is opened like with `open_draw` and keeps the commitment to the
+        ///    salt of the cycle, see `salt_commitment`.
+        /// 2. The salt is revealed with `process_draw_with_salt` in the processing block of
+        ///    the draw, the operator can't choose the salt once the bets are known nor the
+        ///    block it is mixed with.
+        /// 3. If the salt is not revealed in that block the operator forfeits the cycle,
+        ///    `process_draw` refunds all stakes instead of drawing.
+        #[ink(message)]
+        pub fn open_draw_with_commitment(&mut self, lottery_id: u32, draw_number: u32,
+            commitment: [u8; 32]) -> Result<(), ContractError> {
+
+            self.open_cycle(lottery_id, draw_number, Some(commitment))
+        }
+
+        /// Open a cycle of a draw with the commitment to its salt, see `open_draw`
+        fn open_cycle(&mut self, lottery_id: u32, draw_number: u32,
+            commitment: Option<[u8; 32]>) -> Result<(), ContractError> {
+
+            // Only the operator or the delegate of the draw can open a draw
+            let caller = self.env().caller();
+            let current_block: u32 = self.env().block_number();
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            let starting_block = lottery_setup.starting_block;
+            let salt_reveal = lottery_setup.randomness_source == RandomnessSource::SaltReveal;
+            if !self.manages_draw(lottery_id, draw_number, caller) {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Only the draws of a salt-reveal lottery are committed to a salt
+            if salt_reveal != commitment.is_some() {
+                return Err(Error::SaltNotCommitted.into());
+            }
 
             // Check if draw exist
-            let draw = match self.draws.iter().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
+            let mut draw = self.draw(lottery_id, draw_number)?;
 
             // The current block must be greater or equal to the draw opening blocks.
-            let current_block: u32 = self.env().block_number();
-            let draw_opening_blocks: u32 = self.lottery_setup.starting_block + draw.opening_blocks;
+            let draw_opening_blocks: u32 = starting_block + draw.opening_blocks;
             if draw_opening_blocks > current_block  {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::InvalidBlock),
+                return Err(Error::DrawNotDue {
+                    due_block: draw_opening_blocks,
+                    current_block: current_block,
+                }.into());
+            }
+
+            // Open the draw for betting.  Check if the draw is close to open
+            if !draw.is_open && draw.status == DrawStatus::Close {
+                draw.is_open = true;
+                draw.status = DrawStatus::Open;
+                draw.result = None;
+                draw.committed_salt = commitment.map(|commitment| SaltCommitment {
+                    commitment: commitment,
+                    reveal_block: starting_block + draw.processing_blocks,
+                    salt: None,
+                    entropy: [0u8; 32],
+                    nonce: 0,
                 });
-                return Ok(());
+            } else {
+                return Err(Error::DrawOpen.into());
             }
 
-            // Open the draw for betting
-            for draw in &mut self.draws {
-                if draw.draw_number == draw_number {
-                    // Check if the draw is close to open
-                    if !draw.is_open && draw.status == DrawStatus::Close {
-                        draw.is_open = true;
-                        draw.status = DrawStatus::Open;
-                    } else {
-                        self.env().emit_event(LotteryEvent {
-                            operator: caller,
-                            status: LotteryStatus::EmitError(Error::DrawOpen),
-                        });
-                        return Ok(());
-                    }
+            // Schedule the settlement of the draw at its deadline blocks
+            let processing_block = starting_block + draw.processing_blocks;
+            let closing_block = starting_block + draw.closing_blocks;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawOpened {
+                draw_number: draw_number,
+            });
+            for (when, selector) in [
+                (processing_block, ink::selector_bytes!("process_draw")),
+                (closing_block, ink::selector_bytes!("close_draw")),
+            ] {
+                if let Err(error) = self.schedule_call(when, selector, lottery_id, draw_number) {
+                    self.emit_success(lottery_id, Some(draw_number), Success::SchedulingFailed {
+                        draw_number: draw_number,
+                        when: when,
+                        error: error,
+                    });
                 }
             }
+            if let Some(commitment) = commitment {
+                self.emit_success(lottery_id, Some(draw_number), Success::SaltCommitted {
+                    draw_number: draw_number,
+                    commitment: commitment,
+                });
+            }
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::DrawOpened),
+            self.env().emit_event(DrawOpened {
+                draw_number: draw_number,
+                lottery_id: lottery_id,
+                caller: caller,
+                block_number: current_block,
+                block_timestamp: self.env().block_timestamp(),
             });
-            Ok(())
+
+            // Bets carried forward from a refunded cycle join the new cycle
+            self.place_carried_bets(lottery_id, draw_number)
         }
 
         /// Process draw
-        /// 
+        ///
         /// 1. Processing means that stopping the lottery draw in accepting bets.
         /// 2. At the same time it calculates in random the winning number.
-        /// 3. It will also gives the operator the opportunity to override the winning 
+        /// 3. It will also gives the operator the opportunity to override the winning
         ///    number.
         /// 4. It will also checks of the current block is greater than the sum of the
         ///    lottery starting block and the processing blocks of the draw.
+        /// 5. Only the operator or the delegate of the draw can process the draw, or the
+        ///    contract itself when scheduled.
+        /// 6. A draw without bets is not drawn, it is closed and rolls over to the next
+        ///    cycle with its jackpot (`DrawRolledOver`).
+        /// 7. A draw below its minimum participation is not drawn either, it is closed
+        ///    and all stakes of the cycle are refunded (`StakesRefunded`).
+        /// 8. A commit-reveal lottery only keeps the commitment of the operator, the
+        ///    winning number is published with `publish_result`.
         #[ink(message)]
-        pub fn process_draw(&mut self, draw_number: u32) -> Result<(), Error> {
-            // Check if operator
+        pub fn process_draw(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            // Check if operator, delegate or the contract itself through the scheduler
             let caller = self.env().caller();
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            self.lottery(lottery_id)?;
+            if !self.manages_draw(lottery_id, draw_number, caller) && caller != self.env().account_id() {
+                return Err(Error::BadOrigin.into());
+            }
 
-            // Check if draw exist
-            let draw = match self.draws.iter().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
+            // Check if the draw can be processed at the current block
+            self.check_process_draw(lottery_id, draw_number)?;
 
-            // Check if draw is open
-            if !draw.is_open {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::DrawClosed),
+            // Roll a draw without bets over to the next cycle instead of drawing a number
+            let next_starting_block = self.lottery(lottery_id)?.lottery_setup.next_starting_block;
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            let outcome = self.process_outcome(lottery_id, &draw)?;
+            if outcome == ProcessOutcome::RollOver {
+                draw.is_open = false;
+                draw.status = DrawStatus::Close;
+                let next_opening_block = next_starting_block + draw.opening_blocks;
+                self.save_draw(lottery_id, &draw);
+
+                self.emit_success(lottery_id, Some(draw_number), Success::DrawRolledOver {
+                    draw_number: draw_number,
+                    next_opening_block: next_opening_block,
                 });
                 return Ok(());
             }
 
-            // Check if draw status is processing.  We can only process open draws
-            if draw.status == DrawStatus::Processing {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::DrawProcessing),
-                });
-                return Ok(());
+            // Refund the stakes of the cycle instead, see `process_outcome`
+            if outcome == ProcessOutcome::Refund {
+                return self.guarded(|lottery| lottery.refund_stakes(lottery_id, draw_number));
             }
 
-            // The current block must be greater or equal to the draw processing blocks.
-            let current_block: u32 = self.env().block_number();
-            let draw_processing_blocks: u32 = self.lottery_setup.starting_block + draw.processing_blocks;
-            if draw_processing_blocks > current_block  {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::InvalidBlock),
+            // A committed result is only published a few blocks later
+            if outcome == ProcessOutcome::Commit {
+                let reveal_block = self.env().block_number() + REVEAL_DELAY_BLOCKS;
+                let result = draw.result.as_mut().ok_or(Error::ResultNotCommitted)?;
+                result.reveal_block = reveal_block;
+
+                draw.is_open = false;
+                draw.status = DrawStatus::Processing;
+                draw.winning_number = 0;
+                draw.bonus_number = 0;
+                self.save_draw(lottery_id, &draw);
+                self.update_lottery(lottery_id, |l| l.metrics.draws_processed += 1)?;
+
+                self.emit_success(lottery_id, Some(draw_number), Success::DrawProcessed {
+                    draw_number: draw_number,
+                    winning_number: 0,
                 });
                 return Ok(());
             }
 
             // Generate random number
-            let max_value: u16 = 999;
-            let seed = self.env().block_timestamp();
-
-            let mut input: Vec<u8> = Vec::new();
-            input.extend_from_slice(&seed.to_be_bytes());
-            input.extend_from_slice(&self.salt.to_be_bytes());
-
-            let mut output = <hash::Keccak256 as hash::HashOutput>::Type::default();
-            ink::env::hash_bytes::<hash::Keccak256>(&input, &mut output);
-
+            let random_num = self.random_winning_number(lottery_id, draw_number)?;
             self.salt += 1;
 
-            let raw = u16::from_le_bytes([output[0], output[1]]);
-            let random_num: u16 = (raw % max_value) + 1;
+            // Generate the bonus number of the progressive jackpot
+            let bonus_number = self.random_winning_number(lottery_id, draw_number)?;
+            self.salt += 1;
 
             // Close the draw (No one can bet anymore)
-            let draw = match self.draws.iter_mut().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
-
-            draw.is_open = false;            
+            draw.is_open = false;
             draw.status = DrawStatus::Processing;
             draw.winning_number = random_num;
+            draw.bonus_number = bonus_number;
+            self.save_draw(lottery_id, &draw);
+            self.update_lottery(lottery_id, |l| l.metrics.draws_processed += 1)?;
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::DrawProcessed),
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawProcessed {
+                draw_number: draw_number,
+                winning_number: random_num,
             });
             Ok(())
         }
 
-        /// Override draw
-        /// 
-        /// 1. The operator can override the winning number of the draw during the processing period.
+        /// Process draw of a salt-reveal lottery
+        ///
+        /// 1. Only the operator or the delegate of the draw can reveal the salt committed
+        ///    when opening the draw, and only in the reveal block of the commitment.
+        /// 2. The salt is mixed with the runtime randomness of the reveal block, unknown
+        ///    when committing, to draw the winning and the bonus number.  The salt, the
+        ///    randomness and the salt of the contract are kept in the draw, so anyone can
+        ///    recompute the numbers.
+        /// 3. The draw is processed like with `process_draw`.
         #[ink(message)]
-        pub fn override_draw(&mut self, draw_number: u32,
-            winning_number: u16) -> Result<(), Error> {
+        pub fn process_draw_with_salt(&mut self, lottery_id: u32, draw_number: u32,
+            salt: [u8; 32]) -> Result<(), ContractError> {
 
-            // Check if operator
             let caller = self.env().caller();
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            self.lottery(lottery_id)?;
+            if !self.manages_draw(lottery_id, draw_number, caller) {
+                return Err(Error::BadOrigin.into());
+            }
 
             // Check if draw exist
-            let draw = match self.draws.iter_mut().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            let current_block = self.env().block_number();
+            let nonce = self.salt;
+            let committed_salt = draw.committed_salt.as_mut().ok_or(Error::SaltNotCommitted)?;
+            if current_block < committed_salt.reveal_block {
+                return Err(Error::DrawNotDue {
+                    due_block: committed_salt.reveal_block,
+                    current_block: current_block,
+                }.into());
+            }
+            if current_block > committed_salt.reveal_block {
+                return Err(Error::SaltRevealMissed {
+                    reveal_block: committed_salt.reveal_block,
+                    current_block: current_block,
+                }.into());
+            }
+            if Self::salt_commitment(salt) != committed_salt.commitment {
+                return Err(Error::CommitmentMismatch.into());
+            }
+            committed_salt.salt = Some(salt);
+            committed_salt.nonce = nonce;
+
+            // The runtime randomness of the reveal block for the draw
+            #[cfg(feature = "randomness-extension")]
+            {
+                let mut subject = <hash::Keccak256 as hash::HashOutput>::Type::default();
+                ink::env::hash_encoded::<hash::Keccak256, _>(&(lottery_id, draw_number, current_block), &mut subject);
+                committed_salt.entropy = crate::chain_extension::fetch_random(subject);
+            }
+            #[cfg(not(feature = "randomness-extension"))]
+            ink::env::hash_encoded::<hash::Keccak256, _>(&self.env().block_timestamp(), &mut committed_salt.entropy);
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::SaltRevealed {
+                draw_number: draw_number,
+                salt: salt,
+            });
+            self.process_draw(lottery_id, draw_number)
+        }
+
+        /// Returns the commitment to a salt, see `open_draw_with_commitment`
+        pub fn salt_commitment(salt: [u8; 32]) -> [u8; 32] {
+            let mut commitment = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<hash::Keccak256, _>(&salt, &mut commitment);
+            commitment
+        }
+
+        /// Preview process draw
+        ///
+        /// 1. Only the operator or the delegate of the draw can preview the processing,
+        ///    the bettors must not learn the winning number while they can bet.
+        /// 2. Returns the status of the draw and the success messages `process_draw` would
+        ///    emit at the current block without committing them: the roll-over of a draw
+        ///    without bets, the refund of the stakes, the commitment of a commit-reveal
+        ///    draw or the winning number drawn from the randomness source.
+        #[ink(message)]
+        pub fn preview_process_draw(&self, lottery_id: u32, draw_number: u32) -> Result<ProcessPreview, ContractError> {
+            let caller = self.env().caller();
+            let next_starting_block = self.lottery(lottery_id)?.lottery_setup.next_starting_block;
+            if !self.manages_draw(lottery_id, draw_number, caller) {
+                return Err(Error::BadOrigin.into());
+            }
+            self.check_process_draw(lottery_id, draw_number)?;
+
+            let draw = self.draw(lottery_id, draw_number)?;
+            let (status, event) = match self.process_outcome(lottery_id, &draw)? {
+                ProcessOutcome::RollOver => (DrawStatus::Close, Success::DrawRolledOver {
+                    draw_number: draw_number,
+                    next_opening_block: next_starting_block + draw.opening_blocks,
+                }),
+                ProcessOutcome::Refund => {
+                    let bets = self.expand_bets(lottery_id, &draw, |owner| owner);
+                    let (payouts, carried) = self.plan_refund(lottery_id, &draw, &bets);
+                    (DrawStatus::Close, Success::StakesRefunded {
+                        draw_number: draw_number,
+                        bets: payouts.len() as u32,
+                        amount: payouts.iter().map(|p| p.amount).sum(),
+                        carried: carried.len() as u32,
+                    })
                 }
+                ProcessOutcome::Commit => {
+                    draw.result.as_ref().ok_or(Error::ResultNotCommitted)?;
+                    (DrawStatus::Processing, Success::DrawProcessed {
+                        draw_number: draw_number,
+                        winning_number: 0,
+                    })
+                }
+                ProcessOutcome::Draw => (DrawStatus::Processing, Success::DrawProcessed {
+                    draw_number: draw_number,
+                    winning_number: self.random_winning_number(lottery_id, draw_number)?,
+                }),
             };
 
+            Ok(ProcessPreview {
+                draw_number: draw_number,
+                status: status,
+                events: vec![event],
+            })
+        }
+
+        /// Override draw
+        ///
+        /// 1. The operator can override the winning number of the draw during the processing period.
+        #[ink(message)]
+        pub fn override_draw(&mut self, lottery_id: u32, draw_number: u32,
+            winning_number: u16) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // The winning number must be a number that can be bet on
+            Self::check_bet_number(winning_number)?;
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            // A committed result can't be changed
+            if draw.result.is_some() {
+                return Err(Error::CommitmentMismatch.into());
+            }
+
             // Check if draw status is Processing (Override is only after random winning number is generated)
             if draw.status == DrawStatus::Processing {
 
@@ -687,428 +2149,5188 @@ mod lottery {
                 draw.winning_number = winning_number;
 
             } else {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::DrawNotProcessing),
-                });
-                return Ok(());
+                return Err(Error::DrawNotProcessing.into());
             }
+            self.save_draw(lottery_id, &draw);
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::DrawProcessed),
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawProcessed {
+                draw_number: draw_number,
+                winning_number: winning_number,
+            });
+            Ok(())
+        }
+
+        /// Commit the result of a draw
+        ///
+        /// 1. Only the operator or the delegate of the draw can commit, in a commit-reveal
+        ///    lottery and while the draw is open.  A new commitment replaces the last one.
+        /// 2. The commitment is the Keccak256 hash of the SCALE encoded `(number, salt)`,
+        ///    see `result_commitment`.  The draw can't be processed without it.
+        #[ink(message)]
+        pub fn commit_result(&mut self, lottery_id: u32, draw_number: u32,
+            commitment: [u8; 32]) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if !self.manages_draw(lottery_id, draw_number, caller) {
+                return Err(Error::BadOrigin.into());
+            }
+            if lottery.lottery_setup.randomness_source != RandomnessSource::CommitReveal {
+                return Err(Error::ResultNotCommitted.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Open {
+                return Err(Error::DrawClosed.into());
+            }
+            draw.result = Some(ResultCommitment {
+                commitment: commitment,
+                reveal_block: 0,
+                salt: None,
+            });
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::ResultCommitted {
+                draw_number: draw_number,
+                commitment: commitment,
+            });
+            Ok(())
+        }
+
+        /// Publish the result of a draw
+        ///
+        /// 1. Only the operator or the delegate of the draw can publish the result of a
+        ///    processed draw, `REVEAL_DELAY_BLOCKS` after processing.
+        /// 2. The number and the salt must match the commitment.  The bonus number is
+        ///    derived from both, so it is committed too.
+        #[ink(message)]
+        pub fn publish_result(&mut self, lottery_id: u32, draw_number: u32,
+            number: u16, salt: [u8; 32]) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let current_block = self.env().block_number();
+            Self::check_bet_number(number)?;
+            self.lottery(lottery_id)?;
+            if !self.manages_draw(lottery_id, draw_number, caller) {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Processing {
+                return Err(Error::DrawNotProcessing.into());
+            }
+            let result = draw.result.as_mut().ok_or(Error::ResultNotCommitted)?;
+            if result.salt.is_some() {
+                return Err(Error::DrawProcessing.into());
+            }
+            if result.reveal_block > current_block {
+                return Err(Error::DrawNotDue {
+                    due_block: result.reveal_block,
+                    current_block: current_block,
+                }.into());
+            }
+            if Self::result_commitment(number, salt) != result.commitment {
+                return Err(Error::CommitmentMismatch.into());
+            }
+            result.salt = Some(salt);
+
+            // The bonus number is derived from the published result
+            let mut output = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<hash::Keccak256, _>(&(salt, number), &mut output);
+            let raw = u16::from_le_bytes([output[0], output[1]]);
+            let bonus_number = (raw % (MAX_BET_NUMBER - MIN_BET_NUMBER + 1)) + MIN_BET_NUMBER;
+            draw.winning_number = number;
+            draw.bonus_number = bonus_number;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::ResultPublished {
+                draw_number: draw_number,
+                winning_number: number,
+                bonus_number: bonus_number,
             });
             Ok(())
-        }        
+        }
+
+        /// Returns the commitment to a winning number and a salt, see `commit_result`
+        pub fn result_commitment(number: u16, salt: [u8; 32]) -> [u8; 32] {
+            let mut commitment = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<hash::Keccak256, _>(&(number, salt), &mut commitment);
+            commitment
+        }
 
         /// Add to the draw's jackpot balance
-        /// 
+        ///
         /// 1. Make sure to transfer the equivalent asset balance to the contract address
         /// 2. Can only be called by the operator
         /// 3. The draw must be closed.
         #[ink(message)]
-        pub fn add_draw_jackpot(&mut self, draw_number: u32,
-            jackpot: u128) -> Result<(), Error> {
+        pub fn add_draw_jackpot(&mut self, lottery_id: u32, draw_number: u32,
+            jackpot: u128) -> Result<(), ContractError> {
 
             // Check if operator
             let caller = self.env().caller();
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
 
             // Check if draw exist
-            let draw = match self.draws.iter_mut().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
+            let mut draw = self.draw(lottery_id, draw_number)?;
 
             // Check if draw status is Close
             if draw.status == DrawStatus::Close {
                 // Add the transferred value to the existing jackpot
                 draw.jackpot += jackpot;
             } else {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::DrawNotClosed),
-                });
-                return Ok(());
+                return Err(Error::DrawNotClosed.into());
             }
+            let total_jackpot = draw.jackpot;
+            self.save_draw(lottery_id, &draw);
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::JackpotAdded),
+            // The jackpot is held by the vault if the lottery has one
+            self.deposit_jackpot(lottery_id, draw_number, jackpot)?;
+
+            self.emit_success(lottery_id, Some(draw_number), Success::JackpotAdded {
+                draw_number: draw_number,
+                amount: jackpot,
+                jackpot: total_jackpot,
             });
 
             Ok(())
         }
 
-        /// Close draw
-        /// 
-        /// 1. Only the operator can close the draw.
-        /// 2. Only processed draws can be closed.
-        /// 3. The block number must be greater than the lottery starting block plus the
-        ///    draw blocks closing.
-        /// 4. The closing of the draw calls on the following process:
-        ///    4.1. Search for the winners
-        ///    4.2. Calculate the shares of the jackpot and upline percentage.  Only given
-        ///         to upline that bets on the current draw.
-        ///    4.3. Transfer the balance to the bettors and its upline who actively bets
-        ///    4.4. Update the status of the draw.
-        ///    4.5. Delete all bets
-        /// 5. During only this period (closing) the app should display the winning number
-        #[ink(message)]
-        pub fn close_draw(&mut self, draw_number: u32) -> Result<(), ContractError> {
+        /// Add to the draw's rebate pool
+        ///
+        /// 1. Only the operator can boost the rebate of a draw, e.g., as a promotion.
+        /// 2. The amount is pulled from the operator like a stake: transferred with the
+        ///    call (native) or from the approval or allowance of the operator.
+        /// 3. The rebate is shared among the bettors when the draw closes.
+        #[ink(message, payable)]
+        pub fn top_up_rebate(&mut self, lottery_id: u32, draw_number: u32,
+            amount: u128) -> Result<(), ContractError> {
 
             // Check if operator
             let caller = self.env().caller();
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
 
-            // Check if the draw exist
-            let draw = match self.draws.iter().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
 
-            // The current block must be greater or equal to the draw closing blocks.
-            let current_block: u32 = self.env().block_number();
-            let draw_closing_blocks: u32 = self.lottery_setup.starting_block + draw.opening_blocks;
-            if draw_closing_blocks > current_block  {
-                self.env().emit_event(LotteryEvent {
-                    operator: caller,
-                    status: LotteryStatus::EmitError(Error::InvalidBlock),
-                });
-                return Ok(());
-            }  
+            self.collect(lottery_id, caller, amount)?;
 
-            // Get draw for editing
-            let draw = match self.draws.iter_mut().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
-            
-            // Get the winners
-            let mut winners: Vec<Winner> = draw
-                .bets
-                .iter()
-                .filter(|b| b.bet_number == draw.winning_number)
-                .map(|b| Winner {
-                    draw_number: draw.draw_number,
-                    bettor: b.bettor,
-                    upline: b.upline,
-                    bet_number: b.bet_number,
-                    tx_hash: b.tx_hash.clone(),
-                    bettor_share: 0,
-                    upline_share: 0,
-                })
-                .collect();         
-            
-            // Count the number of winners
-            let count_winners = winners.len() as u128;
+            draw.rebate = draw.rebate.saturating_add(amount);
+            let rebate = draw.rebate;
+            self.save_draw(lottery_id, &draw);
 
-            // Distribute the share of the jackpot to the winners
-            if count_winners > 0 {
-                let jackpot_share   = draw.jackpot * 90 / 100;
-                let upline_share   = draw.jackpot * 10 / 100;
-
-                for w in winners.iter_mut() {
-                    w.bettor_share = jackpot_share / count_winners;
-                    w.upline_share = upline_share / count_winners;
-                }  
-
-                // Save the winners here
-                draw.winners = winners;           
-
-                // Drop the mutable draw to start the transfer
-                let draw = self.draws.iter()
-                    .find(|d| d.draw_number == draw_number)
-                    .ok_or(ContractError::Internal(Error::DrawNotFound))?; 
-
-                // Transfer the balances of the winners and the upline
-                for winner in draw.winners.iter() {
-                    // Winners
-                    self.env()
-                        .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                            id: self.lottery_setup.asset_id,
-                            target: winner.bettor.into(),
-                            amount: winner.bettor_share,
-                        }))
-                        .map_err(|_| RuntimeError::CallRuntimeFailed)?;                
-
-                    // Upline
-                    if draw.bets.iter().find(|b| b.bettor == winner.upline).is_none() {
-                        // If the upline is not actively betting the share will go to the operator
-                        self.env()
-                            .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                                id: self.lottery_setup.asset_id,
-                                target: self.lottery_setup.operator.into(),
-                                amount: winner.upline_share,
-                            }))
-                            .map_err(|_| RuntimeError::CallRuntimeFailed)?;    
-                    } else {
-                        // If the upline is actively betting
-                        self.env()
-                            .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                                id: self.lottery_setup.asset_id,
-                                target: winner.upline.into(),
-                                amount: winner.upline_share,
-                            }))
-                            .map_err(|_| RuntimeError::CallRuntimeFailed)?;       
-                    }
-                } 
-            } else {
-                // If there are no winners in the current draw make sure to clean up the winner array
-                draw.winners = Vec::new();
-            }
+            self.emit_success(lottery_id, Some(draw_number), Success::RebateToppedUp {
+                draw_number: draw_number,
+                amount: amount,
+                rebate: rebate,
+            });
 
-            // Distribute the shares of the rebate to the bettors.
-            //
-            // Drop the mutable draw to start the transfer
-            let draw = self.draws.iter()
-                .find(|d| d.draw_number == draw_number)
-                .ok_or(ContractError::Internal(Error::DrawNotFound))?;             
-
-            // Count the bettors
-            let count_bettors = draw.bets.len() as u128;
-
-            if count_bettors > 0 {
-                // Rebate share per bet
-                let bettor_share = draw.rebate / count_bettors;
-
-                for bet in draw.bets.iter() {
-                    // Bettors
-                    self.env()
-                        .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                            id: self.lottery_setup.asset_id,
-                            target: bet.bettor.into(),
-                            amount: bettor_share,
-                        }))
-                        .map_err(|_| RuntimeError::CallRuntimeFailed)?;   
-                }
+            Ok(())
+        }
+
+        /// Set the payout strategy of a draw
+        ///
+        /// 1. Only the dev can register a payout strategy.
+        /// 2. The draw must be closed, i.e., the prize structure can't change while bets
+        ///    are accepted or processed.
+        /// 3. The strategy contract implements `PayoutStrategy`, `None` restores the
+        ///    default split of the jackpot.
+        #[ink(message)]
+        pub fn set_payout_strategy(&mut self, lottery_id: u32, draw_number: u32,
+            strategy: Option<AccountId>) -> Result<(), ContractError> {
+
+            // Check if dev
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
             }
 
-            // Change the status of the draw from open to close
-            let draw = match self.draws.iter_mut().find(|d| d.draw_number == draw_number) {
-                Some(d) => d,
-                None => {
-                    self.env().emit_event(LotteryEvent {
-                        operator: caller,
-                        status: LotteryStatus::EmitError(Error::DrawNotFound),
-                    });
-                    return Ok(());
-                }
-            };
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
 
-            // Clean the jackpot after we distribute it to the winners of the current draw
-            if draw.winners.len() > 0 {
-                draw.jackpot = 0;
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
             }
-            // All rebate will be distributed to all bettors as we close the draw 
-            draw.rebate = 0;
-            // Clean up the bets
-            draw.bets = Vec::new();
-            // Close the draw
-            draw.status = DrawStatus::Close;
-            draw.is_open = false;
+            draw.payout_strategy = strategy;
+            self.save_draw(lottery_id, &draw);
 
-            self.env().emit_event(LotteryEvent {
-                operator: caller,
-                status: LotteryStatus::EmitSuccess(Success::DrawClosed),
+            self.emit_success(lottery_id, Some(draw_number), Success::PayoutStrategySet {
+                draw_number: draw_number,
+                strategy: strategy,
             });
-            Ok(())
 
+            Ok(())
         }
 
-        /// Bets
-        /// ----
-        /// All functions related to bets.
-        
-        /// Add a bet
-        /// 
-        /// 1. Anyone can place a bet on an open draw
-        /// 2. Upon betting the bet amount is already distributed and transferred to the following:
-        ///    2.1. 50% will go to the jackpot where it will be split into the following:
-        ///         2.1.1. Jackpot share is 90%
-        ///         2.1.2. Upline share of the jackpot is 10%
-        ///    2.2. 20% will go to the operator
-        ///    2.3. 10% will go to the developer
-        ///    2.4. 10% will go to the rebate (all bettors)
-        ///    2.5. 10% will go to the affiliate (immediately the active upline will get 10%)
-        #[ink(message)]
-        pub fn add_bet(&mut self, draw_number: u32, 
-            bet_number: u16, 
-            bettor: AccountId, 
-            upline: AccountId, 
-            tx_hash: Vec<u8>) -> Result<(), ContractError> {
-
-            let caller = self.env().caller();
-
-            // Add bet is called at the server by the operator as soon as tx_hash transfer 
-            // of bet has been verified.
-            if caller != self.lottery_setup.operator {
-                self.env().emit_event(LotteryEvent {
-                    operator: self.lottery_setup.operator,
-                    status: LotteryStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+        /// Set the stake range of a draw
+        ///
+        /// 1. Only the operator can set the range of the stakes of a draw.
+        /// 2. The draw must be closed.
+        /// 3. Bets stake any amount from `min_bet` to `max_bet` and all shares and prizes
+        ///    are computed from the stake of the bet.  A `max_bet` of zero restores the
+        ///    fixed bet amount `min_bet`.
+        #[ink(message)]
+        pub fn set_bet_range(&mut self, lottery_id: u32, draw_number: u32,
+            min_bet: u128, max_bet: u128) -> Result<(), ContractError> {
 
-            // Find the draw number
-            let draw = self.draws.iter()
-                .find(|d| d.draw_number == draw_number)
-                .ok_or(ContractError::Internal(Error::DrawNotFound))?;        
-
-            // A draw that the status is not open and the flag is false is considered close draw.
-            if draw.status != DrawStatus::Open && !draw.is_open {
-                self.env().emit_event(LotteryEvent {
-                    operator: self.lottery_setup.operator,
-                    status: LotteryStatus::EmitError(Error::DrawClosed),
-                });
-                return Ok(());
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
             }
 
-            // Shares
-            let jackpot_share   = draw.bet_amount * 50 / 100;
-            let dev_share       = draw.bet_amount * 10 / 100;
-            let operator_share  = draw.bet_amount * 20 / 100;
-            let rebate_share    = draw.bet_amount * 10 / 100;
-            let affiliate_share = draw.bet_amount * 10 / 100;
+            if min_bet == 0 || (max_bet != 0 && max_bet < min_bet) {
+                return Err(Error::InvalidBetRange {
+                    min_bet: min_bet,
+                    max_bet: max_bet,
+                }.into());
+            }
+            Self::check_bet_unit(&lottery.lottery_setup, min_bet)?;
+            Self::check_bet_unit(&lottery.lottery_setup, max_bet)?;
 
-            // Transfer operator's share
-            self.env()
-                .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                    id: self.lottery_setup.asset_id,
-                    target: self.lottery_setup.operator.into(),
-                    amount: operator_share,
-                }))
-                .map_err(|_| RuntimeError::CallRuntimeFailed)?;
-
-            // Transfer dev's share
-            self.env()
-                .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                    id: self.lottery_setup.asset_id,
-                    target: self.lottery_setup.dev.into(),
-                    amount: dev_share,
-                }))
-                .map_err(|_| RuntimeError::CallRuntimeFailed)?;
-
-
-            // Transfer affiliate share.
-            // This will require that the affiliate upline already betted, if not
-            // the share will be sent to the operator.
-            let mut upline_found: Option<AccountId> = None;
-
-            for b in &draw.bets {
-                if b.bettor == upline {
-                    upline_found = Some(b.bettor);
-                    break;
-                }
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
             }
+            if draw.compact_bets && max_bet > 0 {
+                return Err(Error::CompactBetsUnsupported.into());
+            }
+            draw.bet_amount = min_bet;
+            draw.max_bet_amount = max_bet;
+            self.save_draw(lottery_id, &draw);
 
-            match upline_found {
-                Some(valid_upline) => {
-                    // Upline exists, send affiliate share to the upline
-                    self.env()
-                        .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                            id: self.lottery_setup.asset_id,
-                            target: valid_upline.into(),
-                            amount: affiliate_share,
-                        }))
-                        .map_err(|_| RuntimeError::CallRuntimeFailed)?;
-                }
-                None => {
-                    // Upline not found, send affiliate share to the operator
-                    self.env()
-                        .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                            id: self.lottery_setup.asset_id,
-                            target: self.lottery_setup.operator.into(),
-                            amount: affiliate_share,
-                        }))
-                        .map_err(|_| RuntimeError::CallRuntimeFailed)?;
-                }
-            };
+            self.emit_success(lottery_id, Some(draw_number), Success::BetRangeSet {
+                draw_number: draw_number,
+                min_bet: min_bet,
+                max_bet: max_bet,
+            });
 
-            // Add the bet
-            let draw = self.draws.iter_mut()
-                .find(|d| d.draw_number == draw_number)
-                .ok_or(ContractError::Internal(Error::DrawNotFound))?;
-            
-            let new_bet = Bet {
-                bettor: bettor,
-                upline: upline,
-                bet_number: bet_number,
-                tx_hash: tx_hash,
-            };
-            
-            draw.bets.push(new_bet);
+            Ok(())
+        }
 
-            // Compute for jackpot and rebate, these shares are distributed during closing 
-            // 1. jackpot are given to the winners in equal shares
-            // 2. rebate are given to all bettors in equal shares 
-            draw.jackpot += jackpot_share;
-            draw.rebate += rebate_share; 
+        /// Set the early-bird bonus of a draw
+        ///
+        /// 1. Only the operator can set the early-bird bonus, it is funded from its share.
+        /// 2. The draw must be closed.
+        /// 3. `percentage` of the stake of each of the first `bets` bets goes to a bonus
+        ///    pool shared equally by those bets on settlement.  Zero bets disables the
+        ///    bonus.
+        /// 4. The percentage can't exceed the operator share left after the affiliate
+        ///    percentage of the draw.
+        #[ink(message)]
+        pub fn set_early_bird(&mut self, lottery_id: u32, draw_number: u32,
+            bets: u32, percentage: u8) -> Result<(), ContractError> {
 
-            self.env().emit_event(LotteryEvent {
-                operator: self.lottery_setup.operator,
-                status: LotteryStatus::EmitSuccess(Success::BetAdded),
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let lottery_setup = lottery.lottery_setup.clone();
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            let affiliate_percentage = draw.affiliate_percentage.unwrap_or(lottery_setup.affiliate_percentage);
+            Self::check_bonus_percentage(percentage,
+                Self::operator_margin(&lottery_setup.shares, affiliate_percentage))?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            if draw.compact_bets && bets > 0 {
+                return Err(Error::CompactBetsUnsupported.into());
+            }
+            draw.early_bird_bets = bets;
+            draw.early_bird_percentage = percentage;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::EarlyBirdSet {
+                draw_number: draw_number,
+                bets: bets,
+                percentage: percentage,
             });
 
             Ok(())
-        }        
+        }
 
-        /// Getter functions
-        /// 
-        /// These functions returns storage data 
+        /// Set the bet cutoff of a draw
+        ///
+        /// 1. Only the operator can set the cutoff of a draw.
+        /// 2. The draw must be closed.
+        /// 3. No bets are accepted in the last `cutoff_blocks` blocks before the processing
+        ///    block, so late bets can't exploit the randomness inputs known by then.  The
+        ///    cutoff can't cover the whole betting window.
+        #[ink(message)]
+        pub fn set_bet_cutoff(&mut self, lottery_id: u32, draw_number: u32,
+            cutoff_blocks: u32) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            if cutoff_blocks >= draw.processing_blocks - draw.opening_blocks {
+                return Err(Error::EmptyBettingWindow.into());
+            }
+            draw.cutoff_blocks = cutoff_blocks;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::BetCutoffSet {
+                draw_number: draw_number,
+                cutoff_blocks: cutoff_blocks,
+            });
 
-        /// Returns lottery setup
+            Ok(())
+        }
+
+        /// Set the compact bets of a draw
+        ///
+        /// 1. Only the operator can set the compact bets of a draw.
+        /// 2. The draw must be closed.
+        /// 3. A draw where every bet stakes the bet amount can keep only the owners of its
+        ///    bets per number instead of the full bets.  The bets have no transaction
+        ///    hash, bundle or origin and the upline of a bet is the registered referrer of
+        ///    its owner.
+        /// 4. Stake ranges, promotional draws, the early-bird bonus (which depends on the
+        ///    order of the bets), bets placed on behalf of another account and XCM bets
+        ///    need the full bets.
         #[ink(message)]
-        pub fn get_lottery_setup(&self) -> LotterySetup {
-            self.lottery_setup.clone()
+        pub fn set_compact_bets(&mut self, lottery_id: u32, draw_number: u32,
+            compact_bets: bool) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            if compact_bets && (draw.max_bet_amount > 0 || draw.promotional || draw.early_bird_bets > 0) {
+                return Err(Error::CompactBetsUnsupported.into());
+            }
+            draw.compact_bets = compact_bets;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::CompactBetsSet {
+                draw_number: draw_number,
+                compact_bets: compact_bets,
+            });
+
+            Ok(())
         }
 
-        /// Return all the draws
+        /// Set the minimum participation of a draw
+        ///
+        /// 1. Only the operator can set the minimum participation of a draw.
+        /// 2. The draw must be closed.
+        /// 3. A cycle needs at least `min_bets` bets and `min_pot` total stakes to be
+        ///    drawn.  Until then the shares of its bets are withheld, if the minimum is
+        ///    not met by the processing block all stakes are refunded.  Zero disables
+        ///    either minimum.
+        /// 4. With `carry_forward` the bets are carried into the next cycle of the draw
+        ///    instead of refunded, unless their owner chose otherwise (`set_carry_forward`).
         #[ink(message)]
-        pub fn get_draws(&self) -> Vec<Draw> {
-            self.draws.clone()
+        pub fn set_min_participation(&mut self, lottery_id: u32, draw_number: u32,
+            min_bets: u32, min_pot: u128, carry_forward: bool) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            draw.participation.min_bets = min_bets;
+            draw.participation.min_pot = min_pot;
+            draw.participation.carry_forward = carry_forward;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::MinParticipationSet {
+                draw_number: draw_number,
+                min_bets: min_bets,
+                min_pot: min_pot,
+                carry_forward: carry_forward,
+            });
+
+            Ok(())
         }
 
-        /// Return all the bets
+        /// Set the delegate of a draw
+        ///
+        /// 1. Only the operator can delegate a draw, e.g., to a regional team.
+        /// 2. The delegate can open, process and close the draw and add bets to it, but
+        ///    nothing else of the lottery.  `None` revokes the delegation.
         #[ink(message)]
-        pub fn get_bets(&self, draw_number:u32) -> Vec<Bet> {
-            self.draws
-                .iter()
-                .find(|d| d.draw_number == draw_number)
-                .map(|d| d.bets.clone())
-                .unwrap_or_default()
+        pub fn set_draw_delegate(&mut self, lottery_id: u32, draw_number: u32,
+            delegate: Option<AccountId>) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            draw.delegate = delegate;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawDelegateSet {
+                draw_number: draw_number,
+                delegate: delegate,
+            });
+
+            Ok(())
         }
-        
+
+        /// Set the liability cap of a draw
+        ///
+        /// 1. Only the operator can cap the liability of a fixed-odds draw.
+        /// 2. The draw must be closed.
+        /// 3. Bets whose number would owe more than the cap, or more than the prize
+        ///    reserve of the draw, are rejected.  Zero only limits it to the reserve.
+        #[ink(message)]
+        pub fn set_liability_cap(&mut self, lottery_id: u32, draw_number: u32,
+            liability_cap: u128) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            draw.liability_cap = liability_cap;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::LiabilityCapSet {
+                draw_number: draw_number,
+                liability_cap: liability_cap,
+            });
+
+            Ok(())
+        }
+
+        /// Set the prize divisions of a draw
+        ///
+        /// 1. Only the dev can change the prize structure of a draw.
+        /// 2. The draw must be closed, i.e., the prize structure can't change while bets
+        ///    are accepted or processed.
+        /// 3. The percentages of the divisions can't exceed 100% of the jackpot.
+        #[ink(message)]
+        pub fn set_division_shares(&mut self, lottery_id: u32, draw_number: u32,
+            division_shares: DivisionShares) -> Result<(), ContractError> {
+
+            // Check if dev
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let total = division_shares.first as u16 + division_shares.second as u16 + division_shares.third as u16;
+            if total > 100 {
+                return Err(Error::InvalidDivisionShares { total: total }.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            draw.division_shares = division_shares.clone();
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::DivisionSharesSet {
+                draw_number: draw_number,
+                first: division_shares.first,
+                second: division_shares.second,
+                third: division_shares.third,
+            });
+
+            Ok(())
+        }
+
+        /// Set the prize tiers of a draw
+        ///
+        /// 1. Only the dev can change the prize structure of a draw, and only while the
+        ///    draw is closed.
+        /// 2. A tier matches 1 to `MAX_TIER_DIGITS` trailing digits of the winning number,
+        ///    e.g., the exact match, the last two digits and the last digit, every number
+        ///    of digits in one tier at most.  The percentages can't exceed 100% of the
+        ///    jackpot.
+        /// 3. A bet wins in the tier with the most digits it matches.  The tiers replace
+        ///    the divisions of a pari-mutuel draw, no tiers restores the divisions.
+        #[ink(message)]
+        pub fn set_prize_tiers(&mut self, lottery_id: u32, draw_number: u32,
+            prize_tiers: Vec<PrizeTier>) -> Result<(), ContractError> {
+
+            // Check if dev
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let mut prize_tiers = prize_tiers;
+            prize_tiers.sort_by(|a, b| b.digits.cmp(&a.digits));
+            if prize_tiers.iter().any(|t| t.digits == 0 || t.digits > MAX_TIER_DIGITS)
+                || prize_tiers.windows(2).any(|t| t[0].digits == t[1].digits) {
+                return Err(Error::InvalidPrizeTiers.into());
+            }
+            let total: u16 = prize_tiers.iter().map(|t| t.percentage as u16).sum();
+            if total > 100 {
+                return Err(Error::InvalidDivisionShares { total: total }.into());
+            }
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            let tiers = prize_tiers.len() as u8;
+            draw.prize_tiers = prize_tiers;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::PrizeTiersSet {
+                draw_number: draw_number,
+                tiers: tiers,
+            });
+
+            Ok(())
+        }
+
+        /// Set the revenue split of a lottery
+        ///
+        /// 1. Only the dev can set the shares of every stake and of every prize.
+        /// 2. The lottery must be stopped.
+        /// 3. The stake shares must sum up to 100%.  The jackpot share must leave room
+        ///    for the largest progressive and insurance slices.
+        /// 4. The affiliate percentage, the bulk discount and the bonuses of the lottery
+        ///    and of its draws are funded from the operator share, so they must still
+        ///    fit in it.
+        #[ink(message)]
+        pub fn set_shares(&mut self, lottery_id: u32, shares: Shares) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if lottery.lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
+            }
+
+            let total = shares.jackpot as u16 + shares.dev as u16 + shares.rebate as u16 + shares.operator as u16;
+            if total != 100
+                || shares.jackpot < MAX_PROGRESSIVE_PERCENTAGE + MAX_INSURANCE_PERCENTAGE
+                || shares.winner > 100 {
+                return Err(Error::InvalidShares.into());
+            }
+            let lottery_setup = LotterySetup {
+                shares: shares.clone(),
+                ..lottery.lottery_setup.clone()
+            };
+            self.check_operator_shares(lottery_id, &lottery_setup)?;
+
+            self.update_lottery(lottery_id, |l| l.lottery_setup = lottery_setup)?;
+
+            self.emit_success(lottery_id, None, Success::SharesSet {
+                jackpot: shares.jackpot,
+                dev: shares.dev,
+                rebate: shares.rebate,
+                operator: shares.operator,
+                winner: shares.winner,
+            });
+            Ok(())
+        }
+
+        /// Set the progressive jackpot percentage
+        ///
+        /// 1. Only the dev can set the percentage of every bet sent to the progressive
+        ///    jackpot, it is taken from the jackpot share of the bet.
+        /// 2. The lottery must be stopped.
+        /// 3. The percentage can't exceed `MAX_PROGRESSIVE_PERCENTAGE`, zero stops the
+        ///    contributions but keeps the pot for the next hit of a bonus number.
+        #[ink(message)]
+        pub fn set_progressive_percentage(&mut self, lottery_id: u32,
+            percentage: u8) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if lottery.lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
+            }
+            if percentage > MAX_PROGRESSIVE_PERCENTAGE {
+                return Err(Error::InvalidProgressivePercentage {
+                    maximum: MAX_PROGRESSIVE_PERCENTAGE,
+                    actual: percentage,
+                }.into());
+            }
+
+            lottery.progressive.percentage = percentage;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::ProgressivePercentageSet {
+                percentage: percentage,
+            });
+            Ok(())
+        }
+
+        /// Set the insurance fund percentage
+        ///
+        /// 1. Only the dev can set the percentage of every bet sent to the insurance fund,
+        ///    it is taken from the jackpot share of the bet.
+        /// 2. The lottery must be stopped.
+        /// 3. The percentage can't exceed `MAX_INSURANCE_PERCENTAGE`, zero stops the
+        ///    contributions but keeps the fund.
+        #[ink(message)]
+        pub fn set_insurance_percentage(&mut self, lottery_id: u32,
+            percentage: u8) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if lottery.lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
+            }
+            if percentage > MAX_INSURANCE_PERCENTAGE {
+                return Err(Error::InvalidInsurancePercentage {
+                    maximum: MAX_INSURANCE_PERCENTAGE,
+                    actual: percentage,
+                }.into());
+            }
+
+            lottery.insurance.percentage = percentage;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::InsurancePercentageSet {
+                percentage: percentage,
+            });
+            Ok(())
+        }
+
+        /// Top up the insurance fund
+        ///
+        /// 1. Make sure to transfer the equivalent asset balance to the contract address
+        /// 2. Can only be called by the dev
+        #[ink(message)]
+        pub fn top_up_insurance(&mut self, lottery_id: u32, amount: u128) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            lottery.insurance.balance = lottery.insurance.balance.saturating_add(amount);
+            lottery.insurance.topped_up = lottery.insurance.topped_up.saturating_add(amount);
+            let balance = lottery.insurance.balance;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::InsuranceToppedUp {
+                amount: amount,
+                balance: balance,
+            });
+            Ok(())
+        }
+
+        /// Withdraw from the insurance fund
+        ///
+        /// 1. Only the dev can withdraw, the amount is transferred to the recipient.
+        /// 2. The amount can't exceed the balance of the fund.
+        /// 3. The payout policies apply, a held withdrawal is released with the draw
+        ///    number zero.
+        #[ink(message)]
+        pub fn withdraw_insurance(&mut self, lottery_id: u32, amount: u128,
+            recipient: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if amount > lottery.insurance.balance {
+                return Err(Error::InsufficientInsurance {
+                    available: lottery.insurance.balance,
+                }.into());
+            }
+
+            lottery.insurance.balance -= amount;
+            lottery.insurance.withdrawn = lottery.insurance.withdrawn.saturating_add(amount);
+            let balance = lottery.insurance.balance;
+            self.save_lottery(lottery_id, &lottery);
+
+            // The withdrawal is held if its payout policy requires it
+            let payouts = self.hold_payouts(lottery_id, 0, ink::prelude::vec![Payout {
+                recipient: recipient,
+                role: PayoutRole::Insurance,
+                amount: amount,
+                destination: None,
+            }])?;
+            for p in payouts {
+                self.transfer(lottery_id, 0, p.recipient, p.role, p.amount)?;
+            }
+
+            self.emit_success(lottery_id, None, Success::InsuranceWithdrawn {
+                recipient: recipient,
+                amount: amount,
+                balance: balance,
+            });
+            Ok(())
+        }
+
+        /// Set the affiliate percentage of a draw
+        ///
+        /// 1. Only the operator can override the affiliate percentage, e.g., for a
+        ///    marketing campaign.
+        /// 2. The draw must be closed so all bets of a cycle pay the same shares.
+        /// 3. The percentage can't exceed `MAX_AFFILIATE_PERCENTAGE` nor the operator
+        ///    share less the early-bird bonus of the draw, `None` restores the percentage
+        ///    of the lottery setup.
+        #[ink(message)]
+        pub fn set_draw_affiliate_percentage(&mut self, lottery_id: u32, draw_number: u32,
+            affiliate_percentage: Option<u8>) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+            let lottery_setup = lottery.lottery_setup.clone();
+
+            // Check if draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+            draw.affiliate_percentage = affiliate_percentage;
+            Self::check_draw_shares(&draw, &lottery_setup)?;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawAffiliatePercentageSet {
+                draw_number: draw_number,
+                affiliate_percentage: affiliate_percentage,
+            });
+
+            Ok(())
+        }
+
+        /// Close draw
+        ///
+        /// 1. Only the operator or the delegate of the draw can close the draw, or the
+        ///    contract itself when scheduled.
+        /// 2. Only processed draws can be closed.
+        /// 3. The block number must be greater than the lottery starting block plus the
+        ///    draw blocks closing.
+        /// 4. The closing of the draw calls on the following process:
+        ///    4.1. Search for the winners
+        ///    4.2. Calculate the shares of the jackpot and upline percentage of every prize
+        ///         division.  Only given to upline that bets on the current draw.
+        ///    4.3. Update the winners and the status of the draw before any transfer.
+        ///    4.4. Transfer the balance to the bettors and its upline who actively bets
+        ///         The transfers are dispatched in a single `batch_all` runtime call, if
+        ///         any of them fails nothing is paid.  PSP22 transfers cannot be
+        ///         batched.  Failed transfers are kept in the draw and can be retried
+        ///         with `retry_payouts`.  Payouts held by their payout policy are paid
+        ///         by `approve_payouts` and `release_payouts`.  A lottery with claims
+        ///         only records the payouts, see `set_claim_payouts`.
+        ///    4.5. Delete all bets
+        /// 5. During only this period (closing) the app should display the winning number
+        /// 6. The last event of the closing is `DrawSettled` with the totals of the
+        ///    settlement: winners, jackpot and rebates paid, dust and queued payouts.
+        #[ink(message)]
+        pub fn close_draw(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+
+            // Check if operator, delegate or the contract itself through the scheduler
+            let caller = self.env().caller();
+            self.lottery(lottery_id)?;
+            if !self.manages_draw(lottery_id, draw_number, caller) && caller != self.env().account_id() {
+                return Err(Error::BadOrigin.into());
+            }
+
+            self.guarded(|lottery| lottery.settle_draw(lottery_id, draw_number))
+        }
+
+        /// Bets
+        /// ----
+        /// All functions related to bets.
+
+        /// Add a bet
+        ///
+        /// 1. Anyone can place a bet on an open draw.  The operator (or the delegate of the
+        ///    draw) records the bet once the transferred `amount` (tx_hash) is verified and
+        ///    it must equal the draw's bet amount.
+        /// 2. Upon betting the bet amount is already distributed and transferred to the
+        ///    following, by the shares of the lottery (see `set_shares`, the defaults in
+        ///    brackets):
+        ///    2.1. The jackpot share (50%) will go to the jackpot where it will be split
+        ///         into the following:
+        ///         2.1.1. Jackpot share is the winner share (90%)
+        ///         2.1.2. Upline share of the jackpot is the rest (10%)
+        ///    2.2. The operator share (30%) less the affiliate share will go to the operator
+        ///    2.3. The dev share (10%) will go to the developer
+        ///    2.4. The rebate share (10%) will go to the rebate (all bettors)
+        ///    2.5. The affiliate percentage of the draw (10% by default) will go to the
+        ///         affiliate (immediately the referrer of the bettor will get it)
+        #[ink(message)]
+        pub fn add_bet(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            bettor: AccountId,
+            upline: AccountId,
+            tx_hash: Vec<u8>,
+            amount: u128) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+
+            // Add bet is called at the server by the operator (or the delegate of the
+            // draw) as soon as tx_hash transfer of bet has been verified.
+            if !self.manages_draw(lottery_id, draw_number, caller) {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // The lottery may only accept bets whose stake arrived at the contract
+            if lottery.lottery_setup.verified_bets_only {
+                return Err(Error::UnverifiedBet.into());
+            }
+
+            self.record_bet(lottery_id, draw_number, bet_number, bettor, upline, tx_hash, amount, 0)
+        }
+
+        /// Place a bet
+        ///
+        /// 1. The caller bets on an open draw and the contract collects the stake itself,
+        ///    so the bet does not depend on the operator verifying a transfer:
+        ///    1.1. Native: the bet amount is transferred with the call.
+        ///    1.2. Assets: the bet amount is transferred from the approval of the caller
+        ///         to the contract (`approve_transfer`).
+        ///    1.3. PSP22: the bet amount is transferred from the allowance of the caller
+        ///         to the contract.
+        /// 2. The same rules and shares of `add_bet` apply.
+        #[ink(message, payable)]
+        pub fn place_bet(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            upline: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let bet_amount = self.collect_stake(lottery_id, draw_number, caller, 1, 0, None)?;
+
+            self.record_bet(lottery_id, draw_number, bet_number, caller, upline, Vec::new(), bet_amount, 0)
+        }
+
+        /// Place a bet with a stake
+        ///
+        /// 1. The caller stakes any amount within the stake range of the draw, the contract
+        ///    collects it like `place_bet`.
+        /// 2. The shares of the bet and its prize are computed from the stake.
+        #[ink(message, payable)]
+        pub fn place_bet_with_stake(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            upline: AccountId,
+            stake: u128) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let stake = self.collect_stake(lottery_id, draw_number, caller, 1, 0, Some(stake))?;
+
+            self.record_bet(lottery_id, draw_number, bet_number, caller, upline, Vec::new(), stake, 0)
+        }
+
+        /// Place bets
+        ///
+        /// 1. The caller buys a ticket for every number in one call, the contract collects
+        ///    the stake of all tickets like `place_bet`.
+        /// 2. If the lottery has a bulk discount and enough tickets are bought, every
+        ///    ticket is discounted.  The discount is taken from the operator share.
+        /// 3. Either all tickets are placed or none.
+        #[ink(message, payable)]
+        pub fn place_bets(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_numbers: Vec<u16>,
+            upline: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let tickets = bet_numbers.len() as u32;
+            if tickets == 0 {
+                return Err(Error::NoRecords.into());
+            }
+
+            let discount = self.bulk_discount(lottery_id, draw_number, tickets)?;
+            let bet_amount = self.collect_stake(lottery_id, draw_number, caller, tickets, discount, None)?;
+
+            for bet_number in bet_numbers {
+                self.record_bet(lottery_id, draw_number, bet_number, caller, upline, Vec::new(),
+                    bet_amount - discount, discount)?;
+            }
+            Ok(())
+        }
+
+        /// Place a bet on behalf of a user
+        ///
+        /// 1. Contracts, e.g., games and aggregators, bet for their users.  The caller pays
+        ///    the stake like `place_bet` and owns the bet, the beneficiary is the bettor.
+        /// 2. The affiliate relationship and the daily limit are those of the beneficiary.
+        /// 3. A winning bet is paid to the caller, which is then notified with
+        ///    `LotteryIntegrator::on_win` to credit the prize to the beneficiary.
+        /// 4. Draws with compact bets can't keep the caller as owner and refuse the bet.
+        #[ink(message, payable)]
+        pub fn place_bet_for(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            beneficiary: AccountId,
+            upline: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            self.check_full_bets(lottery_id, draw_number)?;
+            let bet_amount = self.collect_stake(lottery_id, draw_number, caller, 1, 0, None)?;
+
+            self.record_bet(lottery_id, draw_number, bet_number, beneficiary, upline, Vec::new(), bet_amount, 0)?;
+
+            // The caller owns the bet just added
+            self.update_last_bet(lottery_id, draw_number, |bet| bet.owner = caller)
+        }
+
+        /// Buy a bundle
+        ///
+        /// 1. The caller bets on the same numbers in every draw of the bundle with one
+        ///    payment, the price is the bet amount of every draw for every number.  The
+        ///    contract collects it like `place_bet`.
+        /// 2. The same rules and shares of `add_bet` apply to every bet and either all
+        ///    bets are placed or none.
+        /// 3. The bets are linked by the id of the bundle for the UI.  Draws with compact
+        ///    bets can't keep the link and refuse the bundle.
+        #[ink(message, payable)]
+        pub fn buy_bundle(&mut self, lottery_id: u32,
+            bet_numbers: Vec<u16>,
+            draw_numbers: Vec<u32>,
+            upline: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            if bet_numbers.is_empty() || draw_numbers.is_empty() {
+                return Err(Error::NoRecords.into());
+            }
+
+            // Price of the bundle
+            let mut price: u128 = 0;
+            for draw_number in draw_numbers.iter() {
+                self.check_full_bets(lottery_id, *draw_number)?;
+                let bet_amount = self.draw(lottery_id, *draw_number)?.bet_amount;
+                price = price.saturating_add(bet_amount.saturating_mul(bet_numbers.len() as u128));
+            }
+
+            // Nothing to collect, e.g., free promotional draws
+            if price > 0 || self.env().transferred_value() > 0 {
+                self.collect(lottery_id, caller, price)?;
+            }
+
+            self.bundle_seq += 1;
+            let bundle_id = self.bundle_seq;
+
+            for draw_number in draw_numbers.iter() {
+                for bet_number in bet_numbers.iter() {
+                    let bet_amount = self.draw(lottery_id, *draw_number)?.bet_amount;
+                    self.record_bet(lottery_id, *draw_number, *bet_number, caller, upline, Vec::new(),
+                        bet_amount, 0)?;
+
+                    // Link the bet just added to the bundle
+                    self.update_last_bet(lottery_id, *draw_number, |bet| bet.bundle_id = Some(bundle_id))?;
+                }
+            }
+
+            self.emit_success(lottery_id, None, Success::BundleBought {
+                bundle_id: bundle_id,
+                draws: draw_numbers.len() as u32,
+                tickets: (draw_numbers.len() * bet_numbers.len()) as u32,
+                price: price,
+            });
+            Ok(())
+        }
+
+        /// Create a voucher
+        ///
+        /// 1. Only the operator can create a redemption code, only its Keccak256 hash is
+        ///    stored so the code can be handed out off-chain.
+        /// 2. The value of the code is pulled from the operator like a stake, i.e., the
+        ///    credits are backed by the contract.
+        /// 3. A hash can only be used once.
+        #[ink(message, payable)]
+        pub fn create_voucher(&mut self, lottery_id: u32,
+            code_hash: [u8; 32],
+            value: u128) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+            if value == 0 {
+                return Err(Error::ZeroBetAmount.into());
+            }
+            if self.vouchers.contains(code_hash) {
+                return Err(Error::VoucherExists.into());
+            }
+
+            self.collect(lottery_id, caller, value)?;
+
+            self.vouchers.insert(code_hash, &Voucher {
+                lottery_id: lottery_id,
+                value: value,
+                redeemed_by: None,
+            });
+
+            self.emit_success(lottery_id, None, Success::VoucherCreated {
+                code_hash: code_hash,
+                value: value,
+            });
+            Ok(())
+        }
+
+        /// Claim a voucher
+        ///
+        /// 1. The caller commits to a code without revealing it, the claim is the hash
+        ///    of the code and the caller, see `voucher_claim`.
+        /// 2. The code can be redeemed by the caller from the next block on, i.e., a
+        ///    code seen in a pending `redeem_voucher` can't be claimed by another account
+        ///    in time.
+        /// 3. An account has one pending claim, a new claim replaces it.  The claim
+        ///    expires `VOUCHER_CLAIM_BLOCKS` blocks after it was made.
+        #[ink(message)]
+        pub fn claim_voucher(&mut self, lottery_id: u32, claim: [u8; 32]) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            self.lottery(lottery_id)?;
+
+            self.voucher_claims.insert(caller, &VoucherClaim {
+                claim: claim,
+                block_number: self.env().block_number(),
+            });
+
+            self.emit_success(lottery_id, None, Success::VoucherClaimed {
+                account: caller,
+                claim: claim,
+            });
+            Ok(())
+        }
+
+        /// Redeem a voucher
+        ///
+        /// 1. The caller reveals the code, its hash must match an unused voucher.
+        /// 2. The caller must have claimed the code with `claim_voucher` in an earlier
+        ///    block that is at most `VOUCHER_CLAIM_BLOCKS` blocks ago.  The claim is
+        ///    deleted once redeemed.
+        /// 3. The value of the voucher is credited to the caller in the lottery of the
+        ///    voucher and the code is marked as used.
+        /// 4. The credits pay for bets with `place_bet_with_credits`.
+        #[ink(message)]
+        pub fn redeem_voucher(&mut self, code: Vec<u8>) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            let mut code_hash = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_bytes::<hash::Keccak256>(&code, &mut code_hash);
+
+            let mut voucher = self.vouchers.get(code_hash).ok_or(Error::VoucherNotFound)?;
+            if voucher.redeemed_by.is_some() {
+                return Err(Error::VoucherRedeemed.into());
+            }
+
+            let claim = self.voucher_claims.get(caller)
+                .filter(|c| c.claim == Self::voucher_claim(code, caller))
+                .ok_or(Error::VoucherNotClaimed)?;
+            let current_block = self.env().block_number();
+            if claim.block_number >= current_block {
+                return Err(Error::VoucherClaimPending { redeemable_block: claim.block_number + 1 }.into());
+            }
+            let expiry_block = claim.block_number.saturating_add(VOUCHER_CLAIM_BLOCKS);
+            if current_block > expiry_block {
+                return Err(Error::VoucherClaimExpired { expiry_block: expiry_block }.into());
+            }
+            self.voucher_claims.remove(caller);
+
+            voucher.redeemed_by = Some(caller);
+            self.vouchers.insert(code_hash, &voucher);
+
+            let lottery_id = voucher.lottery_id;
+            let credits = self.credits.get((caller, lottery_id)).unwrap_or(0).saturating_add(voucher.value);
+            self.credits.insert((caller, lottery_id), &credits);
+
+            self.emit_success(lottery_id, None, Success::VoucherRedeemed {
+                code_hash: code_hash,
+                account: caller,
+                value: voucher.value,
+            });
+            Ok(())
+        }
+
+        /// Returns the claim of a code by an account, see `claim_voucher`
+        pub fn voucher_claim(code: Vec<u8>, account: AccountId) -> [u8; 32] {
+            let mut claim = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<hash::Keccak256, _>(&(code, account), &mut claim);
+            claim
+        }
+
+        /// Place a bet with credits
+        ///
+        /// 1. The bet amount of the draw is paid from the credits of the caller instead
+        ///    of a transfer.
+        /// 2. The same rules and shares of `add_bet` apply.
+        #[ink(message)]
+        pub fn place_bet_with_credits(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            upline: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let bet_amount = self.draw(lottery_id, draw_number)?.bet_amount;
+
+            let credits = self.credits.get((caller, lottery_id)).unwrap_or(0);
+            if credits < bet_amount {
+                return Err(Error::InsufficientCredits {
+                    available: credits,
+                }.into());
+            }
+            self.credits.insert((caller, lottery_id), &(credits - bet_amount));
+
+            self.record_bet(lottery_id, draw_number, bet_number, caller, upline, Vec::new(), bet_amount, 0)
+        }
+
+        /// Choose to carry refunded bets forward
+        ///
+        /// 1. The bets of the caller in a draw that misses its minimum participation are
+        ///    carried into the next cycle of the draw if `carry_forward` is true, or
+        ///    refunded if false.
+        /// 2. `None` follows the default of the draw.
+        #[ink(message)]
+        pub fn set_carry_forward(&mut self, lottery_id: u32,
+            carry_forward: Option<bool>) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            self.lottery(lottery_id)?;
+
+            match carry_forward {
+                Some(carry_forward) => self.carry_forward.insert((caller, lottery_id), &carry_forward),
+                None => {
+                    self.carry_forward.remove((caller, lottery_id));
+                    None
+                }
+            };
+
+            self.emit_success(lottery_id, None, Success::CarryForwardSet {
+                account: caller,
+                carry_forward: carry_forward,
+            });
+            Ok(())
+        }
+
+        /// Only accept bets whose stake is collected by the contract
+        ///
+        /// 1. Only the dev can change the bet verification of the lottery.
+        /// 2. If set, `add_bet` and `add_xcm_bet` of the operator are rejected and bets can
+        ///    only be placed with `place_bet` and `place_bet_for`.
+        #[ink(message)]
+        pub fn set_verified_bets_only(&mut self, lottery_id: u32,
+            verified_bets_only: bool) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            lottery_setup.verified_bets_only = verified_bets_only;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::VerifiedBetsSet {
+                verified_bets_only: verified_bets_only,
+            });
+            Ok(())
+        }
+
+        /// Set the prize vault
+        ///
+        /// 1. Only the dev can set the vault of the lottery.
+        /// 2. The lottery must be stopped and the vault must hold the asset of the lottery,
+        ///    i.e., pallet_assets or the native token.  PSP22 jackpots can't be vaulted.
+        /// 3. Jackpot shares are deposited in the vault and the paid jackpot is released
+        ///    when the draw closes.  Jackpots deposited before are kept by the contract,
+        ///    move them before changing the vault.
+        #[ink(message)]
+        pub fn set_vault(&mut self, lottery_id: u32, vault: Option<AccountId>) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            if lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
+            }
+
+            if let (Some(_), PaymentBackend::Psp22(_) | PaymentBackend::Simulated) = (vault, &lottery_setup.payment_backend) {
+                return Err(RuntimeError::VaultUnsupported.into());
+            }
+
+            lottery_setup.vault = vault;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::VaultSet {
+                vault: vault,
+            });
+            Ok(())
+        }
+
+        /// Set the claiming of the payouts
+        ///
+        /// 1. Only the dev can change how the settlements are paid.
+        /// 2. With claims `close_draw` only records the prizes and the rebates of the local
+        ///    recipients, they withdraw them with `claim_prize` and `claim_rebate`.  The
+        ///    closing no longer depends on the transfers to all winners and bettors.
+        /// 3. Payouts to foreign locations are still delivered via XCM.
+        #[ink(message)]
+        pub fn set_claim_payouts(&mut self, lottery_id: u32, claim_payouts: bool) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            lottery_setup.claim_payouts = claim_payouts;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::ClaimPayoutsSet {
+                claim_payouts: claim_payouts,
+            });
+            Ok(())
+        }
+
+        /// Claim prizes
+        ///
+        /// 1. The caller withdraws all its prizes recorded by the settlements of the
+        ///    lottery, i.e., the jackpot, upline, progressive, house and early-bird shares.
+        /// 2. A failing transfer keeps the prizes for the next claim.
+        #[ink(message)]
+        pub fn claim_prize(&mut self, lottery_id: u32) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            self.lottery(lottery_id)?;
+
+            let mut claim = self.claims.get((caller, lottery_id)).unwrap_or_default();
+            let amount = core::mem::take(&mut claim.prizes);
+            if amount == 0 {
+                return Err(Error::NoRecords.into());
+            }
+            self.claims.insert((caller, lottery_id), &claim);
+
+            self.guarded(|lottery| lottery.payout(lottery_id, 0, caller, PayoutRole::Winner, amount))?;
+
+            self.emit_success(lottery_id, None, Success::PrizeClaimed {
+                account: caller,
+                amount: amount,
+            });
+            Ok(())
+        }
+
+        /// Claim rebates
+        ///
+        /// 1. The caller withdraws all its rebates recorded by the settlements of the
+        ///    lottery.
+        /// 2. A failing transfer keeps the rebates for the next claim.
+        #[ink(message)]
+        pub fn claim_rebate(&mut self, lottery_id: u32) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            self.lottery(lottery_id)?;
+
+            let mut claim = self.claims.get((caller, lottery_id)).unwrap_or_default();
+            let amount = core::mem::take(&mut claim.rebates);
+            if amount == 0 {
+                return Err(Error::NoRecords.into());
+            }
+            self.claims.insert((caller, lottery_id), &claim);
+
+            self.guarded(|lottery| lottery.payout(lottery_id, 0, caller, PayoutRole::Rebate, amount))?;
+
+            self.emit_success(lottery_id, None, Success::RebateClaimed {
+                account: caller,
+                amount: amount,
+            });
+            Ok(())
+        }
+
+        /// Set the payout policies
+        ///
+        /// 1. Only the dev can set the payout policies and the auditor, so a single
+        ///    operator key can't lift them.
+        /// 2. Every prize of a settlement and every withdrawal is evaluated against the
+        ///    policy of its amount band: it is held until the required approvals of the
+        ///    dev or the auditor (`approve_payouts`) and the delay blocks have passed, then
+        ///    released.  Bands without approvals and delay are paid immediately.
+        /// 3. The policies must be strictly ascending by amount and can't require more
+        ///    than `MAX_PAYOUT_APPROVALS`, or more than the dev without an auditor.
+        #[ink(message)]
+        pub fn set_payout_policies(&mut self,
+            lottery_id: u32,
+            policies: Vec<PayoutPolicy>,
+            auditor: Option<AccountId>) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            let mut lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &mut lottery.lottery_setup;
+            if caller != lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let approvers = if auditor.is_some() { MAX_PAYOUT_APPROVALS } else { 1 };
+            if policies.iter().any(|p| p.approvals > approvers)
+                || policies.windows(2).any(|w| w[0].min_amount >= w[1].min_amount) {
+                return Err(Error::InvalidPayoutPolicy.into());
+            }
+
+            let count = policies.len() as u32;
+            lottery_setup.payout_policies = policies;
+            lottery_setup.auditor = auditor;
+            self.save_lottery(lottery_id, &lottery);
+
+            self.emit_success(lottery_id, None, Success::PayoutPoliciesSet {
+                policies: count,
+                auditor: auditor,
+            });
+            Ok(())
+        }
+
+        /// Add an XCM bet
+        ///
+        /// 1. Bets from sibling parachains arrive as reserve-transferred funds plus a bet
+        ///    payload.  The operator records the bet once the transfer (tx_hash) is verified.
+        /// 2. The bettor is the origin account, the origin chain is kept with the bet.
+        /// 3. The same rules and shares of `add_bet` apply.  Draws with compact bets can't
+        ///    keep the origin and refuse the bet.
+        #[ink(message)]
+        pub fn add_xcm_bet(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            origin: XcmOrigin,
+            upline: AccountId,
+            tx_hash: Vec<u8>,
+            amount: u128) -> Result<(), ContractError> {
+
+            self.check_full_bets(lottery_id, draw_number)?;
+            self.add_bet(lottery_id, draw_number, bet_number, origin.account, upline, tx_hash, amount)?;
+
+            // Record the origin of the bet just added
+            self.update_last_bet(lottery_id, draw_number, |bet| bet.origin = Some(origin))
+        }
+
+        /// Claim escrowed prizes
+        ///
+        /// 1. Prizes that could not be delivered via XCM are kept by the contract.
+        /// 2. The winner claims all of its escrowed prizes to its local account.
+        #[ink(message)]
+        pub fn claim_escrow(&mut self, lottery_id: u32) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+            self.lottery(lottery_id)?;
+
+            let claims = self.escrow.get((caller, lottery_id)).unwrap_or_default();
+            if claims.is_empty() {
+                return Err(Error::NoRecords.into());
+            }
+            let freed = self.escrow.size((caller, lottery_id)).unwrap_or(0);
+            self.escrow.remove((caller, lottery_id));
+            self.mapped_bytes = self.mapped_bytes.saturating_sub(freed);
+
+            let amount: u128 = claims.iter().map(|c| c.amount).sum();
+            self.guarded(|lottery| {
+                for claim in claims {
+                    lottery.payout(lottery_id, claim.draw_number, caller, PayoutRole::Winner, claim.amount)?;
+                }
+                Ok(())
+            })?;
+
+            self.emit_success(lottery_id, None, Success::EscrowClaimed {
+                account: caller,
+                amount: amount,
+            });
+
+            Ok(())
+        }
+
+        /// Transfer a bet
+        ///
+        /// 1. Only the current owner of the bet can transfer it.
+        /// 2. Bets can only be transferred while the draw is open, i.e., before processing.
+        /// 3. The new owner receives the winnings and the rebate of the bet on settlement,
+        ///    the affiliate relationship stays with the original bettor.
+        #[ink(message)]
+        pub fn transfer_bet(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_index: u32,
+            new_owner: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+
+            // Find the draw number
+            let draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status == DrawStatus::Processing {
+                return Err(Error::DrawProcessing.into());
+            }
+            if draw.status != DrawStatus::Open || !draw.is_open {
+                return Err(Error::DrawClosed.into());
+            }
+
+            // Compact bets have no index
+            let key = (lottery_id, draw_number, bet_index);
+            let mut bet = self.bets.get(key).ok_or(Error::BetNotFound)?;
+
+            if caller != bet.owner {
+                return Err(Error::NotBetOwner.into());
+            }
+
+            bet.owner = new_owner;
+            self.save_bet(key, &bet);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::BetTransferred {
+                draw_number: draw_number,
+                bet_index: bet_index,
+                from: caller,
+                to: new_owner,
+            });
+
+            Ok(())
+        }
+
+        /// Set the companion contract
+        ///
+        /// 1. Only the dev can set the companion contract.
+        /// 2. The companion is notified of the settlement of every draw of all lotteries,
+        ///    a failing notification does not block the closing of the draw.
+        /// 3. The change applies to all lotteries, it is journaled in lottery 0.
+        #[ink(message)]
+        pub fn set_companion(&mut self, companion: Option<AccountId>) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            self.companion = companion;
+
+            self.emit_success(0, None, Success::CompanionSet {
+                companion: companion,
+            });
+            Ok(())
+        }
+
+        /// Set the storage deposit budget
+        ///
+        /// 1. Only the dev can set the budget, `None` removes it.
+        /// 2. Bets whose storage would raise the estimated deposit above the limit are
+        ///    refused, so the deposit of the contract can't be exhausted by bets.
+        ///    Pruning with `prune_storage` makes room for new bets.
+        /// 3. The budget applies to all lotteries, it is journaled in lottery 0.
+        #[ink(message)]
+        pub fn set_deposit_budget(&mut self, budget: Option<DepositBudget>) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            self.emit_success(0, None, Success::DepositBudgetSet {
+                byte_deposit: budget.as_ref().map_or(0, |b| b.byte_deposit),
+                limit: budget.as_ref().map(|b| b.limit),
+            });
+            self.deposit_budget = budget;
+            Ok(())
+        }
+
+        /// Prune the storage of a lottery
+        ///
+        /// 1. Only the operator or the dev of the lottery can prune its storage.
+        /// 2. The winners of closed draws are deleted once all their payouts are paid,
+        ///    i.e., no payout of the draw failed or is held.  The app can't display them
+        ///    afterwards, the `DrawClosed` and `WinnerPaid` events keep them.
+        /// 3. The storage freed releases its deposit, nothing to prune is an error.
+        #[ink(message)]
+        pub fn prune_storage(&mut self, lottery_id: u32) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let bytes = self.storage_bytes();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator && caller != lottery.lottery_setup.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let mut winners: u32 = 0;
+            for mut draw in self.lottery_draws(lottery_id).into_iter()
+                .filter(|d| d.status == DrawStatus::Close && d.failed_payouts.is_empty()) {
+                if self.held_payouts.contains((lottery_id, draw.draw_number)) {
+                    continue;
+                }
+                let count = (draw.winners.len() + draw.second_winners.len() + draw.third_winners.len()) as u32;
+                if count == 0 {
+                    continue;
+                }
+                winners += count;
+                draw.winners = Vec::new();
+                draw.second_winners = Vec::new();
+                draw.third_winners = Vec::new();
+                self.save_draw(lottery_id, &draw);
+            }
+
+            if winners == 0 {
+                return Err(Error::NoRecords.into());
+            }
+
+            let freed = bytes.saturating_sub(self.storage_bytes());
+            self.emit_success(lottery_id, None, Success::StoragePruned {
+                winners: winners,
+                bytes: freed,
+            });
+            Ok(())
+        }
+
+        /// Affiliates
+        /// ----------
+        /// Referrers of the bettors, shared by all lotteries
+
+        /// Register the upline of the caller
+        ///
+        /// 1. A new bettor binds its upline (referrer) before its first bet, the upline
+        ///    does not need to have bet in any draw.
+        /// 2. An account registers its upline once, it can't be changed afterwards.
+        /// 3. The first bet of an account registers the upline of the bet if the account
+        ///    has no upline yet.
+        /// 4. The upline receives the affiliate share of all bets of the account, the
+        ///    upline given to `add_bet` is ignored once one is registered.
+        #[ink(message)]
+        pub fn register_upline(&mut self, upline: AccountId) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller == upline {
+                return Err(Error::SelfReferral.into());
+            }
+            if self.referrers.contains(caller) {
+                return Err(Error::ReferrerAlreadyRegistered.into());
+            }
+
+            self.bind_referrer(caller, upline);
+            Ok(())
+        }
+
+        /// Upgrade
+        /// -------
+        /// Replace the code of the contract keeping its storage and funds
+
+        /// Announce an upgrade
+        ///
+        /// 1. Only the dev can announce an upgrade.
+        /// 2. The upgrade can be executed `UPGRADE_DELAY_BLOCKS` after the announcement so
+        ///    operators and bettors can review the new code.
+        /// 3. A new announcement replaces the pending upgrade and restarts the delay.
+        #[ink(message)]
+        pub fn announce_upgrade(&mut self, code_hash: Hash) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let executable_block = self.env().block_number() + UPGRADE_DELAY_BLOCKS;
+            self.pending_upgrade = Some(PendingUpgrade {
+                code_hash: code_hash,
+                executable_block: executable_block,
+            });
+
+            self.env().emit_event(UpgradeAnnounced {
+                code_hash: code_hash,
+                executable_block: executable_block,
+            });
+            Ok(())
+        }
+
+        /// Cancel the announced upgrade
+        ///
+        /// 1. Only the dev can cancel the upgrade.
+        #[ink(message)]
+        pub fn cancel_upgrade(&mut self) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let pending_upgrade = self.pending_upgrade.take().ok_or(Error::UpgradeNotAnnounced)?;
+
+            self.env().emit_event(UpgradeCancelled {
+                code_hash: pending_upgrade.code_hash,
+            });
+            Ok(())
+        }
+
+        /// Upgrade the contract
+        ///
+        /// 1. Only the dev can upgrade the contract.
+        /// 2. The code hash must be the announced one and the delay must have passed.
+        /// 3. The storage of the contract is kept, the new code must be able to read it.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let pending_upgrade = self.pending_upgrade
+                .clone()
+                .filter(|u| u.code_hash == code_hash)
+                .ok_or(Error::UpgradeNotAnnounced)?;
+
+            let current_block: u32 = self.env().block_number();
+            if pending_upgrade.executable_block > current_block {
+                return Err(Error::InvalidBlock {
+                    required_block: pending_upgrade.executable_block,
+                    current_block: current_block,
+                }.into());
+            }
+
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(RuntimeError::from)?;
+            self.pending_upgrade = None;
+
+            self.env().emit_event(Upgraded {
+                code_hash: code_hash,
+                schema_version: EVENT_SCHEMA_VERSION,
+                block_number: current_block,
+            });
+            Ok(())
+        }
+
+        /// Import
+        /// ------
+        /// Populate a fresh deployment with the snapshot exported by the previous
+        /// contract, see `export_setup`, `export_draws` and `export_bets`
+
+        /// Import the setup of a lottery
+        ///
+        /// 1. Only the dev can import into a contract instantiated with `new_for_import`,
+        ///    until the import is completed.
+        /// 2. The snapshot replaces a stopped lottery without draws or is added as the
+        ///    next lottery.  The imported lottery is stopped, its held payouts and its
+        ///    leaderboard are imported with it.
+        /// 3. The storage version of the snapshot must be the one of the contract.
+        #[ink(message)]
+        pub fn import_setup(&mut self, lottery_id: u32, snapshot: Vec<u8>) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if self.import_closed {
+                return Err(Error::ImportClosed.into());
+            }
+
+            let (storage_version, _dev, salt, journal_seq, bundle_seq, companion, mut lottery, held_payouts, leaderboard):
+                (u16, AccountId, u64, u64, u64, Option<AccountId>, LotteryState, Vec<HeldPayout>, Vec<LeaderboardEntry>) =
+                scale::Decode::decode(&mut &snapshot[..]).map_err(|_| Error::InvalidSnapshot)?;
+            if storage_version != STORAGE_VERSION {
+                return Err(Error::InvalidSnapshot.into());
+            }
+            lottery.lottery_setup.is_started = false;
+            lottery.draw_count = 0;
+
+            match self.lotteries.get(lottery_id) {
+                Some(existing) if existing.lottery_setup.is_started => return Err(Error::AlreadyStarted.into()),
+                Some(existing) if existing.draw_count > 0 => return Err(Error::InvalidSnapshot.into()),
+                Some(_) => self.save_lottery(lottery_id, &lottery),
+                None if lottery_id == self.lottery_count => {
+                    self.add_lottery(lottery);
+                }
+                None => return Err(Error::LotteryNotFound.into()),
+            }
+
+            // The held payouts are owed to their recipients, they and the leaderboard
+            // replace the ones of the lottery
+            let mut held_draws: Vec<u32> = held_payouts.iter().map(|h| h.draw_number).collect();
+            held_draws.sort();
+            held_draws.dedup();
+            for draw_number in held_draws {
+                let held: Vec<HeldPayout> = held_payouts.iter()
+                    .filter(|h| h.draw_number == draw_number)
+                    .cloned()
+                    .collect();
+                self.save_held_payouts(lottery_id, draw_number, &held);
+            }
+            for (rank, entry) in leaderboard.iter().take(LEADERBOARD_LENGTH).enumerate() {
+                let previous = self.leaderboard.insert((lottery_id, rank as u32), entry).unwrap_or(0);
+                self.mapped_bytes = (self.mapped_bytes + entry.encoded_size() as u32).saturating_sub(previous);
+            }
+
+            // Sequences continue after the ones of the previous contract
+            self.salt = self.salt.max(salt);
+            self.journal_seq = self.journal_seq.max(journal_seq);
+            self.bundle_seq = self.bundle_seq.max(bundle_seq);
+            self.companion = self.companion.or(companion);
+            Ok(())
+        }
+
+        /// Import draws of a lottery
+        ///
+        /// 1. Only the dev can import, until the import is completed, and only into a
+        ///    stopped lottery.
+        /// 2. Every draw is the SCALE encoding of `export_draws`, its bets are imported
+        ///    with `import_bets`.  A draw number can only be imported once.
+        /// 3. The draws are checked like the added draws, i.e., the maximum number of
+        ///    draws, the bet amount and the blocks, and the cutoff must leave a betting
+        ///    window.
+        #[ink(message)]
+        pub fn import_draws(&mut self, lottery_id: u32, draws: Vec<Vec<u8>>) -> Result<(), ContractError> {
+            self.check_import(lottery_id)?;
+
+            for encoded in draws {
+                let mut draw: Draw = scale::Decode::decode(&mut &encoded[..]).map_err(|_| Error::InvalidSnapshot)?;
+                if draw.draw_number == 0 || self.lottery_draw_numbers(lottery_id).contains(&draw.draw_number) {
+                    return Err(Error::InvalidSnapshot.into());
+                }
+
+                let lottery = self.lottery(lottery_id)?;
+                if lottery.draw_count >= lottery.lottery_setup.maximum_draws.into() {
+                    return Err(Error::TooManyDraws {
+                        limit: lottery.lottery_setup.maximum_draws,
+                    }.into());
+                }
+                Self::check_draw_terms(&lottery.lottery_setup,
+                    draw.opening_blocks,
+                    draw.processing_blocks,
+                    draw.closing_blocks,
+                    draw.bet_amount,
+                    &draw.settlement_mode,
+                    draw.promotional)?;
+                if draw.cutoff_blocks >= draw.processing_blocks - draw.opening_blocks {
+                    return Err(Error::EmptyBettingWindow.into());
+                }
+
+                self.push_draw_number(lottery_id, draw.draw_number)?;
+                draw.bet_count = 0;
+                draw.bet_numbers = Vec::new();
+                self.save_draw(lottery_id, &draw);
+            }
+            Ok(())
+        }
+
+        /// Import bets of a draw
+        ///
+        /// 1. Only the dev can import, until the import is completed, and only into a
+        ///    stopped lottery.
+        /// 2. Every bet is the SCALE encoding of `export_bets`, the bets are added in the
+        ///    exported order.
+        #[ink(message)]
+        pub fn import_bets(&mut self, lottery_id: u32, draw_number: u32, bets: Vec<Vec<u8>>) -> Result<(), ContractError> {
+            self.check_import(lottery_id)?;
+
+            let bets = bets.iter()
+                .map(|encoded| scale::Decode::decode(&mut &encoded[..]))
+                .collect::<Result<Vec<Bet>, _>>()
+                .map_err(|_| Error::InvalidSnapshot)?;
+
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            for bet in bets {
+                // Compact bets are the bets of their owner at the bet amount
+                if draw.compact_bets
+                    && (bet.owner != bet.bettor || bet.origin.is_some() || bet.stake != draw.bet_amount) {
+                    return Err(Error::InvalidSnapshot.into());
+                }
+                self.push_bet(lottery_id, &mut draw, bet);
+            }
+            self.save_draw(lottery_id, &draw);
+            Ok(())
+        }
+
+        /// Import the free-bet credits owed to the accounts of a lottery
+        ///
+        /// 1. Only the dev can import, until the import is completed, and only into a
+        ///    stopped lottery.
+        /// 2. The imported credits replace the credits of the accounts.
+        #[ink(message)]
+        pub fn import_credits(&mut self, lottery_id: u32, credits: Vec<(AccountId, u128)>) -> Result<(), ContractError> {
+            self.check_import(lottery_id)?;
+
+            let mut owed = self.imported_owed.get(lottery_id).unwrap_or(0);
+            for (account, amount) in credits {
+                let previous = self.credits.get((account, lottery_id)).unwrap_or(0);
+                owed = owed.saturating_sub(previous).saturating_add(amount);
+                self.credits.insert((account, lottery_id), &amount);
+            }
+            self.imported_owed.insert(lottery_id, &owed);
+            Ok(())
+        }
+
+        /// Import the escrowed prizes owed to the accounts of a lottery
+        ///
+        /// 1. Only the dev can import, until the import is completed, and only into a
+        ///    stopped lottery.
+        /// 2. The imported escrow replaces the escrow of the accounts.
+        #[ink(message)]
+        pub fn import_escrow(&mut self, lottery_id: u32, escrow: Vec<(AccountId, Vec<Escrow>)>) -> Result<(), ContractError> {
+            self.check_import(lottery_id)?;
+
+            let mut owed = self.imported_owed.get(lottery_id).unwrap_or(0);
+            for (account, claims) in escrow {
+                let previous = self.escrow.get((account, lottery_id)).unwrap_or_default();
+                owed = owed.saturating_sub(previous.iter().map(|e| e.amount).sum())
+                    .saturating_add(claims.iter().map(|e| e.amount).sum());
+                let previous = self.escrow.insert((account, lottery_id), &claims).unwrap_or(0);
+                self.mapped_bytes = (self.mapped_bytes + claims.encoded_size() as u32).saturating_sub(previous);
+            }
+            self.imported_owed.insert(lottery_id, &owed);
+            Ok(())
+        }
+
+        /// Complete the import
+        ///
+        /// 1. Only the dev can complete the import, the import messages are rejected
+        ///    afterwards.
+        /// 2. The contract must hold what the lotteries owe, i.e., the jackpots not held
+        ///    by a vault, the held payouts and the imported escrow and credits.  The
+        ///    lotteries paid in the same asset are checked together, simulated lotteries
+        ///    hold nothing.
+        #[ink(message)]
+        pub fn complete_import(&mut self) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            if caller != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if self.import_closed {
+                return Err(Error::ImportClosed.into());
+            }
+
+            // Liabilities per payment backend and asset, with a lottery paid in it
+            let mut liabilities: Vec<(PaymentBackend, u128, u32, u128)> = Vec::new();
+            for lottery_id in 0..self.lottery_count {
+                let lottery_setup = self.lottery(lottery_id)?.lottery_setup;
+                let backend = lottery_setup.payment_backend.clone();
+                let asset_id = match backend {
+                    PaymentBackend::Assets => lottery_setup.asset_id,
+                    PaymentBackend::Simulated => continue,
+                    _ => 0,
+                };
+                let owed = self.owed(lottery_id)?;
+                match liabilities.iter_mut().find(|l| l.0 == backend && l.1 == asset_id) {
+                    Some(liability) => liability.3 = liability.3.saturating_add(owed),
+                    None => liabilities.push((backend, asset_id, lottery_id, owed)),
+                }
+            }
+            for (_, _, lottery_id, owed) in liabilities {
+                let available = self.funds(lottery_id)?;
+                if available < owed {
+                    return Err(Error::ImportUnderfunded {
+                        liabilities: owed,
+                        available: available,
+                    }.into());
+                }
+            }
+
+            self.import_closed = true;
+
+            let lotteries = self.lottery_count;
+            self.emit_success(0, None, Success::ImportCompleted {
+                lotteries: lotteries,
+            });
+            Ok(())
+        }
+
+        /// Check that the dev can import into the lottery
+        fn check_import(&self, lottery_id: u32) -> Result<(), ContractError> {
+            if self.env().caller() != self.dev {
+                return Err(Error::BadOrigin.into());
+            }
+            if self.import_closed {
+                return Err(Error::ImportClosed.into());
+            }
+            if self.lottery(lottery_id)?.lottery_setup.is_started {
+                return Err(Error::AlreadyStarted.into());
+            }
+            Ok(())
+        }
+
+        /// Amount the contract owes for a lottery: the jackpots not held by its vault, the
+        /// held payouts and the imported escrow and credits
+        fn owed(&self, lottery_id: u32) -> Result<u128, ContractError> {
+            let lottery_setup = self.lottery(lottery_id)?.lottery_setup;
+            let jackpots: u128 = match lottery_setup.vault {
+                Some(_) => 0,
+                None => self.lottery_draws(lottery_id).iter().map(|d| d.jackpot).sum(),
+            };
+            let held: u128 = self.lottery_held_payouts(lottery_id).iter().map(|h| h.payout.amount).sum();
+            let imported = self.imported_owed.get(lottery_id).unwrap_or(0);
+            Ok(jackpots.saturating_add(held).saturating_add(imported))
+        }
+
+        /// Balance of the contract in the payment backend of the lottery
+        fn funds(&self, lottery_id: u32) -> Result<u128, ContractError> {
+            let contract = self.env().account_id();
+            match self.lottery(lottery_id)?.lottery_setup.payment_backend {
+                PaymentBackend::Native => Ok(self.env().balance()),
+                PaymentBackend::Psp22(token) => Ok(psp22::balance_of(token, contract)?),
+                PaymentBackend::Assets => self.get_asset_balance(lottery_id, contract),
+                PaymentBackend::Simulated => Ok(0),
+            }
+        }
+
+        /// Check the bet amount and the blocks of a draw against the lottery setup
+        fn check_draw_terms(lottery_setup: &LotterySetup,
+            opening_blocks: u32,
+            processing_blocks: u32,
+            closing_blocks: u32,
+            bet_amount: u128,
+            settlement_mode: &SettlementMode,
+            promotional: bool) -> Result<(), ContractError> {
+
+            // A draw without a bet amount can't fund its jackpot, unless the operator
+            // funds it
+            if bet_amount == 0 && !promotional {
+                return Err(Error::ZeroBetAmount.into());
+            }
+
+            // The bet amount must be a whole number of cents of the asset
+            Self::check_bet_unit(lottery_setup, bet_amount)?;
+
+            // Fixed odds must pay more than the bet
+            if let SettlementMode::FixedOdds { multiplier } = settlement_mode {
+                if *multiplier <= 1 {
+                    return Err(Error::InvalidOdds { multiplier: *multiplier }.into());
+                }
+            }
+
+            // Blocks must follow hierarchy order.
+            if processing_blocks <= opening_blocks {
+                return Err(Error::EmptyBettingWindow.into());
+            }
+            if closing_blocks <= processing_blocks {
+                return Err(Error::EmptyProcessingWindow.into());
+            }
+            if lottery_setup.daily_total_blocks <= closing_blocks {
+                return Err(Error::InvalidBlocksHierarchy.into());
+            }
+            Ok(())
+        }
+
+        /// Validate and add a new draw to the lottery
+        fn insert_draw(&mut self,
+            lottery_id: u32,
+            opening_blocks: u32,
+            processing_blocks: u32,
+            closing_blocks: u32,
+            bet_amount: u128,
+            settlement_mode: SettlementMode,
+            promotional: bool) -> Result<(), ContractError>  {
+
+            // Only the operator can add a draw
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Must not exceed the maximum number of draws setup in the lottery
+            if lottery.draw_count >= lottery.lottery_setup.maximum_draws.into() {
+                return Err(Error::TooManyDraws {
+                    limit: lottery.lottery_setup.maximum_draws,
+                }.into());
+            }
+
+            Self::check_draw_terms(&lottery.lottery_setup,
+                opening_blocks,
+                processing_blocks,
+                closing_blocks,
+                bet_amount,
+                &settlement_mode,
+                promotional)?;
+
+            // Check if the lottery is stopped
+            if lottery.lottery_setup.is_started == true {
+                return Err(Error::AlreadyStarted.into());
+            }
+
+            let next_draw_number = self.lottery_draw_numbers(lottery_id)
+                                            .into_iter()
+                                            .max()
+                                            .unwrap_or(0)
+                                            .saturating_add(1);
+
+            let new_draw = Draw {
+                draw_number: next_draw_number,
+                opening_blocks: opening_blocks,
+                processing_blocks: processing_blocks,
+                closing_blocks: closing_blocks,
+                bet_amount: bet_amount,
+                max_bet_amount: 0,
+                jackpot: 0,
+                rebate: 0,
+                bet_count: 0,
+                winning_number: 0,
+                bonus_number: 0,
+                winners: Vec::new(),
+                second_winners: Vec::new(),
+                third_winners: Vec::new(),
+                division_shares: DivisionShares::default(),
+                settlement_mode: settlement_mode,
+                liability_cap: 0,
+                promotional: promotional,
+                handle: 0,
+                fees: 0,
+                house_edge: HouseEdge::default(),
+                status: DrawStatus::Close,
+                is_open: false,
+                failed_payouts: Vec::new(),
+                payout_strategy: None,
+                affiliate_percentage: None,
+                early_bird_bets: 0,
+                early_bird_percentage: 0,
+                early_bird_pool: 0,
+                reconciliation: Reconciliation::default(),
+                cutoff_blocks: 0,
+                participation: Participation::default(),
+                delegate: None,
+                result: None,
+                compact_bets: false,
+                committed_salt: None,
+                prize_tiers: Vec::new(),
+                bet_numbers: Vec::new(),
+            };
+
+            self.emit_draw_scheduled(lottery_id, &lottery.lottery_setup, &new_draw);
+            self.push_draw_number(lottery_id, next_draw_number)?;
+            self.save_draw(lottery_id, &new_draw);
+
+            self.emit_success(lottery_id, Some(next_draw_number), Success::DrawAdded {
+                draw_number: next_draw_number,
+                bet_amount: bet_amount,
+            });
+            Ok(())
+        }
+
+        /// Transfer a jackpot amount held by the contract to the vault of the lottery and
+        /// credit it to the pot of the draw.  Without a vault the contract keeps it.
+        fn deposit_jackpot(&mut self, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), ContractError> {
+            let vault = match self.lottery(lottery_id)?.lottery_setup.vault {
+                Some(vault) => vault,
+                None => return Ok(()),
+            };
+
+            self.transfer(lottery_id, draw_number, vault, PayoutRole::Vault, amount)?;
+            vault::credit(vault, lottery_id, draw_number, amount)?;
+            Ok(())
+        }
+
+        /// Returns the registered referrer of the bettor.  An unregistered bettor gets the
+        /// upline of the bet as referrer if the upline has bet in any draw of any lottery,
+        /// unless it is the bettor itself.
+        fn resolve_referrer(&mut self, bettor: AccountId, upline: AccountId) -> Option<AccountId> {
+            if let Some(referrer) = self.referrers.get(bettor) {
+                return Some(referrer);
+            }
+            if upline == bettor || !self.bettors.contains(upline) {
+                return None;
+            }
+
+            self.bind_referrer(bettor, upline);
+            Some(upline)
+        }
+
+        /// Register the referrer of an account and add the account to its downline
+        fn bind_referrer(&mut self, account: AccountId, referrer: AccountId) {
+            self.referrers.insert(account, &referrer);
+
+            let count = self.downline_count.get(referrer).unwrap_or(0);
+            self.downlines.insert((referrer, count), &account);
+            self.downline_count.insert(referrer, &(count + 1));
+
+            self.env().emit_event(ReferrerRegistered {
+                account: account,
+                referrer: referrer,
+            });
+        }
+
+        /// Add a prize to the lifetime winnings of the account and update the leaderboard
+        fn record_winnings(&mut self, lottery_id: u32, account: AccountId, amount: u128) -> Result<(), ContractError> {
+            let winnings = self.winnings.get((account, lottery_id)).unwrap_or(0).saturating_add(amount);
+            self.winnings.insert((account, lottery_id), &winnings);
+
+            self.lottery(lottery_id)?;
+            let mut leaderboard = self.lottery_leaderboard(lottery_id);
+            leaderboard.retain(|e| e.account != account);
+            let position = leaderboard.iter()
+                .position(|e| e.winnings < winnings)
+                .unwrap_or(leaderboard.len());
+            leaderboard.insert(position, LeaderboardEntry {
+                account: account,
+                winnings: winnings,
+            });
+            leaderboard.truncate(LEADERBOARD_LENGTH);
+
+            // The leaderboard never shrinks, every rank is overwritten
+            for (rank, entry) in leaderboard.iter().enumerate() {
+                let previous = self.leaderboard.insert((lottery_id, rank as u32), entry).unwrap_or(0);
+                self.mapped_bytes = (self.mapped_bytes + entry.encoded_size() as u32).saturating_sub(previous);
+            }
+            Ok(())
+        }
+
+        /// Prizes owed on a number of a fixed-odds draw if it hit now
+        fn number_liability(&self, lottery_id: u32, draw: &Draw, bet_number: u16, multiplier: u32) -> u128 {
+            let (_, stakes) = self.bets_on(lottery_id, draw.draw_number, bet_number);
+            stakes.saturating_mul(multiplier as u128)
+        }
+
+        /// Largest liability of a number of a fixed-odds draw, i.e., the prize reserve
+        /// including the incoming jackpot share, capped by the liability cap
+        fn liability_limit(draw: &Draw, incoming: u128) -> u128 {
+            let reserve = draw.jackpot.saturating_add(incoming);
+            match draw.liability_cap {
+                0 => reserve,
+                cap => reserve.min(cap),
+            }
+        }
+
+        /// Count the current cycle in the streak of the bettor.  Returns the length of the
+        /// streak if it just reached a multiple of the streak threshold.
+        fn extend_streak(&mut self, lottery_id: u32, bettor: AccountId) -> Result<Option<u32>, ContractError> {
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            let cycle = lottery_setup.starting_block;
+            let previous_cycle = cycle.saturating_sub(lottery_setup.daily_total_blocks);
+            let threshold = lottery_setup.streak_threshold;
+
+            let mut streak = self.streaks.get((bettor, lottery_id)).unwrap_or_default();
+            if streak.length > 0 && streak.last_cycle == cycle {
+                return Ok(None);
+            }
+
+            streak.length = if streak.length > 0 && streak.last_cycle == previous_cycle {
+                streak.length + 1
+            } else {
+                1
+            };
+            streak.last_cycle = cycle;
+            streak.best = streak.best.max(streak.length);
+            self.streaks.insert((bettor, lottery_id), &streak);
+
+            if threshold > 0 && streak.length % threshold == 0 {
+                return Ok(Some(streak.length));
+            }
+            Ok(None)
+        }
+
+        /// Add an affiliate share to the lifetime and draw earnings of the upline
+        fn record_affiliate_earnings(&mut self, upline: AccountId, lottery_id: u32, draw_number: u32, amount: u128) {
+            let lifetime = self.affiliate_earnings.get(upline).unwrap_or(0).saturating_add(amount);
+            self.affiliate_earnings.insert(upline, &lifetime);
+
+            let key = (upline, lottery_id, draw_number);
+            let draw_earnings = self.draw_affiliate_earnings.get(key).unwrap_or(0).saturating_add(amount);
+            self.draw_affiliate_earnings.insert(key, &draw_earnings);
+        }
+
+        /// Collect the price of the tickets from the payer, i.e., the stake (the bet amount
+        /// of the draw by default) less the discount for every ticket.  Returns the stake.
+        ///
+        /// 1. Native: the price is transferred with the call.
+        /// 2. Assets: the price is transferred from the approval of the payer.
+        /// 3. PSP22: the price is transferred from the allowance of the payer.
+        /// 4. Simulated: the price is recorded in the ledger of the contract.
+        fn collect_stake(&mut self, lottery_id: u32,
+            draw_number: u32,
+            payer: AccountId,
+            tickets: u32,
+            discount: u128,
+            stake: Option<u128>) -> Result<u128, ContractError> {
+
+            // Find the draw number, the stake is checked against its range with the bet
+            let bet_amount = stake.unwrap_or(self.draw(lottery_id, draw_number)?.bet_amount);
+            let price = bet_amount.saturating_sub(discount).saturating_mul(tickets as u128);
+
+            // Nothing to collect, e.g., a free promotional bet
+            if price == 0 && self.env().transferred_value() == 0 {
+                return Ok(bet_amount);
+            }
+
+            // Collect the stake, a failing bet reverts the collection
+            self.collect(lottery_id, payer, price)?;
+
+            Ok(bet_amount)
+        }
+
+        /// Collect an amount from the payer with the payment backend of the lottery
+        fn collect(&mut self, lottery_id: u32, payer: AccountId, amount: u128) -> Result<(), ContractError> {
+            let contract = self.env().account_id();
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            match lottery_setup.payment_backend {
+                PaymentBackend::Native => {
+                    let transferred = self.env().transferred_value();
+                    if transferred != amount {
+                        return Err(Error::InvalidBetAmount {
+                            expected: amount,
+                            actual: transferred,
+                        }.into());
+                    }
+                }
+                PaymentBackend::Assets => {
+                    runtime::dispatch(&RuntimeCall::Assets(AssetsCall::TransferApproved {
+                        id: lottery_setup.asset_id,
+                        owner: payer.into(),
+                        destination: contract.into(),
+                        amount: amount,
+                    }))?;
+                }
+                PaymentBackend::Psp22(token) => {
+                    psp22::transfer_from(token, payer, contract, amount)?;
+                }
+                PaymentBackend::Simulated => {
+                    let mut balance = self.ledger.get((payer, lottery_id)).unwrap_or_default();
+                    balance.collected = balance.collected.saturating_add(amount);
+                    self.ledger.insert((payer, lottery_id), &balance);
+                }
+            }
+            Ok(())
+        }
+
+        /// Discount of every ticket of a bulk purchase, zero if the lottery has no bulk
+        /// discount or too few tickets are bought
+        fn bulk_discount(&self, lottery_id: u32, draw_number: u32, tickets: u32) -> Result<u128, ContractError> {
+            let lottery = self.lottery(lottery_id)?;
+            let lottery_setup = &lottery.lottery_setup;
+            if lottery_setup.bulk_discount_tickets == 0 || tickets < lottery_setup.bulk_discount_tickets {
+                return Ok(0);
+            }
+
+            let bet_amount = self.draw(lottery_id, draw_number)?.bet_amount;
+            Ok(bet_amount * lottery_setup.bulk_discount_percentage as u128 / 100)
+        }
+
+        /// Record a paid bet and transfer its shares.  The discount of the bet is taken
+        /// from the operator share.
+        fn record_bet(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            bettor: AccountId,
+            upline: AccountId,
+            tx_hash: Vec<u8>,
+            amount: u128,
+            discount: u128) -> Result<(), ContractError> {
+
+            self.guarded(|lottery| lottery.accept_bet(lottery_id, draw_number, bet_number, bettor, upline,
+                tx_hash, amount, discount))
+        }
+
+        /// Run a money path of the contract.  Re-entering any money path while it runs,
+        /// e.g., from the callback of a token transfer, is rejected.
+        fn guarded<T>(&mut self,
+            action: impl FnOnce(&mut Self) -> Result<T, ContractError>) -> Result<T, ContractError> {
+
+            if self.locked.get().unwrap_or(false) {
+                return Err(Error::Reentrancy.into());
+            }
+            self.locked.set(&true);
+            let result = action(self);
+            self.locked.set(&false);
+            result
+        }
+
+        /// Checks, effects and interactions of `record_bet`: the bet, the pools and the
+        /// accounting are stored before any share is transferred.
+        fn accept_bet(&mut self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            bettor: AccountId,
+            upline: AccountId,
+            tx_hash: Vec<u8>,
+            amount: u128,
+            discount: u128) -> Result<(), ContractError> {
+
+            let terms = self.validate_bet(lottery_id, draw_number, bet_number, bettor, upline, tx_hash, amount, discount)?;
+            let shares = self.record_effects(lottery_id, amount, terms)?;
+            self.transfer_shares(lottery_id, draw_number, shares)
+        }
+
+        /// Check a bet without storing anything.  Returns the bet with its stake and
+        /// shares.
+        fn validate_bet(&self, lottery_id: u32,
+            draw_number: u32,
+            bet_number: u16,
+            bettor: AccountId,
+            upline: AccountId,
+            tx_hash: Vec<u8>,
+            amount: u128,
+            discount: u128) -> Result<BetTerms, ContractError> {
+
+            let lottery = self.lottery(lottery_id)?;
+
+            // Bets outside of the draw format can never win
+            Self::check_bet_number(bet_number)?;
+
+            // Find the draw number
+            let draw = self.draw(lottery_id, draw_number)?;
+
+            // Bets are only accepted within the betting window, i.e., the draw status is
+            // open and the flag is true.
+            if draw.status == DrawStatus::Processing {
+                return Err(Error::DrawProcessing.into());
+            }
+            if draw.status != DrawStatus::Open || !draw.is_open {
+                return Err(Error::DrawClosed.into());
+            }
+
+            // Bets are cut off before the processing block, at the latest when it is
+            // reached, so the winning number can't be known when betting
+            let current_block = self.env().block_number();
+            let cutoff_block = (lottery.lottery_setup.starting_block + draw.processing_blocks)
+                .saturating_sub(draw.cutoff_blocks);
+            if current_block >= cutoff_block {
+                return Err(Error::BetCutoffReached {
+                    cutoff_block: cutoff_block,
+                    current_block: current_block,
+                }.into());
+            }
+
+            // The verified payment must be exactly the bet amount of the draw less the
+            // discount, or a stake within the range of the draw
+            let stake = amount.saturating_add(discount);
+            if draw.max_bet_amount == 0 && stake != draw.bet_amount {
+                return Err(Error::InvalidBetAmount {
+                    expected: draw.bet_amount.saturating_sub(discount),
+                    actual: amount,
+                }.into());
+            }
+            if draw.max_bet_amount > 0 && (stake < draw.bet_amount || stake > draw.max_bet_amount) {
+                return Err(Error::StakeOutOfRange {
+                    min_bet: draw.bet_amount,
+                    max_bet: draw.max_bet_amount,
+                    actual: stake,
+                }.into());
+            }
+
+            // Shares
+            let shares = &lottery.lottery_setup.shares;
+            let jackpot_share   = stake * shares.jackpot as u128 / 100;
+            let dev_share       = stake * shares.dev as u128 / 100;
+            let rebate_share    = stake * shares.rebate as u128 / 100;
+            // The progressive jackpot takes its slice from the jackpot share
+            let progressive_share = stake * lottery.progressive.percentage as u128 / 100;
+            // So does the insurance fund
+            let insurance_share = stake * lottery.insurance.percentage as u128 / 100;
+            let jackpot_share   = jackpot_share - progressive_share - insurance_share;
+            let affiliate_percentage = draw.affiliate_percentage
+                .unwrap_or(lottery.lottery_setup.affiliate_percentage)
+                .min(shares.operator) as u128;
+            let affiliate_share = stake * affiliate_percentage / 100;
+            let operator_share  = stake * shares.operator as u128 / 100 - affiliate_share;
+            let operator_share  = operator_share
+                .checked_sub(discount)
+                .ok_or(Error::DiscountExceedsOperatorShare)?;
+
+            // The fee of a promotional bet is added to the jackpot in full, every account
+            // bets once
+            let (jackpot_share, dev_share, rebate_share, progressive_share, insurance_share, affiliate_share, operator_share) =
+                if draw.promotional {
+                    if self.promotional_bettors.contains((lottery_id, draw_number, bettor)) {
+                        return Err(Error::AlreadyBet.into());
+                    }
+                    (amount, 0, 0, 0, 0, 0, 0)
+                } else {
+                    (jackpot_share, dev_share, rebate_share, progressive_share, insurance_share, affiliate_share, operator_share)
+                };
+
+            // The shares never exceed the paid stake
+            #[cfg(feature = "debug-assertions")]
+            assert!(jackpot_share + dev_share + rebate_share + progressive_share + insurance_share
+                + affiliate_share + operator_share <= amount, "shares exceed the stake");
+
+            // A fixed-odds bet can't owe more on its number than the cap and the reserve
+            if let SettlementMode::FixedOdds { multiplier } = draw.settlement_mode {
+                let liability = self.number_liability(lottery_id, &draw, bet_number, multiplier)
+                    .saturating_add(stake.saturating_mul(multiplier as u128));
+                if liability > Self::liability_limit(&draw, jackpot_share) {
+                    return Err(Error::LiabilityCapReached.into());
+                }
+            }
+
+            // The fiat value of the bets of the bettor is limited per day
+            let daily_stake = self.check_daily_limit(lottery_id, bettor, amount)?;
+
+            // The upline is replaced by the referrer of the bettor once the bet is
+            // recorded, the size of the bet stays the same
+            let bet = Bet {
+                bettor: bettor,
+                owner: bettor,
+                upline: upline,
+                bet_number: bet_number,
+                tx_hash: tx_hash,
+                origin: None,
+                stake: stake,
+                bundle_id: None,
+            };
+
+            // The storage of the bet must fit the deposit budget
+            self.check_deposit_budget(match draw.compact_bets {
+                true => bettor.encoded_size(),
+                false => bet.encoded_size(),
+            })?;
+
+            Ok(BetTerms {
+                draw: draw,
+                bet: bet,
+                jackpot_share: jackpot_share,
+                dev_share: dev_share,
+                rebate_share: rebate_share,
+                progressive_share: progressive_share,
+                insurance_share: insurance_share,
+                affiliate_share: affiliate_share,
+                operator_share: operator_share,
+                daily_stake: daily_stake,
+            })
+        }
+
+        /// Store a validated bet with the pools, the bonuses and the accounting of the
+        /// draw.  Returns the shares to transfer, none while the draw is below its minimum
+        /// participation.
+        fn record_effects(&mut self, lottery_id: u32,
+            amount: u128,
+            terms: BetTerms) -> Result<Vec<Payout>, ContractError> {
+
+            let lottery = self.lottery(lottery_id)?;
+            let BetTerms {
+                mut draw,
+                mut bet,
+                jackpot_share,
+                dev_share,
+                rebate_share,
+                progressive_share,
+                insurance_share,
+                affiliate_share,
+                operator_share,
+                daily_stake,
+            } = terms;
+            let draw_number = draw.draw_number;
+            let bettor = bet.bettor;
+            let stake = bet.stake;
+
+            let operator = lottery.lottery_setup.operator;
+            let dev = lottery.lottery_setup.dev;
+            let vault = lottery.lottery_setup.vault;
+            let house_draw = draw.settlement_mode != SettlementMode::PariMutuel;
+            let streak_bonus = stake * lottery.lottery_setup.streak_bonus_percentage as u128 / 100;
+            let referral_bonus = stake * lottery.lottery_setup.referral_bonus_percentage as u128 / 100;
+            let early_bird_share = if draw.bet_count < draw.early_bird_bets {
+                stake * draw.early_bird_percentage as u128 / 100
+            } else {
+                0
+            };
+
+            // Shares paid on the bet, the bonuses are paid from the operator share
+            let fees = operator_share + dev_share + affiliate_share;
+
+            // The affiliate share goes to the referrer of the bettor in the affiliate
+            // registry.  If none is registered and the upline of the bet never bet in any
+            // draw, the share will be sent to the operator.
+            let referrer = self.resolve_referrer(bettor, bet.upline);
+
+            if let Some((day, daily_stake)) = daily_stake {
+                self.daily_stakes.insert((bettor, lottery_id, day), &daily_stake);
+            }
+
+            // The bet that reaches a streak threshold pays the bonus from the operator share
+            let streak = self.extend_streak(lottery_id, bettor)?;
+            let streak_bonus = match streak {
+                Some(_) => streak_bonus.min(operator_share),
+                None => 0,
+            };
+            let operator_share = operator_share - streak_bonus;
+
+            // The first bets of the draw fund the early-bird bonus from the operator share
+            let early_bird_share = early_bird_share.min(operator_share);
+            let operator_share = operator_share - early_bird_share;
+
+            // The first bet of a referee pays the referral bonus to both the referee and
+            // its referrer from the operator share
+            let referral_bonus = referral_bonus.min(operator_share / 2);
+            let referral = match referrer {
+                Some(valid_upline) if referral_bonus > 0 && !self.referral_bonuses.contains(bettor) => {
+                    self.referral_bonuses.insert(bettor, &());
+                    Some(valid_upline)
+                }
+                _ => None,
+            };
+            let operator_share = match referral {
+                Some(_) => operator_share - 2 * referral_bonus,
+                None => operator_share,
+            };
+
+            if let Some(valid_upline) = referrer {
+                self.record_affiliate_earnings(valid_upline, lottery_id, draw_number, affiliate_share);
+            }
+
+            // The bettor is a valid upline of later bets in every draw
+            self.bettors.insert(bettor, &());
+
+            // Add the bet
+            // The first bet of a cycle keeps what a refund of the cycle restores
+            if draw.bet_count == 0 {
+                draw.participation.jackpot = draw.jackpot;
+                draw.participation.reconciliation = draw.reconciliation.clone();
+            }
+
+            bet.upline = referrer.unwrap_or(bet.upline);
+            let (bet_number, tx_hash) = (bet.bet_number, bet.tx_hash.clone());
+            self.push_bet(lottery_id, &mut draw, bet);
+            let bet_index = draw.bet_count - 1;
+
+            // Compute for jackpot and rebate, these shares are distributed during closing
+            // 1. jackpot are given to the winners in shares of their stake
+            // 2. rebate are given to all bettors in shares of their stake
+            draw.jackpot += jackpot_share;
+            draw.rebate += rebate_share;
+            draw.handle += amount;
+            draw.early_bird_pool += early_bird_share;
+            draw.fees += amount.saturating_sub(jackpot_share + rebate_share + progressive_share + insurance_share
+                + early_bird_share);
+            self.update_lottery(lottery_id, |l| {
+                l.progressive.balance += progressive_share;
+                l.progressive.contributed += progressive_share;
+                l.insurance.balance += insurance_share;
+                l.insurance.contributed += insurance_share;
+                l.metrics.bets_accepted += 1;
+            })?;
+
+            let reserved = jackpot_share + progressive_share + insurance_share + early_bird_share;
+            let fees = fees - early_bird_share;
+            let reconciliation = &mut draw.reconciliation;
+            reconciliation.stake_received += amount;
+            reconciliation.fees_paid += fees;
+            reconciliation.jackpot_reserved += reserved;
+            reconciliation.rebate_reserved += rebate_share;
+            reconciliation.dust += amount.saturating_sub(reserved + rebate_share + fees);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::BetAdded {
+                draw_number: draw_number,
+                bet_index: bet_index,
+                bet_number: bet_number,
+                amount: stake,
+            });
+
+            self.env().emit_event(BetPlaced {
+                bettor: bettor,
+                lottery_id: lottery_id,
+                draw_number: draw_number,
+                bet_number: bet_number,
+                amount: stake,
+                tx_hash: tx_hash,
+            });
+
+            if let Some(length) = streak {
+                self.emit_success(lottery_id, Some(draw_number), Success::StreakBonusPaid {
+                    account: bettor,
+                    streak: length,
+                    amount: streak_bonus,
+                });
+            }
+
+            if let Some(valid_upline) = referral {
+                self.emit_success(lottery_id, Some(draw_number), Success::ReferralBonusPaid {
+                    referee: bettor,
+                    referrer: valid_upline,
+                    amount: referral_bonus,
+                });
+            }
+
+            // The shares of the bet: the bonuses, the operator, dev and affiliate shares.
+            // If the referrer is not found the affiliate share is sent to the operator.
+            // The jackpot share is held by the vault if the lottery has one, the house
+            // keeps the jackpot share of fixed-odds draws.
+            let mut shares: Vec<Payout> = Vec::new();
+            if streak.is_some() {
+                shares.push(Self::share(bettor, PayoutRole::StreakBonus, streak_bonus));
+            }
+            if let Some(valid_upline) = referral {
+                shares.push(Self::share(bettor, PayoutRole::ReferralBonus, referral_bonus));
+                shares.push(Self::share(valid_upline, PayoutRole::ReferralBonus, referral_bonus));
+            }
+            shares.push(Self::share(operator, PayoutRole::OperatorShare, operator_share));
+            shares.push(Self::share(dev, PayoutRole::DevShare, dev_share));
+            shares.push(Self::share(referrer.unwrap_or(operator), PayoutRole::Affiliate, affiliate_share));
+            if let (false, Some(vault)) = (house_draw, vault) {
+                shares.push(Self::share(vault, PayoutRole::Vault, jackpot_share));
+            }
+
+            // The shares of a draw below its minimum participation are withheld, the ones
+            // withheld so far are transferred with the bet that meets the minimum
+            if !Self::participation_met(&draw) {
+                draw.participation.withheld.extend(shares);
+                draw.participation.progressive += progressive_share;
+                draw.participation.insurance += insurance_share;
+                self.save_draw(lottery_id, &draw);
+                return Ok(Vec::new());
+            }
+            let mut withheld = core::mem::take(&mut draw.participation.withheld);
+            draw.participation.progressive = 0;
+            draw.participation.insurance = 0;
+            self.save_draw(lottery_id, &draw);
+            withheld.extend(shares);
+            Ok(withheld)
+        }
+
+        /// Transfer the shares of a recorded bet.  A failing transfer rejects the bet and
+        /// reverts it.
+        fn transfer_shares(&mut self, lottery_id: u32,
+            draw_number: u32,
+            shares: Vec<Payout>) -> Result<(), ContractError> {
+
+            for p in shares {
+                match p.role {
+                    PayoutRole::Vault => self.deposit_jackpot(lottery_id, draw_number, p.amount)?,
+                    _ => self.transfer(lottery_id, draw_number, p.recipient, p.role, p.amount)?,
+                }
+            }
+            Ok(())
+        }
+
+        /// Returns a draw of the lottery or `DrawNotFound`
+        fn draw(&self, lottery_id: u32, draw_number: u32) -> Result<Draw, ContractError> {
+            self.lottery(lottery_id)?;
+            Ok(self.draws.get((lottery_id, draw_number)).ok_or(Error::DrawNotFound)?)
+        }
+
+        /// Numbers of the draws of the lottery in the order they were added
+        fn lottery_draw_numbers(&self, lottery_id: u32) -> Vec<u32> {
+            let count = self.lotteries.get(lottery_id).map_or(0, |l| l.draw_count);
+            (0..count)
+                .filter_map(|index| self.draw_numbers.get((lottery_id, index)))
+                .collect()
+        }
+
+        /// Draws of the lottery in the order they were added
+        fn lottery_draws(&self, lottery_id: u32) -> Vec<Draw> {
+            self.lottery_draw_numbers(lottery_id)
+                .into_iter()
+                .filter_map(|n| self.draws.get((lottery_id, n)))
+                .collect()
+        }
+
+        /// Add a draw number after the draws of the lottery
+        fn push_draw_number(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            let mut lottery = self.lottery(lottery_id)?;
+            let previous = self.draw_numbers.insert((lottery_id, lottery.draw_count), &draw_number).unwrap_or(0);
+            self.mapped_bytes = (self.mapped_bytes + draw_number.encoded_size() as u32).saturating_sub(previous);
+            lottery.draw_count += 1;
+            self.save_lottery(lottery_id, &lottery);
+            Ok(())
+        }
+
+        /// Remove the last draw number of the lottery and return it
+        fn pop_draw_number(&mut self, lottery_id: u32) -> Result<Option<u32>, ContractError> {
+            let mut lottery = self.lottery(lottery_id)?;
+            if lottery.draw_count == 0 {
+                return Ok(None);
+            }
+            lottery.draw_count -= 1;
+            let key = (lottery_id, lottery.draw_count);
+            let draw_number = self.draw_numbers.get(key);
+            let freed = self.draw_numbers.size(key).unwrap_or(0);
+            self.draw_numbers.remove(key);
+            self.mapped_bytes = self.mapped_bytes.saturating_sub(freed);
+            self.save_lottery(lottery_id, &lottery);
+            Ok(draw_number)
+        }
+
+        /// Accounts with the highest lifetime winnings of the lottery, highest first
+        fn lottery_leaderboard(&self, lottery_id: u32) -> Vec<LeaderboardEntry> {
+            (0..LEADERBOARD_LENGTH as u32)
+                .map_while(|rank| self.leaderboard.get((lottery_id, rank)))
+                .collect()
+        }
+
+        /// Payouts held for the withdrawals and then for the draws of the lottery
+        fn lottery_held_payouts(&self, lottery_id: u32) -> Vec<HeldPayout> {
+            core::iter::once(0)
+                .chain(self.lottery_draw_numbers(lottery_id))
+                .flat_map(|n| self.held_payouts.get((lottery_id, n)).unwrap_or_default())
+                .collect()
+        }
+
+        /// Store the payouts held for a draw, zero for the withdrawals
+        fn save_held_payouts(&mut self, lottery_id: u32, draw_number: u32, held: &Vec<HeldPayout>) {
+            let key = (lottery_id, draw_number);
+            if held.is_empty() {
+                let freed = self.held_payouts.size(key).unwrap_or(0);
+                self.held_payouts.remove(key);
+                self.mapped_bytes = self.mapped_bytes.saturating_sub(freed);
+            } else {
+                let previous = self.held_payouts.insert(key, held).unwrap_or(0);
+                self.mapped_bytes = (self.mapped_bytes + held.encoded_size() as u32).saturating_sub(previous);
+            }
+        }
+
+        /// Store a draw of the lottery
+        fn save_draw(&mut self, lottery_id: u32, draw: &Draw) {
+            let previous = self.draws.insert((lottery_id, draw.draw_number), draw).unwrap_or(0);
+            self.mapped_bytes = (self.mapped_bytes + draw.encoded_size() as u32).saturating_sub(previous);
+        }
+
+        /// Delete a draw of the lottery and the bets of its current cycle
+        fn delete_draw(&mut self, lottery_id: u32, draw_number: u32) {
+            if let Some(mut draw) = self.draws.get((lottery_id, draw_number)) {
+                self.take_bets(lottery_id, &mut draw, |owner| owner);
+                let freed = self.draws.size((lottery_id, draw_number)).unwrap_or(0);
+                self.draws.remove((lottery_id, draw_number));
+                self.mapped_bytes = self.mapped_bytes.saturating_sub(freed);
+            }
+        }
+
+        /// Store a bet of a draw with full bets
+        fn save_bet(&mut self, key: (u32, u32, u32), bet: &Bet) {
+            let previous = self.bets.insert(key, bet).unwrap_or(0);
+            self.mapped_bytes = (self.mapped_bytes + bet.encoded_size() as u32).saturating_sub(previous);
+        }
+
+        /// Store a bet of the current cycle, a draw with compact bets only keeps its owner
+        /// under its number
+        fn push_bet(&mut self, lottery_id: u32, draw: &mut Draw, bet: Bet) {
+            if !draw.compact_bets {
+                self.save_bet((lottery_id, draw.draw_number, draw.bet_count), &bet);
+            } else {
+                let (count, _) = self.number_stakes.get((lottery_id, draw.draw_number, bet.bet_number)).unwrap_or_default();
+                if count == 0 {
+                    draw.bet_numbers.push(bet.bet_number);
+                }
+                let key = (lottery_id, draw.draw_number, bet.bet_number, count);
+                let previous = self.number_bets.insert(key, &bet.owner).unwrap_or(0);
+                self.mapped_bytes = (self.mapped_bytes + bet.owner.encoded_size() as u32).saturating_sub(previous);
+            }
+            draw.bet_count += 1;
+            if draw.promotional {
+                self.promotional_bettors.insert((lottery_id, draw.draw_number, bet.bettor), &());
+            }
+
+            let key = (lottery_id, draw.draw_number, bet.bet_number);
+            let (count, stakes) = self.number_stakes.get(key).unwrap_or_default();
+            let tally = (count + 1, stakes.saturating_add(bet.stake));
+            let previous = self.number_stakes.insert(key, &tally).unwrap_or(0);
+            self.mapped_bytes = (self.mapped_bytes + tally.encoded_size() as u32).saturating_sub(previous);
+        }
+
+        /// Update the last bet of the current cycle of a draw with full bets, compact bets
+        /// have nothing to update
+        fn update_last_bet(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            update: impl FnOnce(&mut Bet)) -> Result<(), ContractError> {
+
+            let draw = self.draw(lottery_id, draw_number)?;
+            if draw.compact_bets {
+                return Err(Error::CompactBetsUnsupported.into());
+            }
+            if draw.bet_count == 0 {
+                return Ok(());
+            }
+
+            let key = (lottery_id, draw_number, draw.bet_count - 1);
+            if let Some(mut bet) = self.bets.get(key) {
+                update(&mut bet);
+                self.save_bet(key, &bet);
+            }
+            Ok(())
+        }
+
+        /// Bets of the current cycle of a draw.  Compact bets are expanded per number with
+        /// the bet amount as stake and the upline returned for their owner.
+        fn expand_bets(&self,
+            lottery_id: u32,
+            draw: &Draw,
+            upline: impl Fn(AccountId) -> AccountId) -> Vec<Bet> {
+
+            if !draw.compact_bets {
+                return (0..draw.bet_count)
+                    .filter_map(|index| self.bets.get((lottery_id, draw.draw_number, index)))
+                    .collect();
+            }
+
+            let mut bets: Vec<Bet> = Vec::new();
+            for bet_number in draw.bet_numbers.iter() {
+                let (count, _) = self.number_stakes.get((lottery_id, draw.draw_number, *bet_number)).unwrap_or_default();
+                let owners = (0..count)
+                    .filter_map(|index| self.number_bets.get((lottery_id, draw.draw_number, *bet_number, index)));
+                bets.extend(owners.map(|owner| Bet {
+                    bettor: owner,
+                    owner: owner,
+                    upline: upline(owner),
+                    bet_number: *bet_number,
+                    tx_hash: Vec::new(),
+                    origin: None,
+                    stake: draw.bet_amount,
+                    bundle_id: None,
+                }));
+            }
+            bets
+        }
+
+        /// Bets of the current cycle of a draw, the upline of a compact bet is the
+        /// registered referrer of its owner or the operator
+        fn draw_bets(&self, lottery_id: u32, draw: &Draw) -> Vec<Bet> {
+            let operator = self.lottery(lottery_id).map(|l| l.lottery_setup.operator).unwrap_or(self.dev);
+            self.expand_bets(lottery_id, draw, |owner| self.referrers.get(owner).unwrap_or(operator))
+        }
+
+        /// Remove the bets of the current cycle and return them, see `expand_bets`
+        fn take_bets(&mut self,
+            lottery_id: u32,
+            draw: &mut Draw,
+            upline: impl Fn(AccountId) -> AccountId) -> Vec<Bet> {
+
+            let bets = self.expand_bets(lottery_id, draw, upline);
+            let mut freed: u32 = 0;
+            if !draw.compact_bets {
+                for index in 0..draw.bet_count {
+                    freed += self.bets.size((lottery_id, draw.draw_number, index)).unwrap_or(0);
+                    self.bets.remove((lottery_id, draw.draw_number, index));
+                }
+            }
+            for bet_number in draw.bet_numbers.iter() {
+                let (count, _) = self.number_stakes.get((lottery_id, draw.draw_number, *bet_number)).unwrap_or_default();
+                for index in 0..count {
+                    let key = (lottery_id, draw.draw_number, *bet_number, index);
+                    freed += self.number_bets.size(key).unwrap_or(0);
+                    self.number_bets.remove(key);
+                }
+            }
+            for bet in bets.iter() {
+                freed += self.number_stakes.size((lottery_id, draw.draw_number, bet.bet_number)).unwrap_or(0);
+                self.number_stakes.remove((lottery_id, draw.draw_number, bet.bet_number));
+                if draw.promotional {
+                    self.promotional_bettors.remove((lottery_id, draw.draw_number, bet.bettor));
+                }
+            }
+            self.mapped_bytes = self.mapped_bytes.saturating_sub(freed);
+
+            draw.bet_count = 0;
+            draw.bet_numbers = Vec::new();
+            bets
+        }
+
+        /// Number of bets of the current cycle on a number and their total stake
+        fn bets_on(&self, lottery_id: u32, draw_number: u32, bet_number: u16) -> (u32, u128) {
+            self.number_stakes.get((lottery_id, draw_number, bet_number)).unwrap_or_default()
+        }
+
+        /// Refuse bets a draw with compact bets can't keep, i.e., bets not owned by their
+        /// bettor or from another chain
+        fn check_full_bets(&self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            let draw = self.draw(lottery_id, draw_number)?;
+            if draw.compact_bets {
+                return Err(Error::CompactBetsUnsupported.into());
+            }
+            Ok(())
+        }
+
+        /// Encoded size of the lotteries and of the entries kept in mappings, e.g., the
+        /// draws, the bets and the journal
+        fn storage_bytes(&self) -> u32 {
+            self.mapped_bytes
+        }
+
+        /// Refuse storage that would raise the deposit above the budget of the contract
+        fn check_deposit_budget(&self, bytes: usize) -> Result<(), ContractError> {
+            let budget = match &self.deposit_budget {
+                Some(budget) => budget,
+                None => return Ok(()),
+            };
+
+            let required = (self.storage_bytes() as u128 + bytes as u128).saturating_mul(budget.byte_deposit);
+            if required > budget.limit {
+                return Err(Error::DepositBudgetExceeded {
+                    limit: budget.limit,
+                    required: required,
+                }.into());
+            }
+            Ok(())
+        }
+
+        /// Transfer of a share of a bet to a local account
+        fn share(recipient: AccountId, role: PayoutRole, amount: u128) -> Payout {
+            Payout {
+                recipient: recipient,
+                role: role,
+                amount: amount,
+                destination: None,
+            }
+        }
+
+        /// True if the account is the operator of the lottery or the delegate of the draw
+        fn manages_draw(&self, lottery_id: u32, draw_number: u32, account: AccountId) -> bool {
+            self.lottery(lottery_id).is_ok_and(|l| account == l.lottery_setup.operator)
+                || self.draws.get((lottery_id, draw_number)).is_some_and(|d| d.delegate == Some(account))
+        }
+
+        /// True if the current cycle of the draw meets its minimum participation
+        fn participation_met(draw: &Draw) -> bool {
+            draw.bet_count >= draw.participation.min_bets && draw.handle >= draw.participation.min_pot
+        }
+
+        /// Setup of a new lottery that is not yet started
+        fn new_lottery_setup(operator: AccountId,
+            dev: AccountId,
+            asset_id: u128,
+            starting_block: u32,
+            daily_total_blocks: u32,
+            maximum_draws: u8,
+            maximum_bets: u16) -> LotterySetup {
+            LotterySetup {
+                operator: operator,
+                dev: dev,
+                asset_id: asset_id,
+                payment_backend: PaymentBackend::Assets,
+                verified_bets_only: false,
+                randomness_source: RandomnessSource::Internal,
+                vault: None,
+                nft_collection: None,
+                asset_metadata: None,
+                affiliate_percentage: DEFAULT_AFFILIATE_PERCENTAGE,
+                starting_block: starting_block,
+                daily_total_blocks: daily_total_blocks,
+                next_starting_block: (starting_block + daily_total_blocks),
+                maximum_draws: maximum_draws,
+                maximum_bets: maximum_bets,
+                is_started: false,
+                oracle: None,
+                max_daily_value: 0,
+                bulk_discount_tickets: 0,
+                bulk_discount_percentage: 0,
+                streak_threshold: 0,
+                streak_bonus_percentage: 0,
+                rebate_tiers: Vec::new(),
+                referral_bonus_percentage: 0,
+                payout_policies: Vec::new(),
+                auditor: None,
+                claim_payouts: false,
+                shares: Shares::default(),
+            }
+        }
+
+        /// New lottery without draws
+        fn new_lottery_state(lottery_setup: LotterySetup) -> LotteryState {
+            LotteryState {
+                lottery_setup: lottery_setup,
+                draw_count: 0,
+                metrics: Metrics::default(),
+                nft_items: 0,
+                progressive: ProgressivePot::default(),
+                house_edge: HouseEdge::default(),
+                insurance: InsuranceFund::default(),
+            }
+        }
+
+        /// Returns the lottery or `LotteryNotFound`
+        fn lottery(&self, lottery_id: u32) -> Result<LotteryState, ContractError> {
+            Ok(self.lotteries.get(lottery_id).ok_or(Error::LotteryNotFound)?)
+        }
+
+        /// Store a lottery
+        fn save_lottery(&mut self, lottery_id: u32, lottery: &LotteryState) {
+            let previous = self.lotteries.insert(lottery_id, lottery).unwrap_or(0);
+            self.mapped_bytes = (self.mapped_bytes + lottery.encoded_size() as u32).saturating_sub(previous);
+        }
+
+        /// Update a lottery with the given function and store it
+        fn update_lottery<T>(&mut self,
+            lottery_id: u32,
+            update: impl FnOnce(&mut LotteryState) -> T) -> Result<T, ContractError> {
+
+            let mut lottery = self.lottery(lottery_id)?;
+            let result = update(&mut lottery);
+            self.save_lottery(lottery_id, &lottery);
+            Ok(result)
+        }
+
+        /// Store a new lottery and return its id
+        fn add_lottery(&mut self, lottery: LotteryState) -> u32 {
+            let lottery_id = self.lottery_count;
+            self.save_lottery(lottery_id, &lottery);
+            self.lottery_count += 1;
+            lottery_id
+        }
+
+        /// Emit a success event for the caller at the current block and record it in
+        /// the journal
+        fn emit_success(&mut self, lottery_id: u32, draw_number: Option<u32>, success: Success) {
+            let caller = self.env().caller();
+            let block_number = self.env().block_number();
+
+            // The entry overwrites the one `JOURNAL_LENGTH` transitions older
+            self.journal_seq += 1;
+            let entry = JournalEntry {
+                seq: self.journal_seq,
+                lottery_id: lottery_id,
+                block_number: block_number,
+                caller: caller,
+                draw_number: draw_number,
+                status: success.clone(),
+            };
+            let previous = self.journal.insert(self.journal_seq % JOURNAL_LENGTH, &entry).unwrap_or(0);
+            self.mapped_bytes = (self.mapped_bytes + entry.encoded_size() as u32).saturating_sub(previous);
+
+            let operator = self.lotteries
+                .get(lottery_id)
+                .map(|l| l.lottery_setup.operator)
+                .unwrap_or(self.dev);
+
+            #[cfg(feature = "debug-assertions")]
+            self.assert_invariants(lottery_id, &success);
+
+            self.env().emit_event(LotteryEvent {
+                operator: operator,
+                lottery_id: lottery_id,
+                caller: caller,
+                draw_number: draw_number,
+                block_number: block_number,
+                block_timestamp: self.env().block_timestamp(),
+                code: success.code(),
+                status: LotteryStatus::EmitSuccess(success),
+            });
+        }
+
+        /// Assert the invariants of a lottery after a state transition
+        ///
+        /// 1. Pots: every stake of a draw is paid as fees, reserved or left as dust, and
+        ///    the progressive jackpot and the insurance fund hold what they received
+        ///    less what they paid.
+        /// 2. Status: only open draws accept bets and only closed draws hold winners and
+        ///    no bets.  The draw of a status transition has the new status.
+        #[cfg(feature = "debug-assertions")]
+        fn assert_invariants(&self, lottery_id: u32, success: &Success) {
+            let lottery = match self.lotteries.get(lottery_id) {
+                Some(lottery) => lottery,
+                None => return,
+            };
+
+            let progressive = &lottery.progressive;
+            assert_eq!(progressive.balance, progressive.contributed - progressive.paid,
+                "progressive jackpot not conserved");
+            let insurance = &lottery.insurance;
+            assert_eq!(insurance.balance + insurance.withdrawn + insurance.covered,
+                insurance.contributed + insurance.topped_up,
+                "insurance fund not conserved");
+
+            for draw in self.lottery_draws(lottery_id).iter() {
+                let r = &draw.reconciliation;
+                assert_eq!(r.stake_received, r.fees_paid + r.jackpot_reserved + r.rebate_reserved + r.dust,
+                    "stakes of draw {} not conserved", draw.draw_number);
+                assert!(!draw.is_open || draw.status == DrawStatus::Open,
+                    "draw {} accepts bets while not open", draw.draw_number);
+                assert!(draw.status != DrawStatus::Open || draw.winners.is_empty(),
+                    "open draw {} has winners", draw.draw_number);
+                assert!(draw.status != DrawStatus::Close || draw.bet_count == 0,
+                    "closed draw {} has bets", draw.draw_number);
+            }
+
+            let (draw_number, status) = match success {
+                Success::DrawOpened { draw_number } => (draw_number, DrawStatus::Open),
+                Success::DrawProcessed { draw_number, .. } => (draw_number, DrawStatus::Processing),
+                Success::DrawClosed { draw_number, .. } => (draw_number, DrawStatus::Close),
+                Success::DrawRolledOver { draw_number, .. } => (draw_number, DrawStatus::Close),
+                Success::StakesRefunded { draw_number, .. } => (draw_number, DrawStatus::Close),
+                Success::DrawSettled { draw_number, .. } => (draw_number, DrawStatus::Close),
+                _ => return,
+            };
+            let draw = self.draws.get((lottery_id, *draw_number));
+            assert_eq!(draw.map(|d| d.status), Some(status),
+                "draw {} did not transition", draw_number);
+        }
+
+        /// Emit the schedule of a draw for the current lottery cycle
+        fn emit_draw_scheduled(&self, lottery_id: u32, lottery_setup: &LotterySetup, draw: &Draw) {
+            self.env().emit_event(NextDrawScheduled {
+                draw_number: draw.draw_number,
+                lottery_id: lottery_id,
+                opening_block: lottery_setup.starting_block + draw.opening_blocks,
+                pot_seed: draw.jackpot,
+            });
+        }
+
+        /// Transfer an asset amount from the contract to the recipient.
+        ///
+        /// A failed dispatch emits `PayoutFailed` and returns the transfer that failed.
+        fn transfer(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            recipient: AccountId,
+            role: PayoutRole,
+            amount: u128) -> Result<(), ContractError> {
+
+            // Nothing to transfer, e.g., the shares of a promotional bet
+            if amount == 0 {
+                return Ok(());
+            }
+
+            let lottery = self.lottery(lottery_id)?;
+            let result = match lottery.lottery_setup.payment_backend {
+                PaymentBackend::Psp22(token) => psp22::transfer(token, recipient, amount),
+                PaymentBackend::Simulated => {
+                    self.record_paid(lottery_id, recipient, amount);
+                    Ok(())
+                }
+                _ => runtime::dispatch(&Self::runtime_transfer(&lottery.lottery_setup, recipient, amount)),
+            };
+
+            let failed = result.is_err() as u64;
+            self.update_lottery(lottery_id, |l| {
+                l.metrics.transfers_attempted += 1;
+                l.metrics.transfers_failed += failed;
+            })?;
+
+            if let Err(cause) = result {
+                self.env().emit_event(PayoutFailed {
+                    recipient: recipient,
+                    lottery_id: lottery_id,
+                    draw_number: draw_number,
+                    role: role.clone(),
+                    amount: amount,
+                    error: cause.clone(),
+                });
+                return Err(TransferFailure {
+                    draw_number: draw_number,
+                    recipient: recipient,
+                    role: role,
+                    amount: amount,
+                    cause: cause,
+                }.into());
+            }
+            Ok(())
+        }
+
+        /// Record a transfer of a simulated lottery in the ledger
+        fn record_paid(&mut self, lottery_id: u32, recipient: AccountId, amount: u128) {
+            let mut balance = self.ledger.get((recipient, lottery_id)).unwrap_or_default();
+            balance.paid = balance.paid.saturating_add(amount);
+            self.ledger.insert((recipient, lottery_id), &balance);
+        }
+
+        /// Runtime call transferring the asset of the lottery, i.e., pallet assets or the
+        /// native balances
+        fn runtime_transfer(lottery_setup: &LotterySetup, recipient: AccountId, amount: u128) -> RuntimeCall {
+            match lottery_setup.payment_backend {
+                PaymentBackend::Native => RuntimeCall::Balances(BalancesCall::TransferKeepAlive {
+                    dest: recipient.into(),
+                    value: amount,
+                }),
+                _ => RuntimeCall::Assets(AssetsCall::Transfer {
+                    id: lottery_setup.asset_id,
+                    target: recipient.into(),
+                    amount: amount,
+                }),
+            }
+        }
+
+        /// Schedule a call of a draw message of this contract at the given block
+        fn schedule_call(&self,
+            when: u32,
+            selector: [u8; 4],
+            lottery_id: u32,
+            draw_number: u32) -> Result<(), RuntimeError> {
+
+            let mut data: Vec<u8> = selector.to_vec();
+            data.extend(scale::Encode::encode(&(lottery_id, draw_number)));
+
+            let call = RuntimeCall::Contracts(ContractsCall::Call {
+                dest: self.env().account_id().into(),
+                value: 0,
+                gas_limit: Weight {
+                    ref_time: SCHEDULED_CALL_REF_TIME,
+                    proof_size: SCHEDULED_CALL_PROOF_SIZE,
+                },
+                storage_deposit_limit: None,
+                data: data,
+            });
+
+            runtime::dispatch(&RuntimeCall::Scheduler(SchedulerCall::Schedule {
+                when: when,
+                maybe_periodic: None,
+                priority: 0,
+                call: Box::new(call),
+            }))
+        }
+
+        /// Check the daily stake of the bettor including the fiat value of a bet against
+        /// the daily limit.  Returns the day and the daily stake to store, if the
+        /// lottery has a daily limit.
+        ///
+        /// The day is the block number divided by the daily total blocks, so the limit
+        /// resets at every day boundary whether or not the lottery is restarted.
+        fn check_daily_limit(&self, lottery_id: u32, bettor: AccountId, amount: u128) -> Result<Option<(u32, u128)>, ContractError> {
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            let oracle = match lottery_setup.oracle {
+                Some(oracle) if lottery_setup.max_daily_value > 0 => oracle,
+                _ => return Ok(None),
+            };
+            let max_daily_value = lottery_setup.max_daily_value;
+            let day = self.env().block_number()
+                .checked_div(lottery_setup.daily_total_blocks)
+                .unwrap_or(0);
+
+            let price = oracle::get_price(oracle, lottery_setup.asset_id)?;
+            let value = oracle::value_of(amount, price);
+
+            let actual = self.daily_stakes
+                .get((bettor, lottery_id, day))
+                .unwrap_or(0)
+                .saturating_add(value);
+            if actual > max_daily_value {
+                return Err(Error::DailyLimitExceeded {
+                    limit: max_daily_value,
+                    actual: actual,
+                }.into());
+            }
+            Ok(Some((day, actual)))
+        }
+
+        /// Transition of `process_draw` for the current cycle of the draw
+        ///
+        /// 1. A draw without bets rolls over to the next cycle.
+        /// 2. The stakes of a draw below its minimum participation are refunded, so are
+        ///    those of a salt-reveal draw whose salt was not revealed in its reveal block,
+        ///    the operator forfeits the cycle.
+        /// 3. A commit-reveal draw keeps the commitment of the operator.
+        fn process_outcome(&self, lottery_id: u32, draw: &Draw) -> Result<ProcessOutcome, ContractError> {
+            if draw.bet_count == 0 {
+                return Ok(ProcessOutcome::RollOver);
+            }
+
+            let current_block = self.env().block_number();
+            if !Self::participation_met(draw)
+                || draw.committed_salt.as_ref().is_some_and(|c| c.salt.is_none() && current_block > c.reveal_block) {
+                return Ok(ProcessOutcome::Refund);
+            }
+
+            if self.lottery(lottery_id)?.lottery_setup.randomness_source == RandomnessSource::CommitReveal {
+                return Ok(ProcessOutcome::Commit);
+            }
+            Ok(ProcessOutcome::Draw)
+        }
+
+        /// Check that the draw exists, is open and has reached its processing block
+        fn check_process_draw(&self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            let lottery = self.lottery(lottery_id)?;
+
+            // Check if draw exist
+            let draw = self.draw(lottery_id, draw_number)?;
+
+            // Check if draw is open
+            if !draw.is_open {
+                return Err(Error::DrawClosed.into());
+            }
+
+            // Check if draw status is processing.  We can only process open draws
+            if draw.status == DrawStatus::Processing {
+                return Err(Error::DrawProcessing.into());
+            }
+
+            // The current block must be greater or equal to the draw processing blocks.
+            let current_block: u32 = self.env().block_number();
+            let draw_processing_blocks: u32 = lottery.lottery_setup.starting_block + draw.processing_blocks;
+            if draw_processing_blocks > current_block  {
+                return Err(Error::DrawNotDue {
+                    due_block: draw_processing_blocks,
+                    current_block: current_block,
+                }.into());
+            }
+
+            Ok(())
+        }
+
+        /// Check that the amount is a whole number of cents of the asset, if its decimals
+        /// are known
+        fn check_bet_unit(lottery_setup: &LotterySetup, amount: u128) -> Result<(), ContractError> {
+            let decimals = match &lottery_setup.asset_metadata {
+                Some(metadata) => metadata.decimals,
+                None => return Ok(()),
+            };
+
+            let unit = 10u128.saturating_pow(decimals.saturating_sub(2) as u32);
+            if amount % unit != 0 {
+                return Err(Error::InvalidBetUnit {
+                    unit: unit,
+                    actual: amount,
+                }.into());
+            }
+            Ok(())
+        }
+
+        /// Check that the affiliate percentage is within `MAX_AFFILIATE_PERCENTAGE` and the
+        /// operator share
+        fn check_affiliate_percentage(percentage: u8, shares: &Shares) -> Result<(), ContractError> {
+            let maximum = MAX_AFFILIATE_PERCENTAGE.min(shares.operator);
+            if percentage > maximum {
+                return Err(Error::InvalidAffiliatePercentage {
+                    maximum: maximum,
+                    actual: percentage,
+                }.into());
+            }
+            Ok(())
+        }
+
+        /// Check that a bonus percentage is within the given maximum
+        fn check_bonus_percentage(percentage: u8, maximum: u8) -> Result<(), ContractError> {
+            if percentage > maximum {
+                return Err(Error::InvalidBonusPercentage {
+                    maximum: maximum,
+                    actual: percentage,
+                }.into());
+            }
+            Ok(())
+        }
+
+        /// Operator share left after the affiliate percentage, it funds the bulk discount
+        /// and the bonuses
+        fn operator_margin(shares: &Shares, affiliate_percentage: u8) -> u8 {
+            shares.operator.saturating_sub(affiliate_percentage)
+        }
+
+        /// Check that the percentages funded from the operator share by the lottery setup
+        /// and by every draw of the lottery fit in the operator share of the setup
+        fn check_operator_shares(&self, lottery_id: u32, lottery_setup: &LotterySetup) -> Result<(), ContractError> {
+            let shares = &lottery_setup.shares;
+            Self::check_affiliate_percentage(lottery_setup.affiliate_percentage, shares)?;
+
+            let margin = Self::operator_margin(shares, lottery_setup.affiliate_percentage);
+            if lottery_setup.bulk_discount_percentage > margin {
+                return Err(Error::DiscountExceedsOperatorShare.into());
+            }
+            Self::check_bonus_percentage(lottery_setup.streak_bonus_percentage, margin)?;
+            Self::check_bonus_percentage(lottery_setup.referral_bonus_percentage, margin / 2)?;
+
+            for draw in self.lottery_draws(lottery_id).iter() {
+                Self::check_draw_shares(draw, lottery_setup)?;
+            }
+            Ok(())
+        }
+
+        /// Check that the affiliate percentage and the early-bird bonus of a draw fit in
+        /// the operator share
+        fn check_draw_shares(draw: &Draw, lottery_setup: &LotterySetup) -> Result<(), ContractError> {
+            let affiliate_percentage = draw.affiliate_percentage.unwrap_or(lottery_setup.affiliate_percentage);
+            Self::check_affiliate_percentage(affiliate_percentage, &lottery_setup.shares)?;
+            Self::check_bonus_percentage(draw.early_bird_percentage,
+                Self::operator_margin(&lottery_setup.shares, affiliate_percentage))
+        }
+
+        /// Check that the number is within the draw format
+        fn check_bet_number(number: u16) -> Result<(), ContractError> {
+            if number < MIN_BET_NUMBER || number > MAX_BET_NUMBER {
+                return Err(Error::BetNumberOutOfRange {
+                    min: MIN_BET_NUMBER,
+                    max: MAX_BET_NUMBER,
+                    actual: number,
+                }.into());
+            }
+            Ok(())
+        }
+
+        /// Generate a random winning number from the randomness source of the lottery
+        /// and the salt.  Unit tests can inject the numbers or the seed, see
+        /// `randomness::mock`.
+        fn random_winning_number(&self, lottery_id: u32, draw_number: u32) -> Result<u16, ContractError> {
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            let randomness_source = &lottery_setup.randomness_source;
+            #[cfg(feature = "randomness-extension")]
+            let starting_block = lottery_setup.starting_block;
+
+            #[cfg(test)]
+            if let Some(number) = randomness::mock::next_winning_number() {
+                return Ok(number);
+            }
+
+            // The subject identifies the draw processing at the randomness source
+            let mut subject = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<hash::Keccak256, _>(&(lottery_id, draw_number, self.salt), &mut subject);
+
+            let seed: [u8; 32] = match randomness_source {
+                RandomnessSource::Internal => {
+                    let mut seed = <hash::Keccak256 as hash::HashOutput>::Type::default();
+                    ink::env::hash_encoded::<hash::Keccak256, _>(&self.env().block_timestamp(), &mut seed);
+                    seed
+                }
+                RandomnessSource::Runtime => {
+                    #[cfg(feature = "randomness-extension")]
+                    {
+                        crate::chain_extension::fetch_random(subject)
+                    }
+                    #[cfg(not(feature = "randomness-extension"))]
+                    {
+                        return Err(RuntimeError::ChainExtensionFailed.into());
+                    }
+                }
+                RandomnessSource::Oracle(oracle) => randomness::oracle_random(*oracle, subject)?,
+                RandomnessSource::Vrf(provider) => randomness::vrf_output(*provider, subject)?,
+                // The number is published by the operator, see `publish_result`
+                RandomnessSource::CommitReveal => return Err(Error::ResultNotPublished.into()),
+                RandomnessSource::Epoch => {
+                    #[cfg(feature = "randomness-extension")]
+                    {
+                        // Randomness known while the bets were accepted could be exploited
+                        let (seed, known_since) = crate::chain_extension::fetch_epoch_random(subject);
+                        let bets_closed = starting_block + self.draw(lottery_id, draw_number)?.processing_blocks;
+                        if known_since <= bets_closed {
+                            return Err(RuntimeError::RandomnessUnavailable.into());
+                        }
+                        seed
+                    }
+                    #[cfg(not(feature = "randomness-extension"))]
+                    {
+                        return Err(RuntimeError::ChainExtensionFailed.into());
+                    }
+                }
+                // The salt revealed by the operator, see `process_draw_with_salt`
+                RandomnessSource::SaltReveal => {
+                    let revealed = self.draw(lottery_id, draw_number)?.committed_salt
+                        .and_then(|c| c.salt.map(|salt| (salt, c.reveal_block, c.entropy)))
+                        .ok_or(Error::SaltNotRevealed)?;
+                    let mut seed = <hash::Keccak256 as hash::HashOutput>::Type::default();
+                    ink::env::hash_encoded::<hash::Keccak256, _>(&revealed, &mut seed);
+                    seed
+                }
+            };
+            #[cfg(test)]
+            let seed = randomness::mock::seed().unwrap_or(seed);
+
+            let mut input: Vec<u8> = Vec::new();
+            input.extend_from_slice(&seed);
+            input.extend_from_slice(&self.salt.to_be_bytes());
+
+            let mut output = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_bytes::<hash::Keccak256>(&input, &mut output);
+
+            let raw = u16::from_le_bytes([output[0], output[1]]);
+            Ok((raw % (MAX_BET_NUMBER - MIN_BET_NUMBER + 1)) + MIN_BET_NUMBER)
+        }
+
+        /// Compute the winners and the transfers of a draw settlement
+        fn plan_settlement(&self, lottery_id: u32, draw_number: u32) -> Result<SettlementPlan, ContractError> {
+            let lottery = self.lottery(lottery_id)?;
+
+            // Check if the draw exist
+            let draw = self.draw(lottery_id, draw_number)?;
+
+            // The current block must be greater or equal to the draw closing blocks.
+            let current_block: u32 = self.env().block_number();
+            let draw_closing_blocks: u32 = lottery.lottery_setup.starting_block + draw.closing_blocks;
+            if draw_closing_blocks > current_block  {
+                return Err(Error::DrawNotDue {
+                    due_block: draw_closing_blocks,
+                    current_block: current_block,
+                }.into());
+            }
+
+            // A committed result must be published and the winning number must still be
+            // the committed one
+            if let Some(result) = &draw.result {
+                let salt = result.salt.ok_or(Error::ResultNotPublished)?;
+                if Self::result_commitment(draw.winning_number, salt) != result.commitment {
+                    return Err(Error::CommitmentMismatch.into());
+                }
+            }
+
+            // Compact bets are settled like any bet
+            let bets = self.draw_bets(lottery_id, &draw);
+
+            // Compute the winners with the settlement model of the draw
+            let mut plan = match draw.settlement_mode {
+                SettlementMode::PariMutuel => Self::plan_pari_mutuel(&lottery.lottery_setup, &draw, &bets)?,
+                SettlementMode::FixedOdds { multiplier } => Self::plan_fixed_odds(&draw, &bets, multiplier),
+            };
+
+            // The bets matching the bonus number share the progressive jackpot
+            let progressive_winners: Vec<AccountId> = bets
+                .iter()
+                .filter(|b| b.bet_number == draw.bonus_number)
+                .map(|b| b.owner)
+                .collect();
+            let mut progressive_paid: u128 = 0;
+
+            if !progressive_winners.is_empty() {
+                let progressive_share = lottery.progressive.balance / progressive_winners.len() as u128;
+
+                for owner in progressive_winners.iter() {
+                    plan.payouts.push(Payout {
+                        recipient: *owner,
+                        role: PayoutRole::Progressive,
+                        amount: progressive_share,
+                        destination: None,
+                    });
+                    progressive_paid += progressive_share;
+                }
+            }
+
+            // Distribute the shares of the rebate to the bettors, every bet is weighted by
+            // its stake and the rebate tier of its owner
+            let weights = Self::rebate_weights(&lottery.lottery_setup.rebate_tiers, &bets);
+            let total_weight: u128 = bets.iter().map(|b| weights[&b.owner] * b.stake).sum();
+
+            if total_weight > 0 {
+                for bet in bets.iter() {
+                    plan.payouts.push(Payout {
+                        recipient: bet.owner,
+                        role: PayoutRole::Rebate,
+                        amount: Self::mul_div(draw.rebate, weights[&bet.owner] * bet.stake, total_weight),
+                        destination: None,
+                    });
+                }
+            }
+
+            // The first bets of the draw share the early-bird bonus
+            let early_birds = bets.len().min(draw.early_bird_bets as usize);
+            if draw.early_bird_pool > 0 && early_birds > 0 {
+                let early_bird_share = draw.early_bird_pool / early_birds as u128;
+
+                for bet in bets.iter().take(early_birds) {
+                    plan.payouts.push(Payout {
+                        recipient: bet.owner,
+                        role: PayoutRole::EarlyBird,
+                        amount: early_bird_share,
+                        destination: None,
+                    });
+                }
+                plan.early_bird_paid = draw.early_bird_pool;
+            }
+
+            plan.rebate_paid = draw.rebate;
+            plan.progressive_winners = progressive_winners;
+            plan.progressive_paid = progressive_paid;
+            Ok(plan)
+        }
+
+        /// Rebate weight of every owner of a bet in the draw, i.e., the weight of the
+        /// highest tier reached by its number of bets
+        fn rebate_weights(rebate_tiers: &[RebateTier], bets: &[Bet]) -> BTreeMap<AccountId, u128> {
+            let mut counts: BTreeMap<AccountId, u32> = BTreeMap::new();
+            for bet in bets.iter() {
+                *counts.entry(bet.owner).or_insert(0) += 1;
+            }
+
+            counts.into_iter()
+                .map(|(owner, count)| {
+                    let weight = rebate_tiers.iter()
+                        .rev()
+                        .find(|t| count >= t.min_bets)
+                        .map_or(1, |t| t.weight);
+                    (owner, weight as u128)
+                })
+                .collect()
+        }
+
+        /// Pari-mutuel settlement, the winners of every division share the jackpot
+        fn plan_pari_mutuel(lottery_setup: &LotterySetup,
+            draw: &Draw,
+            bets: &[Bet]) -> Result<SettlementPlan, ContractError> {
+
+            // The prize tiers of the draw replace the divisions
+            if !draw.prize_tiers.is_empty() {
+                return Ok(Self::plan_prize_tiers(lottery_setup, draw, bets));
+            }
+
+            // Get the winners of every division, a bet wins in one division at most
+            let winning_number = draw.winning_number;
+            let mut winners = Self::division_winners(draw, bets, 0, |n| n == winning_number);
+            let mut second_winners = Self::division_winners(draw, bets, 1, |n| n.abs_diff(winning_number) == 1);
+            let mut third_winners = Self::division_winners(draw, bets, 2, |n| {
+                n != winning_number && n % 100 == winning_number % 100
+            });
+
+            // Count the number of winners
+            let count_winners = winners.len() as u128;
+            let winner_percentage = lottery_setup.shares.winner;
+
+            let mut jackpot_paid: u128 = 0;
+
+            // Distribute the share of the jackpot to the winners
+            let first_pot = draw.jackpot * draw.division_shares.first as u128 / 100;
+            if count_winners > 0 {
+                match draw.payout_strategy {
+                    Some(strategy) => {
+                        // The strategy contract computes the shares of the winners
+                        let shares = payout_strategy::compute_shares(strategy,
+                            draw.draw_number,
+                            first_pot,
+                            count_winners as u32,
+                            bets.len() as u32)?;
+
+                        for (w, (bettor_share, upline_share)) in winners.iter_mut().zip(shares) {
+                            w.bettor_share = bettor_share;
+                            w.upline_share = upline_share;
+                            jackpot_paid += bettor_share + upline_share;
+                        }
+                    }
+                    None => {
+                        jackpot_paid += Self::split_division(&mut winners, first_pot, winner_percentage);
+                    }
+                }
+            }
+
+            // The lower divisions always use the default split
+            jackpot_paid += Self::split_division(&mut second_winners,
+                draw.jackpot * draw.division_shares.second as u128 / 100, winner_percentage);
+            jackpot_paid += Self::split_division(&mut third_winners,
+                draw.jackpot * draw.division_shares.third as u128 / 100, winner_percentage);
+
+            let payouts = Self::winner_payouts(lottery_setup,
+                winners.iter().chain(second_winners.iter()).chain(third_winners.iter()),
+                bets);
+
+            Ok(SettlementPlan {
+                draw_number: draw.draw_number,
+                winning_number: draw.winning_number,
+                winners: winners,
+                second_winners: second_winners,
+                third_winners: third_winners,
+                payouts: payouts,
+                jackpot_paid: jackpot_paid,
+                rebate_paid: 0,
+                progressive_winners: Vec::new(),
+                progressive_paid: 0,
+                house_paid: 0,
+                early_bird_paid: 0,
+            })
+        }
+
+        /// Prize tier settlement, the winners of every tier share its percentage of the
+        /// jackpot.  A bet wins in the first tier it matches, i.e., the tier with the most
+        /// digits.
+        fn plan_prize_tiers(lottery_setup: &LotterySetup, draw: &Draw, bets: &[Bet]) -> SettlementPlan {
+            let winning_number = draw.winning_number;
+            let matches = |n: u16, digits: u8| {
+                let modulus = 10u16.pow(digits as u32);
+                n % modulus == winning_number % modulus
+            };
+
+            let mut winners: Vec<Winner> = Vec::new();
+            let mut jackpot_paid: u128 = 0;
+            for (index, tier) in draw.prize_tiers.iter().enumerate() {
+                let mut tier_winners = Self::division_winners(draw, bets, index as u8, |n| {
+                    matches(n, tier.digits)
+                        && !draw.prize_tiers[..index].iter().any(|t| matches(n, t.digits))
+                });
+                jackpot_paid += Self::split_division(&mut tier_winners,
+                    draw.jackpot * tier.percentage as u128 / 100, lottery_setup.shares.winner);
+                winners.extend(tier_winners);
+            }
+
+            let payouts = Self::winner_payouts(lottery_setup, winners.iter(), bets);
+
+            SettlementPlan {
+                draw_number: draw.draw_number,
+                winning_number: winning_number,
+                winners: winners,
+                second_winners: Vec::new(),
+                third_winners: Vec::new(),
+                payouts: payouts,
+                jackpot_paid: jackpot_paid,
+                rebate_paid: 0,
+                progressive_winners: Vec::new(),
+                progressive_paid: 0,
+                house_paid: 0,
+                early_bird_paid: 0,
+            }
+        }
+
+        /// Plan the transfers of the winners and their upline.  The share of an upline
+        /// that is not actively betting goes to the operator.
+        fn winner_payouts<'a>(lottery_setup: &LotterySetup,
+            winners: impl Iterator<Item = &'a Winner>,
+            bets: &[Bet]) -> Vec<Payout> {
+
+            let mut payouts: Vec<Payout> = Vec::new();
+            for winner in winners {
+                // Winners
+                payouts.push(Payout {
+                    recipient: winner.owner,
+                    role: PayoutRole::Winner,
+                    amount: winner.bettor_share,
+                    destination: winner.destination.clone(),
+                });
+
+                // Upline
+                if bets.iter().find(|b| b.bettor == winner.upline).is_none() {
+                    // If the upline is not actively betting the share will go to the operator
+                    payouts.push(Payout {
+                        recipient: lottery_setup.operator,
+                        role: PayoutRole::Operator,
+                        amount: winner.upline_share,
+                        destination: None,
+                    });
+                } else {
+                    // If the upline is actively betting
+                    payouts.push(Payout {
+                        recipient: winner.upline,
+                        role: PayoutRole::Upline,
+                        amount: winner.upline_share,
+                        destination: None,
+                    });
+                }
+            }
+            payouts
+        }
+
+        /// Fixed-odds settlement, every bet matching the winning number is paid the odds
+        /// of the draw on its stake.  The jackpot pays first and the house covers the rest.
+        fn plan_fixed_odds(draw: &Draw, bets: &[Bet], multiplier: u32) -> SettlementPlan {
+            let winning_number = draw.winning_number;
+            let mut winners = Self::division_winners(draw, bets, 0, |n| n == winning_number);
+
+            let mut payouts: Vec<Payout> = Vec::new();
+            let mut total: u128 = 0;
+            for winner in winners.iter_mut() {
+                let prize = winner.stake.saturating_mul(multiplier as u128);
+                winner.bettor_share = prize;
+                total = total.saturating_add(prize);
+                payouts.push(Payout {
+                    recipient: winner.owner,
+                    role: PayoutRole::House,
+                    amount: prize,
+                    destination: winner.destination.clone(),
+                });
+            }
+
+            let jackpot_paid = total.min(draw.jackpot);
+
+            SettlementPlan {
+                draw_number: draw.draw_number,
+                winning_number: winning_number,
+                winners: winners,
+                second_winners: Vec::new(),
+                third_winners: Vec::new(),
+                payouts: payouts,
+                jackpot_paid: jackpot_paid,
+                rebate_paid: 0,
+                progressive_winners: Vec::new(),
+                progressive_paid: 0,
+                house_paid: total - jackpot_paid,
+                early_bird_paid: 0,
+            }
+        }
+
+        /// Winners of a division or a prize tier, i.e., the bets whose number matches it
+        fn division_winners(draw: &Draw, bets: &[Bet], tier: u8, matches: impl Fn(u16) -> bool) -> Vec<Winner> {
+            bets.iter()
+                .filter(|b| matches(b.bet_number))
+                .map(|b| Winner {
+                    draw_number: draw.draw_number,
+                    bettor: b.bettor,
+                    owner: b.owner,
+                    // Only the original bettor is registered at the foreign location
+                    destination: if b.owner == b.bettor { b.origin.clone() } else { None },
+                    upline: b.upline,
+                    bet_number: b.bet_number,
+                    tx_hash: b.tx_hash.clone(),
+                    stake: b.stake,
+                    bettor_share: 0,
+                    upline_share: 0,
+                    tier: tier,
+                })
+                .collect()
+        }
+
+        /// Split the pot of a division among its winners in shares of their stake, the
+        /// winner percentage to the bettors and the rest to their upline.  Returns the
+        /// amount taken from the jackpot, i.e., the shares actually assigned; the rounding
+        /// stays in the jackpot.
+        fn split_division(winners: &mut [Winner], pot: u128, winner_percentage: u8) -> u128 {
+            if winners.is_empty() {
+                return 0;
+            }
+
+            // Free bets of a promotional draw share equally
+            let total_stake: u128 = winners.iter().map(|w| w.stake.max(1)).sum();
+            let jackpot_share = pot * winner_percentage as u128 / 100;
+            let upline_share  = pot * (100 - winner_percentage) as u128 / 100;
+
+            for w in winners.iter_mut() {
+                w.bettor_share = Self::mul_div(jackpot_share, w.stake.max(1), total_stake);
+                w.upline_share = Self::mul_div(upline_share, w.stake.max(1), total_stake);
+            }
+            winners.iter().map(|w| w.bettor_share + w.upline_share).sum()
+        }
+
+        /// `amount * part / total` rounded down, the product is computed in 256 bits so
+        /// pro-rata shares of large amounts don't overflow.  Zero if the total is zero.
+        fn mul_div(amount: u128, part: u128, total: u128) -> u128 {
+            helpers_128bit::multiply_by_rational_with_rounding(amount, part, total, Rounding::Down)
+                .unwrap_or(0)
+        }
+
+        /// Transfer all planned payouts of a draw.  A failing transfer does not abort
+        /// the settlement; it is returned so it can be retried later.
+        fn settle(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            payouts: Vec<Payout>) -> Result<Vec<TransferFailure>, ContractError> {
+
+            let mut failed: Vec<TransferFailure> = Vec::new();
+            for p in payouts {
+                // Prizes of foreign winners are delivered via XCM, if the send fails the
+                // prize is kept in escrow for the winner to claim locally.
+                if let Some(destination) = p.destination {
+                    if self.payout_xcm(lottery_id, draw_number, &destination, p.role, p.amount).is_err() {
+                        let key = (p.recipient, lottery_id);
+                        let mut escrow = self.escrow.get(key).unwrap_or_default();
+                        escrow.push(Escrow {
+                            draw_number: draw_number,
+                            account: p.recipient,
+                            amount: p.amount,
+                        });
+                        let previous = self.escrow.insert(key, &escrow).unwrap_or(0);
+                        self.mapped_bytes = (self.mapped_bytes + escrow.encoded_size() as u32).saturating_sub(previous);
+                    }
+                    continue;
+                }
+                match self.payout(lottery_id, draw_number, p.recipient, p.role, p.amount) {
+                    Err(ContractError::TransferFailed(failure)) => failed.push(failure),
+                    Err(e) => return Err(e),
+                    Ok(()) => {}
+                }
+            }
+            Ok(failed)
+        }
+
+        /// Settle a processed draw, see `close_draw`.  The settlement is stored before the
+        /// payouts are transferred.
+        fn settle_draw(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            // Compute the winners and the transfers of the settlement
+            let plan = self.plan_settlement(lottery_id, draw_number)?;
+            let (dust, failed) = self.settlement_totals(lottery_id, draw_number)?;
+
+            // Store the settlement before any transfer is dispatched
+            let (bets, bet_amount) = self.commit_settlement(lottery_id, draw_number, &plan)?;
+
+            // Payouts of a band with approvals or a delay are held, the rest is paid now
+            let payouts = self.hold_payouts(lottery_id, draw_number, plan.payouts.clone())?;
+            self.pay_out(lottery_id, draw_number, payouts)?;
+
+            let winners = plan.all_winners();
+
+            // Rank the prizes of the draw in the leaderboard
+            for payout in plan.payouts.iter() {
+                if matches!(payout.role, PayoutRole::Winner | PayoutRole::House | PayoutRole::Progressive) {
+                    self.record_winnings(lottery_id, payout.recipient, payout.amount)?;
+                }
+            }
+
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawClosed {
+                draw_number: draw_number,
+                winning_number: plan.winning_number,
+                winners: winners.len() as u32,
+                jackpot_paid: plan.jackpot_paid,
+                rebate_paid: plan.rebate_paid,
+            });
+
+            // Mint the commemorative NFTs of the winners
+            self.mint_winner_nfts(lottery_id, &winners)?;
+
+            // Let the companion contract aggregate the settlement, it never blocks closing
+            if let Some(companion) = self.companion {
+                let bettors: Vec<AccountId> = bets.iter().map(|b| b.owner).collect();
+                let prizes: Vec<(AccountId, u128)> = winners.iter()
+                    .map(|w| (w.owner, w.bettor_share))
+                    .collect();
+                let _ = companion::on_settlement(companion, lottery_id, draw_number, bet_amount, bettors, prizes);
+            }
+
+            // Report the wins of bets placed by contracts for their users, a failing
+            // integrating contract does not block closing either
+            for winner in winners.iter() {
+                if winner.owner != winner.bettor && self.env().is_contract(&winner.owner) {
+                    let _ = integration::on_win(winner.owner,
+                        lottery_id,
+                        draw_number,
+                        winner.bettor,
+                        winner.bet_number,
+                        winner.bettor_share);
+                }
+            }
+
+            // Summary of the settlement, the authoritative record for indexers
+            let (total_dust, total_failed) = self.settlement_totals(lottery_id, draw_number)?;
+            self.emit_success(lottery_id, Some(draw_number), Success::DrawSettled {
+                draw_number: draw_number,
+                winners: winners.len() as u32,
+                jackpot_paid: plan.jackpot_paid,
+                rebate_paid: plan.rebate_paid,
+                dust: total_dust.saturating_sub(dust),
+                failed_payouts: total_failed.saturating_sub(failed),
+            });
+            Ok(())
+
+        }
+
+        /// Dust of a draw and its payouts queued for retry, see `DrawSettled`
+        fn settlement_totals(&self, lottery_id: u32, draw_number: u32) -> Result<(u128, u32), ContractError> {
+            let draw = self.draw(lottery_id, draw_number)?;
+            Ok((draw.reconciliation.dust, draw.failed_payouts.len() as u32))
+        }
+
+        /// Preview close draw
+        ///
+        /// Computes the settlement `close_draw` would perform at the current block without
+        /// committing anything: the winners with their shares and every transfer (each
+        /// one emits a `WinnerPaid` event when closing).
+        #[ink(message)]
+        pub fn preview_close_draw(&self, lottery_id: u32, draw_number: u32) -> Result<SettlementPlan, ContractError> {
+            self.plan_settlement(lottery_id, draw_number)
+        }
+
+        /// Estimate the payout of a number
+        ///
+        /// Returns the prize per winner if the number hit now, i.e., the first division
+        /// (or top prize tier) share of the current jackpot split among the bets already
+        /// on the number (or a single winner if there is none).  Fixed-odds draws return the fixed prize.  The
+        /// estimate is for a bet of the minimum stake among bets of the same stake, the
+        /// shares of a payout strategy are not estimated, the default split is assumed.
+        #[ink(message)]
+        pub fn estimate_payout(&self, lottery_id: u32, draw_number: u32, bet_number: u16) -> Result<u128, ContractError> {
+            Self::check_bet_number(bet_number)?;
+
+            let draw = self.draw(lottery_id, draw_number)?;
+
+            if let SettlementMode::FixedOdds { multiplier } = draw.settlement_mode {
+                return Ok(draw.bet_amount.saturating_mul(multiplier as u128));
+            }
+
+            let (winners, _) = self.bets_on(lottery_id, draw_number, bet_number);
+            let winners = winners.max(1) as u128;
+            let first_percentage = draw.prize_tiers.first().map_or(draw.division_shares.first, |t| t.percentage);
+            let first_pot = draw.jackpot * first_percentage as u128 / 100;
+            let winner_percentage = self.lottery(lottery_id)?.lottery_setup.shares.winner;
+
+            Ok(first_pot * winner_percentage as u128 / 100 / winners)
+        }
+
+        /// Retry failed payouts
+        ///
+        /// 1. Only the operator can retry the payouts.
+        /// 2. Only closed draws can be retried.
+        /// 3. Payouts that fail again are kept for the next retry.
+        #[ink(message)]
+        pub fn retry_payouts(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+
+            // Check if operator
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.operator {
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if the draw exist
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            if draw.status != DrawStatus::Close {
+                return Err(Error::DrawNotClosed.into());
+            }
+
+            if draw.failed_payouts.is_empty() {
+                return Err(Error::NoRecords.into());
+            }
+
+            let payouts: Vec<Payout> = core::mem::take(&mut draw.failed_payouts)
+                .into_iter()
+                .map(|f| Payout {
+                    recipient: f.recipient,
+                    role: f.role,
+                    amount: f.amount,
+                    destination: None,
+                })
+                .collect();
+            let attempted = payouts.len() as u32;
+            self.save_draw(lottery_id, &draw);
+
+            let failed_payouts = self.settle(lottery_id, draw_number, payouts)?;
+            let failed = failed_payouts.len() as u32;
+
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            let pending: u128 = failed_payouts.iter().map(|f| f.amount).sum();
+            let paid = draw.reconciliation.pending_payouts.saturating_sub(pending);
+            draw.reconciliation.prizes_paid += paid;
+            draw.reconciliation.pending_payouts = pending;
+            draw.failed_payouts = failed_payouts;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::PayoutsRetried {
+                draw_number: draw_number,
+                paid: attempted - failed,
+                failed: failed,
+            });
+            Ok(())
+        }
+
+        /// Approve held payouts
+        ///
+        /// 1. Only the dev or the auditor of the lottery can approve the payouts held for
+        ///    a draw, zero for the withdrawals.  Every approver counts once per payout.
+        /// 2. The payouts with all their approvals and past their delay are released.
+        #[ink(message)]
+        pub fn approve_payouts(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+
+            // Check if dev or auditor
+            let caller = self.env().caller();
+            let lottery = self.lottery(lottery_id)?;
+            if caller != lottery.lottery_setup.dev && Some(caller) != lottery.lottery_setup.auditor {
+                return Err(Error::BadOrigin.into());
+            }
+
+            let mut held_payouts = self.held_payouts.get((lottery_id, draw_number)).unwrap_or_default();
+            let mut approved = 0u32;
+            for held in held_payouts.iter_mut() {
+                if !held.approvals.contains(&caller) {
+                    held.approvals.push(caller);
+                    approved += 1;
+                }
+            }
+
+            if approved == 0 {
+                return Err(Error::NoRecords.into());
+            }
+            self.save_held_payouts(lottery_id, draw_number, &held_payouts);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::PayoutsApproved {
+                draw_number: draw_number,
+                approver: caller,
+                payouts: approved,
+            });
+
+            self.release_ready_payouts(lottery_id, draw_number)?;
+            Ok(())
+        }
+
+        /// Release held payouts
+        ///
+        /// 1. Only the operator, the dev or the auditor can release the payouts held for a
+        ///    draw, zero for the withdrawals.
+        /// 2. The payouts with all their approvals and past their delay are transferred,
+        ///    prizes that fail are queued for retry like any settlement transfer.
+        #[ink(message)]
+        pub fn release_payouts(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            if caller != lottery_setup.operator
+                && caller != lottery_setup.dev
+                && Some(caller) != lottery_setup.auditor {
+                return Err(Error::BadOrigin.into());
+            }
+
+            if self.release_ready_payouts(lottery_id, draw_number)? == 0 {
+                return Err(Error::NoRecords.into());
+            }
+            Ok(())
+        }
+
+        /// Store a settlement before its payouts are transferred: the winners, the pools,
+        /// the status of the draw and the accounting.  Returns the bets of the cycle and
+        /// their bet amount.
+        fn commit_settlement(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            plan: &SettlementPlan) -> Result<(Vec<Bet>, u128), ContractError> {
+
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            // Save the winners here.  If there are no winners in the current draw this
+            // also cleans up the winner array.
+            draw.winners = plan.winners.clone();
+            draw.second_winners = plan.second_winners.clone();
+            draw.third_winners = plan.third_winners.clone();
+
+            // Clean the jackpot after we distribute it to the winners of the current draw,
+            // what a payout strategy or the rounding of the shares does not distribute
+            // stays for the next cycle
+            draw.jackpot = draw.jackpot.saturating_sub(plan.jackpot_paid);
+            // All rebate will be distributed to all bettors as we close the draw, what the
+            // split leaves is dust
+            draw.reconciliation.dust += draw.rebate.saturating_sub(plan.rebate_paid)
+                + draw.early_bird_pool.saturating_sub(plan.early_bird_paid);
+            draw.rebate = 0;
+            draw.early_bird_pool = 0;
+            // Report the house edge of the cycle
+            let prizes = plan.jackpot_paid + plan.house_paid + plan.rebate_paid + plan.progressive_paid
+                + plan.early_bird_paid;
+            draw.reconciliation.prizes_paid += prizes;
+            let mut house_edge = HouseEdge::default();
+            house_edge.add(draw.handle, draw.fees, prizes);
+            let (handle, fees) = (draw.handle, draw.fees);
+            draw.house_edge = house_edge;
+            draw.handle = 0;
+            draw.fees = 0;
+            let bonus_number = draw.bonus_number;
+            // Clean up the bets
+            let bets = self.take_bets(lottery_id, &mut draw, |owner| owner);
+            let bet_amount = draw.bet_amount;
+            // Change the status of the draw from open to close
+            draw.status = DrawStatus::Close;
+            draw.is_open = false;
+            self.save_draw(lottery_id, &draw);
+
+            let mut lottery = self.lottery(lottery_id)?;
+            lottery.house_edge.add(handle, fees, prizes);
+            lottery.metrics.draws_closed += 1;
+
+            // The progressive jackpot paid to the bonus number winners starts over
+            let progressive_won = !plan.progressive_winners.is_empty();
+            if progressive_won {
+                lottery.progressive.balance = lottery.progressive.balance.saturating_sub(plan.progressive_paid);
+                lottery.progressive.paid = lottery.progressive.paid.saturating_add(plan.progressive_paid);
+                lottery.progressive.last_hit_draw = Some(draw_number);
+            }
+
+            // The insurance fund covers what the jackpot of a fixed-odds draw could not pay
+            let insurance = &mut lottery.insurance;
+            let covered = plan.house_paid.min(insurance.balance);
+            insurance.balance -= covered;
+            insurance.covered = insurance.covered.saturating_add(covered);
+            let balance = insurance.balance;
+            self.save_lottery(lottery_id, &lottery);
+
+            if progressive_won {
+                self.emit_success(lottery_id, Some(draw_number), Success::ProgressiveJackpotWon {
+                    draw_number: draw_number,
+                    bonus_number: bonus_number,
+                    winners: plan.progressive_winners.len() as u32,
+                    amount: plan.progressive_paid,
+                });
+            }
+            if covered > 0 {
+                self.emit_success(lottery_id, Some(draw_number), Success::InsuranceCovered {
+                    draw_number: draw_number,
+                    amount: covered,
+                    balance: balance,
+                });
+            }
+
+            Ok((bets, bet_amount))
+        }
+
+        /// Keep the transfers of a settlement that failed for retry
+        fn queue_failed_payouts(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            failed_payouts: Vec<TransferFailure>) -> Result<(), ContractError> {
+
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            let pending: u128 = failed_payouts.iter().map(|f| f.amount).sum();
+            draw.reconciliation.prizes_paid = draw.reconciliation.prizes_paid.saturating_sub(pending);
+            draw.reconciliation.pending_payouts += pending;
+            draw.failed_payouts.extend(failed_payouts);
+            self.save_draw(lottery_id, &draw);
+            Ok(())
+        }
+
+        /// Hold the payouts whose payout policy requires approvals or a delay and return
+        /// the payouts to transfer now.  The draw number is zero for the withdrawals.
+        fn hold_payouts(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            payouts: Vec<Payout>) -> Result<Vec<Payout>, ContractError> {
+
+            let block_number = self.env().block_number();
+            let lottery = self.lottery(lottery_id)?;
+            if lottery.lottery_setup.payout_policies.is_empty() {
+                return Ok(payouts);
+            }
+
+            let mut immediate: Vec<Payout> = Vec::new();
+            let mut held: Vec<HeldPayout> = Vec::new();
+            for payout in payouts {
+                let policy = lottery.lottery_setup.payout_policies.iter()
+                    .rev()
+                    .find(|p| payout.amount >= p.min_amount)
+                    .filter(|p| p.approvals > 0 || p.delay_blocks > 0);
+                match policy {
+                    Some(policy) => held.push(HeldPayout {
+                        draw_number: draw_number,
+                        payout: payout,
+                        approvals: Vec::new(),
+                        required_approvals: policy.approvals,
+                        release_block: block_number.saturating_add(policy.delay_blocks),
+                    }),
+                    None => immediate.push(payout),
+                }
+            }
+
+            let pending: u128 = held.iter().map(|h| h.payout.amount).sum();
+            let mut held_payouts = self.held_payouts.get((lottery_id, draw_number)).unwrap_or_default();
+            held_payouts.extend(held.iter().cloned());
+            self.save_held_payouts(lottery_id, draw_number, &held_payouts);
+            if draw_number > 0 {
+                let mut draw = self.draw(lottery_id, draw_number)?;
+                draw.reconciliation.prizes_paid = draw.reconciliation.prizes_paid.saturating_sub(pending);
+                draw.reconciliation.pending_payouts += pending;
+                self.save_draw(lottery_id, &draw);
+            }
+
+            for h in held {
+                self.emit_success(lottery_id, Some(draw_number), Success::PayoutHeld {
+                    draw_number: draw_number,
+                    recipient: h.payout.recipient,
+                    role: h.payout.role,
+                    amount: h.payout.amount,
+                    required_approvals: h.required_approvals,
+                    release_block: h.release_block,
+                });
+            }
+            Ok(immediate)
+        }
+
+        /// Transfer the held payouts of a draw with all their approvals and past their
+        /// delay, returns the number of released payouts
+        fn release_ready_payouts(&mut self, lottery_id: u32, draw_number: u32) -> Result<u32, ContractError> {
+            let block_number = self.env().block_number();
+            self.lottery(lottery_id)?;
+            let (ready, held): (Vec<HeldPayout>, Vec<HeldPayout>) = self.held_payouts
+                .get((lottery_id, draw_number))
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|h| h.approvals.len() >= h.required_approvals as usize
+                    && block_number >= h.release_block);
+            self.save_held_payouts(lottery_id, draw_number, &held);
+
+            if ready.is_empty() {
+                return Ok(0);
+            }
+
+            let payouts: Vec<Payout> = ready.into_iter().map(|h| h.payout).collect();
+            let amount: u128 = payouts.iter().map(|p| p.amount).sum();
+            let count = payouts.len() as u32;
+            if draw_number > 0 {
+                let mut draw = self.draw(lottery_id, draw_number)?;
+                draw.reconciliation.prizes_paid += amount;
+                draw.reconciliation.pending_payouts = draw.reconciliation.pending_payouts.saturating_sub(amount);
+                self.save_draw(lottery_id, &draw);
+            }
+
+            self.guarded(|lottery| {
+                if draw_number > 0 {
+                    return lottery.pay_out(lottery_id, draw_number, payouts);
+                }
+                for p in payouts {
+                    lottery.transfer(lottery_id, 0, p.recipient, p.role, p.amount)?;
+                }
+                Ok(())
+            })?;
+
+            self.emit_success(lottery_id, Some(draw_number), Success::PayoutsReleased {
+                draw_number: draw_number,
+                payouts: count,
+                amount: amount,
+            });
+            Ok(count)
+        }
+
+        /// Transfer settlement payouts: the vault pays the jackpot of the draw, the rest
+        /// is paid by the contract.  Transfers that fail are queued for retry.
+        fn pay_out(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            payouts: Vec<Payout>) -> Result<(), ContractError> {
+
+            // The local payouts of a lottery with claims are only recorded
+            let payouts = match self.lottery(lottery_id)?.lottery_setup.claim_payouts {
+                true => self.record_claims(lottery_id, draw_number, payouts)?,
+                false => payouts,
+            };
+
+            let payouts = match self.lottery(lottery_id)?.lottery_setup.vault {
+                Some(vault) => self.settle_vault(lottery_id, draw_number, vault, payouts)?,
+                None => payouts,
+            };
+
+            // Transfer the balances of the winners, the upline and the rebates of the
+            // bettors
+            let failed_payouts = match self.lottery(lottery_id)?.lottery_setup.payment_backend {
+                PaymentBackend::Psp22(_) | PaymentBackend::Simulated => self.settle(lottery_id, draw_number, payouts)?,
+                _ => self.settle_atomic(lottery_id, draw_number, payouts)?,
+            };
+            self.queue_failed_payouts(lottery_id, draw_number, failed_payouts)
+        }
+
+        /// Record the local payouts of a settlement as claims of their recipients and
+        /// return the foreign payouts.  The vault releases the jackpot of the claims to
+        /// the contract.
+        fn record_claims(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            payouts: Vec<Payout>) -> Result<Vec<Payout>, ContractError> {
+
+            let (foreign, local): (Vec<Payout>, Vec<Payout>) = payouts
+                .into_iter()
+                .partition(|p| p.destination.is_some());
+            if local.is_empty() {
+                return Ok(foreign);
+            }
+
+            if let Some(vault) = self.lottery(lottery_id)?.lottery_setup.vault {
+                let jackpot: u128 = local.iter()
+                    .filter(|p| matches!(p.role, PayoutRole::Winner | PayoutRole::Upline | PayoutRole::Operator))
+                    .map(|p| p.amount)
+                    .sum();
+                if jackpot > 0 {
+                    vault::release(vault, lottery_id, draw_number, jackpot)?;
+                }
+            }
+
+            let claims = local.len() as u32;
+            let amount: u128 = local.iter().map(|p| p.amount).sum();
+            for p in local {
+                let mut claim = self.claims.get((p.recipient, lottery_id)).unwrap_or_default();
+                match p.role {
+                    PayoutRole::Rebate => claim.rebates = claim.rebates.saturating_add(p.amount),
+                    _ => claim.prizes = claim.prizes.saturating_add(p.amount),
+                }
+                self.claims.insert((p.recipient, lottery_id), &claim);
+            }
+
+            self.emit_success(lottery_id, Some(draw_number), Success::ClaimsRecorded {
+                draw_number: draw_number,
+                claims: claims,
+                amount: amount,
+            });
+            Ok(foreign)
+        }
+
+        /// Close a draw below its minimum participation without drawing: the pools and
+        /// the statement of the cycle are restored and the stakes of the cycle are
+        /// refunded to the owners of the bets in proportion to their stakes
+        fn refund_stakes(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            let mut draw = self.draw(lottery_id, draw_number)?;
+
+            let bets = self.take_bets(lottery_id, &mut draw, |owner| owner);
+            let (payouts, carried) = self.plan_refund(lottery_id, &draw, &bets);
+            let participation = &mut draw.participation;
+            let (progressive, insurance) = (participation.progressive, participation.insurance);
+            participation.withheld = Vec::new();
+            participation.progressive = 0;
+            participation.insurance = 0;
+            draw.jackpot = participation.jackpot;
+            draw.reconciliation = participation.reconciliation.clone();
+            draw.rebate = 0;
+            draw.early_bird_pool = 0;
+            draw.handle = 0;
+            draw.fees = 0;
+            draw.status = DrawStatus::Close;
+            draw.is_open = false;
+            self.update_lottery(lottery_id, |l| {
+                l.progressive.balance = l.progressive.balance.saturating_sub(progressive);
+                l.progressive.contributed = l.progressive.contributed.saturating_sub(progressive);
+                l.insurance.balance = l.insurance.balance.saturating_sub(insurance);
+                l.insurance.contributed = l.insurance.contributed.saturating_sub(insurance);
+            })?;
+
+            let refunded: u128 = payouts.iter().map(|p| p.amount).sum();
+            let carried_count = carried.len() as u32;
+            draw.participation.carried_bets.extend(carried);
+            self.save_draw(lottery_id, &draw);
+
+            let failed_payouts = self.settle(lottery_id, draw_number, payouts)?;
+            let pending: u128 = failed_payouts.iter().map(|f| f.amount).sum();
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            draw.reconciliation.refunded += refunded - pending;
+            draw.reconciliation.pending_payouts += pending;
+            draw.failed_payouts.extend(failed_payouts);
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::StakesRefunded {
+                draw_number: draw_number,
+                bets: bets.len() as u32 - carried_count,
+                amount: refunded,
+                carried: carried_count,
+            });
+            Ok(())
+        }
+
+        /// Refunds and bets carried forward of a cycle refunded by `refund_stakes`.  The
+        /// contract holds every stake of the cycle, the rounding stays as dust.  Bets
+        /// carried forward keep their part of the stakes for the next cycle.
+        fn plan_refund(&self, lottery_id: u32, draw: &Draw, bets: &[Bet]) -> (Vec<Payout>, Vec<Bet>) {
+            let carry_default = draw.participation.carry_forward;
+            let total_stake: u128 = bets.iter().map(|b| b.stake).sum();
+            let mut payouts: Vec<Payout> = Vec::new();
+            let mut carried: Vec<Bet> = Vec::new();
+            for bet in bets.iter() {
+                let amount = Self::mul_div(draw.handle, bet.stake, total_stake);
+                if self.carry_forward.get((bet.owner, lottery_id)).unwrap_or(carry_default) {
+                    carried.push(Bet {
+                        stake: amount,
+                        bundle_id: None,
+                        ..bet.clone()
+                    });
+                } else {
+                    payouts.push(Self::share(bet.owner, PayoutRole::Refund, amount));
+                }
+            }
+            (payouts, carried)
+        }
+
+        /// Place the bets carried forward into the draw again, paid by the stakes held by
+        /// the contract
+        fn place_carried_bets(&mut self, lottery_id: u32, draw_number: u32) -> Result<(), ContractError> {
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            let bets = core::mem::take(&mut draw.participation.carried_bets);
+            if bets.is_empty() {
+                return Ok(());
+            }
+            self.save_draw(lottery_id, &draw);
+
+            let mut placed = 0u32;
+            let mut refunded = 0u128;
+            for bet in bets {
+                let result = self.record_bet(lottery_id, draw_number, bet.bet_number, bet.owner, bet.upline,
+                    bet.tx_hash, bet.stake, 0);
+                // Only a rejected bet is refunded, a failing transfer fails the opening
+                match result {
+                    Ok(()) => placed += 1,
+                    Err(ContractError::Internal(_)) => {
+                        self.guarded(|lottery| lottery.transfer(lottery_id, draw_number, bet.owner,
+                            PayoutRole::Refund, bet.stake))?;
+                        refunded += bet.stake;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let mut draw = self.draw(lottery_id, draw_number)?;
+            draw.reconciliation.refunded += refunded;
+            self.save_draw(lottery_id, &draw);
+
+            self.emit_success(lottery_id, Some(draw_number), Success::BetsCarriedForward {
+                draw_number: draw_number,
+                placed: placed,
+                refunded: refunded,
+            });
+            Ok(())
+        }
+
+        /// Transfer all local payouts of a draw in a single `batch_all` runtime call so
+        /// either all of them or none are paid.  Payouts to foreign locations are
+        /// delivered afterwards via XCM.
+        fn settle_atomic(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            payouts: Vec<Payout>) -> Result<Vec<TransferFailure>, ContractError> {
+
+            let (foreign, local): (Vec<Payout>, Vec<Payout>) = payouts
+                .into_iter()
+                .partition(|p| p.destination.is_some());
+
+            if !local.is_empty() {
+                let lottery = self.lottery(lottery_id)?;
+                let calls: Vec<RuntimeCall> = local.iter()
+                    .map(|p| Self::runtime_transfer(&lottery.lottery_setup, p.recipient, p.amount))
+                    .collect();
+
+                let result = runtime::dispatch(&RuntimeCall::Utility(UtilityCall::BatchAll { calls: calls }));
+                let attempted = local.len() as u64;
+                let failed = if result.is_err() { attempted } else { 0 };
+                self.update_lottery(lottery_id, |l| {
+                    l.metrics.transfers_attempted += attempted;
+                    l.metrics.transfers_failed += failed;
+                })?;
+
+                // Nothing of a failing batch is paid, every payout is kept for retry
+                if let Err(cause) = result {
+                    let mut failed: Vec<TransferFailure> = Vec::new();
+                    for p in local {
+                        self.env().emit_event(PayoutFailed {
+                            recipient: p.recipient,
+                            lottery_id: lottery_id,
+                            draw_number: draw_number,
+                            role: p.role.clone(),
+                            amount: p.amount,
+                            error: cause.clone(),
+                        });
+                        failed.push(TransferFailure {
+                            draw_number: draw_number,
+                            recipient: p.recipient,
+                            role: p.role,
+                            amount: p.amount,
+                            cause: cause.clone(),
+                        });
+                    }
+                    failed.extend(self.settle(lottery_id, draw_number, foreign)?);
+                    return Ok(failed);
+                }
+
+                for p in local {
+                    self.env().emit_event(WinnerPaid {
+                        recipient: p.recipient,
+                        lottery_id: lottery_id,
+                        draw_number: draw_number,
+                        role: p.role,
+                        amount: p.amount,
+                    });
+                }
+            }
+
+            self.settle(lottery_id, draw_number, foreign)
+        }
+
+        /// Instruct the vault to pay the local jackpot payouts (winners and uplines) of a
+        /// draw.  The jackpot of foreign winners is released to the contract to deliver it
+        /// via XCM.  Returns the payouts left to the contract.
+        fn settle_vault(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            vault: AccountId,
+            payouts: Vec<Payout>) -> Result<Vec<Payout>, ContractError> {
+
+            // The rebate, the progressive jackpot and the house are held by the contract
+            let (jackpot, rest): (Vec<Payout>, Vec<Payout>) = payouts
+                .into_iter()
+                .partition(|p| matches!(p.role, PayoutRole::Winner | PayoutRole::Upline | PayoutRole::Operator));
+            let (foreign, local): (Vec<Payout>, Vec<Payout>) = jackpot
+                .into_iter()
+                .partition(|p| p.destination.is_some());
+
+            if !local.is_empty() {
+                let instructions: Vec<(AccountId, u128)> = local.iter()
+                    .map(|p| (p.recipient, p.amount))
+                    .collect();
+                vault::settle(vault, lottery_id, draw_number, instructions)?;
+
+                for p in local {
+                    self.env().emit_event(WinnerPaid {
+                        recipient: p.recipient,
+                        lottery_id: lottery_id,
+                        draw_number: draw_number,
+                        role: p.role,
+                        amount: p.amount,
+                    });
+                }
+            }
+
+            let released: u128 = foreign.iter().map(|p| p.amount).sum();
+            if released > 0 {
+                vault::release(vault, lottery_id, draw_number, released)?;
+            }
+
+            Ok(foreign.into_iter().chain(rest).collect())
+        }
+
+        /// Mint a commemorative NFT for every winner recording the draw, the number and the
+        /// prize.  A failing mint does not block the settlement.
+        fn mint_winner_nfts(&mut self, lottery_id: u32, winners: &[Winner]) -> Result<(), ContractError> {
+            let collection = match self.lottery(lottery_id)?.lottery_setup.nft_collection {
+                Some(collection) => collection,
+                None => return Ok(()),
+            };
+
+            for winner in winners {
+                let item = self.update_lottery(lottery_id, |l| {
+                    l.nft_items += 1;
+                    l.nft_items - 1
+                })?;
+
+                let metadata = format!("{{\"lottery\":{},\"draw\":{},\"number\":{},\"prize\":{}}}",
+                    lottery_id,
+                    winner.draw_number,
+                    winner.bet_number,
+                    winner.bettor_share);
+
+                let _ = runtime::dispatch(&RuntimeCall::Nfts(NftsCall::Mint {
+                        collection: collection,
+                        item: item,
+                        mint_to: winner.owner.into(),
+                        witness_data: None,
+                    }))
+                    .and_then(|_| runtime::dispatch(&RuntimeCall::Nfts(NftsCall::SetMetadata {
+                        collection: collection,
+                        item: item,
+                        data: metadata.into_bytes(),
+                    })));
+            }
+            Ok(())
+        }
+
+        /// Transfer a settlement payout (prize or rebate) and emit its outcome
+        fn payout(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            recipient: AccountId,
+            role: PayoutRole,
+            amount: u128) -> Result<(), ContractError> {
+
+            self.transfer(lottery_id, draw_number, recipient, role.clone(), amount)?;
+
+            self.env().emit_event(WinnerPaid {
+                recipient: recipient,
+                lottery_id: lottery_id,
+                draw_number: draw_number,
+                role: role,
+                amount: amount,
+            });
+            Ok(())
+        }
+
+        /// Deliver a settlement payout to a foreign location via XCM and emit its outcome
+        fn payout_xcm(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            destination: &XcmOrigin,
+            role: PayoutRole,
+            amount: u128) -> Result<(), ContractError> {
+
+            let lottery_setup = &self.lottery(lottery_id)?.lottery_setup;
+            let assets = match lottery_setup.payment_backend {
+                PaymentBackend::Assets => Some(xcm::local_asset(ASSETS_PALLET_INSTANCE,
+                    lottery_setup.asset_id,
+                    amount)),
+                PaymentBackend::Native => Some(xcm::native_asset(amount)),
+                PaymentBackend::Psp22(_) | PaymentBackend::Simulated => None,
+            };
+
+            let result = match assets {
+                Some(assets) => runtime::dispatch(&RuntimeCall::PolkadotXcm(PolkadotXcmCall::LimitedReserveTransferAssets {
+                    dest: xcm::sibling(destination.para_id),
+                    beneficiary: xcm::beneficiary(*destination.account.as_ref()),
+                    assets: assets,
+                    fee_asset_item: 0,
+                    weight_limit: WeightLimit::Unlimited,
+                })),
+                None => Err(RuntimeError::XcmUnsupported),
+            };
+
+            let failed = result.is_err() as u64;
+            self.update_lottery(lottery_id, |l| {
+                l.metrics.transfers_attempted += 1;
+                l.metrics.transfers_failed += failed;
+            })?;
+
+            if let Err(cause) = result {
+                self.env().emit_event(PayoutFailed {
+                    recipient: destination.account,
+                    lottery_id: lottery_id,
+                    draw_number: draw_number,
+                    role: role,
+                    amount: amount,
+                    error: cause.clone(),
+                });
+                return Err(cause.into());
+            }
+
+            self.env().emit_event(WinnerPaid {
+                recipient: destination.account,
+                lottery_id: lottery_id,
+                draw_number: draw_number,
+                role: role,
+                amount: amount,
+            });
+            Ok(())
+        }
+
+        /// Getter functions
+        ///
+        /// These functions returns storage data
+
+        /// Returns the number of lotteries hosted by the contract
+        #[ink(message)]
+        pub fn get_lottery_count(&self) -> u32 {
+            self.lottery_count
+        }
+
+        /// Returns lottery setup, `None` if the lottery does not exist
+        #[ink(message)]
+        pub fn get_lottery_setup(&self, lottery_id: u32) -> Option<LotterySetup> {
+            self.lotteries
+                .get(lottery_id)
+                .map(|l| l.lottery_setup.clone())
+        }
+
+        /// Returns the companion contract notified of the settlements
+        #[ink(message)]
+        pub fn get_companion(&self) -> Option<AccountId> {
+            self.companion
+        }
+
+        /// Returns the number of bets on every number of a draw with at least one bet,
+        /// in ascending order of the numbers
+        #[ink(message)]
+        pub fn get_number_distribution(&self, lottery_id: u32, draw_number: u32) -> Vec<(u16, u32)> {
+            let draw = match self.draw(lottery_id, draw_number) {
+                Ok(draw) => draw,
+                Err(_) => return Vec::new(),
+            };
+
+            let mut distribution: BTreeMap<u16, u32> = BTreeMap::new();
+            for bet in self.expand_bets(lottery_id, &draw, |owner| owner).iter() {
+                *distribution.entry(bet.bet_number).or_insert(0) += 1;
+            }
+            distribution.into_iter().collect()
+        }
+
+        /// Returns the bets of the minimum stake a number of a fixed-odds draw can still
+        /// take before reaching its liability limit, `None` if the draw does not exist or
+        /// is not fixed-odds
+        #[ink(message)]
+        pub fn get_number_capacity(&self, lottery_id: u32, draw_number: u32, bet_number: u16) -> Option<u128> {
+            let lottery = self.lottery(lottery_id).ok()?;
+            let draw = self.draw(lottery_id, draw_number).ok()?;
+            let multiplier = match draw.settlement_mode {
+                SettlementMode::FixedOdds { multiplier } => multiplier,
+                SettlementMode::PariMutuel => return None,
+            };
+
+            // Every bet owes the prize on its number and adds its jackpot share to the
+            // reserve
+            let prize = draw.bet_amount.saturating_mul(multiplier as u128);
+            let jackpot_share = if draw.promotional {
+                draw.bet_amount
+            } else {
+                let slices = lottery.progressive.percentage + lottery.insurance.percentage;
+                draw.bet_amount * (lottery.lottery_setup.shares.jackpot - slices) as u128 / 100
+            };
+            let liability = self.number_liability(lottery_id, &draw, bet_number, multiplier);
+            if prize == 0 {
+                return Some(u128::MAX);
+            }
+
+            let mut capacity = u128::MAX;
+            if draw.liability_cap > 0 {
+                capacity = draw.liability_cap.saturating_sub(liability) / prize;
+            }
+            if prize > jackpot_share {
+                let headroom = draw.jackpot.saturating_sub(liability);
+                capacity = capacity.min(headroom / (prize - jackpot_share));
+            }
+            Some(capacity)
+        }
+
+        /// Returns the house edge of a draw's last settlement, or of all settled draws of
+        /// the lottery if no draw is given
+        #[ink(message)]
+        pub fn get_house_edge(&self, lottery_id: u32, draw_number: Option<u32>) -> Option<HouseEdge> {
+            let lottery = self.lottery(lottery_id).ok()?;
+            match draw_number {
+                Some(draw_number) => self.draws.get((lottery_id, draw_number))
+                    .map(|d| d.house_edge),
+                None => Some(lottery.house_edge.clone()),
+            }
+        }
+
+        /// Returns the accounts with the highest lifetime winnings of a lottery, highest
+        /// first
+        #[ink(message)]
+        pub fn get_leaderboard(&self, lottery_id: u32) -> Vec<LeaderboardEntry> {
+            self.lottery_leaderboard(lottery_id)
+        }
+
+        /// Returns the lifetime winnings of an account in a lottery
+        #[ink(message)]
+        pub fn get_winnings(&self, lottery_id: u32, account: AccountId) -> u128 {
+            self.winnings.get((account, lottery_id)).unwrap_or(0)
+        }
+
+        /// Returns the betting streak of an account in a lottery
+        #[ink(message)]
+        pub fn get_streak(&self, lottery_id: u32, account: AccountId) -> Streak {
+            self.streaks.get((account, lottery_id)).unwrap_or_default()
+        }
+
+        /// Returns the progressive jackpot of a lottery
+        #[ink(message)]
+        pub fn get_progressive_pot(&self, lottery_id: u32) -> Option<ProgressivePot> {
+            self.lottery(lottery_id).ok().map(|l| l.progressive.clone())
+        }
+
+        /// Returns true if the referral bonus of a referee was paid
+        #[ink(message)]
+        pub fn is_referral_bonus_paid(&self, account: AccountId) -> bool {
+            self.referral_bonuses.contains(account)
+        }
+
+        /// Returns the free-bet credits of an account in a lottery
+        #[ink(message)]
+        pub fn get_credits(&self, lottery_id: u32, account: AccountId) -> u128 {
+            self.credits.get((account, lottery_id)).unwrap_or(0)
+        }
+
+        /// Returns the transfers of an account recorded by a simulated lottery
+        #[ink(message)]
+        pub fn get_ledger_balance(&self, lottery_id: u32, account: AccountId) -> LedgerBalance {
+            self.ledger.get((account, lottery_id)).unwrap_or_default()
+        }
+
+        /// Returns the voucher of a code hash
+        #[ink(message)]
+        pub fn get_voucher(&self, code_hash: [u8; 32]) -> Option<Voucher> {
+            self.vouchers.get(code_hash)
+        }
+
+        /// Returns the insurance fund of a lottery
+        #[ink(message)]
+        pub fn get_insurance_fund(&self, lottery_id: u32) -> Option<InsuranceFund> {
+            self.lottery(lottery_id).ok().map(|l| l.insurance.clone())
+        }
+
+        /// Returns the affiliate shares received by an upline over all lotteries
+        #[ink(message)]
+        pub fn get_affiliate_earnings(&self, account: AccountId) -> u128 {
+            self.affiliate_earnings.get(account).unwrap_or(0)
+        }
+
+        /// Returns the affiliate shares received by an upline in a draw
+        #[ink(message)]
+        pub fn get_draw_affiliate_earnings(&self, account: AccountId, lottery_id: u32, draw_number: u32) -> u128 {
+            self.draw_affiliate_earnings.get((account, lottery_id, draw_number)).unwrap_or(0)
+        }
+
+        /// Returns up to `count` accounts referred by an upline starting at index `from`,
+        /// in registration order
+        #[ink(message)]
+        pub fn get_downlines(&self, account: AccountId, from: u32, count: u32) -> Vec<AccountId> {
+            let total = self.downline_count.get(account).unwrap_or(0);
+            (from..total.min(from.saturating_add(count)))
+                .filter_map(|index| self.downlines.get((account, index)))
+                .collect()
+        }
+
+        /// Returns true if the account placed a bet in any draw, i.e., it is a valid upline
+        #[ink(message)]
+        pub fn is_bettor(&self, account: AccountId) -> bool {
+            self.bettors.contains(account)
+        }
+
+        /// Returns the upline of an account in the affiliate registry
+        #[ink(message)]
+        pub fn get_upline(&self, account: AccountId) -> Option<AccountId> {
+            self.referrers.get(account)
+        }
+
+        /// Returns the announced upgrade, if any
+        #[ink(message)]
+        pub fn get_pending_upgrade(&self) -> Option<PendingUpgrade> {
+            self.pending_upgrade.clone()
+        }
+
+        /// Returns the event schema version
+        #[ink(message)]
+        pub fn get_event_schema_version(&self) -> u16 {
+            EVENT_SCHEMA_VERSION
+        }
+
+        /// Returns the asset balance of an account, e.g., the contract itself
+        ///
+        /// Requires the `assets-extension` feature and a runtime that registers the
+        /// assets chain extension, otherwise `ChainExtensionFailed` is returned.
+        #[ink(message)]
+        pub fn get_asset_balance(&self, lottery_id: u32, account: AccountId) -> Result<u128, ContractError> {
+            let asset_id = self.lottery(lottery_id)?.lottery_setup.asset_id;
+            #[cfg(feature = "assets-extension")]
+            {
+                Ok(crate::chain_extension::balance_of(asset_id, account)?)
+            }
+            #[cfg(not(feature = "assets-extension"))]
+            {
+                let _ = (asset_id, account);
+                Err(RuntimeError::ChainExtensionFailed.into())
+            }
+        }
+
+        /// Returns the prizes and rebates an account can claim
+        #[ink(message)]
+        pub fn get_claim(&self, lottery_id: u32, account: AccountId) -> Claim {
+            self.claims.get((account, lottery_id)).unwrap_or_default()
+        }
+
+        /// Returns the escrowed prizes of an account
+        #[ink(message)]
+        pub fn get_escrow(&self, lottery_id: u32, account: AccountId) -> Vec<Escrow> {
+            self.escrow.get((account, lottery_id)).unwrap_or_default()
+        }
+
+        /// Returns the health of the lottery, `None` if the lottery does not exist
+        #[ink(message)]
+        pub fn get_health(&self, lottery_id: u32) -> Option<Health> {
+            let lottery = self.lotteries.get(lottery_id)?;
+            let current_block: u32 = self.env().block_number();
+            let starting_block = lottery.lottery_setup.starting_block;
+
+            let mut health = Health {
+                is_started: lottery.lottery_setup.is_started,
+                open_draws: 0,
+                processing_draws: 0,
+                overdue_draws: 0,
+                pending_failed_payouts: 0,
+                storage_version: STORAGE_VERSION,
+            };
+
+            for draw in self.lottery_draws(lottery_id).iter() {
+                match draw.status {
+                    DrawStatus::Open => {
+                        health.open_draws += 1;
+                        if starting_block + draw.processing_blocks < current_block {
+                            health.overdue_draws += 1;
+                        }
+                    }
+                    DrawStatus::Processing => {
+                        health.processing_draws += 1;
+                        if starting_block + draw.closing_blocks < current_block {
+                            health.overdue_draws += 1;
+                        }
+                    }
+                    DrawStatus::Close => {}
+                }
+                health.pending_failed_payouts += draw.failed_payouts.len() as u32;
+            }
+
+            Some(health)
+        }
+
+        /// Returns the version of the contract, so deployed instances can be identified
+        /// and incompatible front-ends can warn their users
+        #[ink(message)]
+        pub fn get_version(&self) -> Version {
+            Version {
+                crate_version: String::from(CRATE_VERSION),
+                git_hash: String::from(GIT_HASH),
+                storage_version: STORAGE_VERSION,
+                event_schema_version: EVENT_SCHEMA_VERSION,
+            }
+        }
+
+        /// Check invariants
+        ///
+        /// Scans the draws of all lotteries for inconsistent states and returns the
+        /// violations, e.g., for monitoring and before upgrades.  Nothing is changed.
+        /// 1. A draw flagged open must not be closed.
+        /// 2. An open draw must not have winners.
+        /// 3. An open or processing draw with a jackpot must have bets.
+        #[ink(message)]
+        pub fn check_invariants(&self) -> Vec<Violation> {
+            let mut violations: Vec<Violation> = Vec::new();
+            for lottery_id in 0..self.lottery_count {
+                for draw in self.lottery_draws(lottery_id).iter() {
+                    let mut invariants: Vec<Invariant> = Vec::new();
+                    if draw.is_open && draw.status == DrawStatus::Close {
+                        invariants.push(Invariant::OpenFlagOnClosedDraw);
+                    }
+                    if draw.status == DrawStatus::Open
+                        && !(draw.winners.is_empty() && draw.second_winners.is_empty() && draw.third_winners.is_empty()) {
+                        invariants.push(Invariant::WinnersOnOpenDraw);
+                    }
+                    if draw.status != DrawStatus::Close && draw.jackpot > 0 && draw.bet_count == 0 {
+                        invariants.push(Invariant::JackpotWithoutBets);
+                    }
+
+                    violations.extend(invariants.into_iter().map(|invariant| Violation {
+                        lottery_id: lottery_id,
+                        draw_number: draw.draw_number,
+                        invariant: invariant,
+                    }));
+                }
+            }
+            violations
+        }
+
+        /// Returns the counts and encoded sizes of the draws, bets, winners and indexes of
+        /// all lotteries, to plan pruning before the storage deposit or the buffer limits
+        /// are reached
+        #[ink(message)]
+        pub fn get_storage_report(&self) -> StorageReport {
+            let journal = self.get_journal(0);
+            let mut report = StorageReport {
+                lotteries: self.lottery_count,
+                index_entries: journal.len() as u32,
+                indexes_size: journal.iter().map(|e| e.encoded_size() as u32).sum(),
+                total_size: self.storage_bytes(),
+                ..Default::default()
+            };
+
+            for lottery_id in 0..self.lottery_count {
+                let leaderboard = self.lottery_leaderboard(lottery_id);
+                let held_payouts = self.lottery_held_payouts(lottery_id);
+                report.index_entries += (leaderboard.len() + held_payouts.len()) as u32;
+                report.indexes_size += (leaderboard.encoded_size() + held_payouts.encoded_size()) as u32;
+
+                for draw in self.lottery_draws(lottery_id).iter() {
+                    report.draws += 1;
+                    report.bets += draw.bet_count;
+                    report.winners += (draw.winners.len()
+                        + draw.second_winners.len()
+                        + draw.third_winners.len()) as u32;
+                    report.index_entries += draw.failed_payouts.len() as u32;
+                    report.draws_size += draw.encoded_size() as u32;
+                    report.bets_size += self.expand_bets(lottery_id, draw, |owner| owner).encoded_size() as u32;
+                    report.winners_size += (draw.winners.encoded_size()
+                        + draw.second_winners.encoded_size()
+                        + draw.third_winners.encoded_size()) as u32;
+                    report.indexes_size += draw.failed_payouts.encoded_size() as u32;
+                }
+            }
+            report
+        }
+
+        /// Returns the storage deposit consumed by the contract with its budget
+        #[ink(message)]
+        pub fn get_storage_deposit(&self) -> StorageDeposit {
+            let bytes = self.storage_bytes();
+            StorageDeposit {
+                bytes: bytes,
+                deposit: self.deposit_budget.as_ref()
+                    .map(|b| (bytes as u128).saturating_mul(b.byte_deposit))
+                    .unwrap_or(0),
+                budget: self.deposit_budget.clone(),
+            }
+        }
+
+        /// Returns the statement of the funds of a draw, `None` if the draw does not exist
+        #[ink(message)]
+        pub fn get_reconciliation(&self, lottery_id: u32, draw_number: u32) -> Option<Reconciliation> {
+            self.draw(lottery_id, draw_number).ok().map(|d| d.reconciliation)
+        }
+
+        /// Returns the prizes and withdrawals held by the payout policies of a lottery
+        #[ink(message)]
+        pub fn get_held_payouts(&self, lottery_id: u32) -> Vec<HeldPayout> {
+            self.lottery_held_payouts(lottery_id)
+        }
+
+        /// Returns the telemetry counters, `None` if the lottery does not exist
+        #[ink(message)]
+        pub fn get_metrics(&self, lottery_id: u32) -> Option<Metrics> {
+            self.lotteries
+                .get(lottery_id)
+                .map(|l| l.metrics)
+        }
+
+        /// Returns the journaled state transitions of all lotteries starting at the given
+        /// sequence number.  Only the last `JOURNAL_LENGTH` transitions are kept.
+        #[ink(message)]
+        pub fn get_journal(&self, from_seq: u64) -> Vec<JournalEntry> {
+            let oldest_seq = self.journal_seq.saturating_sub(JOURNAL_LENGTH) + 1;
+            (from_seq.max(oldest_seq)..=self.journal_seq)
+                .filter_map(|seq| self.journal.get(seq % JOURNAL_LENGTH))
+                .collect()
+        }
+
+        /// Return all the draws of the lottery
+        #[ink(message)]
+        pub fn get_draws(&self, lottery_id: u32) -> Vec<Draw> {
+            self.lottery_draws(lottery_id)
+        }
+
+        /// Return a draw, `None` if the draw does not exist
+        #[ink(message)]
+        pub fn get_draw(&self, lottery_id: u32, draw_number: u32) -> Option<Draw> {
+            self.draw(lottery_id, draw_number).ok()
+        }
+
+        /// Return all the bets of a draw, `None` if the draw does not exist
+        #[ink(message)]
+        pub fn get_bets(&self, lottery_id: u32, draw_number:u32) -> Option<Vec<Bet>> {
+            self.draw(lottery_id, draw_number).ok()
+                .map(|d| self.draw_bets(lottery_id, &d))
+        }
+
+        /// Export the setup of a lottery for a migration
+        ///
+        /// 1. The snapshot is the SCALE encoding of `(storage version, dev, salt, journal
+        ///    sequence, bundle sequence, companion, lottery state, held payouts,
+        ///    leaderboard)`.  The lottery state is exported without its draws, see
+        ///    `export_draws`.
+        /// 2. The mappings, e.g., the affiliate registry or the credits, can't be
+        ///    enumerated.  They are exported per account with their getters.
+        #[ink(message)]
+        pub fn export_setup(&self, lottery_id: u32) -> Option<Vec<u8>> {
+            let mut lottery = self.lotteries.get(lottery_id)?;
+            lottery.draw_count = 0;
+            Some((STORAGE_VERSION,
+                self.dev,
+                self.salt,
+                self.journal_seq,
+                self.bundle_seq,
+                self.companion,
+                lottery,
+                self.lottery_held_payouts(lottery_id),
+                self.lottery_leaderboard(lottery_id)).encode())
+        }
+
+        /// Export up to `limit` draws of a lottery starting at index `offset`, every draw
+        /// SCALE-encoded without its bets, see `export_bets`.  The limit is capped at
+        /// `MAX_EXPORT_LIMIT`.
+        #[ink(message)]
+        pub fn export_draws(&self, lottery_id: u32, offset: u32, limit: u32) -> Vec<Vec<u8>> {
+            self.lottery_draw_numbers(lottery_id)
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit.min(MAX_EXPORT_LIMIT) as usize)
+                .filter_map(|n| self.draws.get((lottery_id, n)))
+                .map(|d| Draw {
+                    bet_count: 0,
+                    bet_numbers: Vec::new(),
+                    ..d
+                }.encode())
+                .collect()
+        }
+
+        /// Export up to `limit` bets of a draw starting at index `offset`, every bet
+        /// SCALE-encoded.  The limit is capped at `MAX_EXPORT_LIMIT`.
+        #[ink(message)]
+        pub fn export_bets(&self, lottery_id: u32, draw_number: u32, offset: u32, limit: u32) -> Vec<Vec<u8>> {
+            self.draw(lottery_id, draw_number).ok()
+                .map(|d| self.expand_bets(lottery_id, &d, |owner| owner).iter()
+                    .skip(offset as usize)
+                    .take(limit.min(MAX_EXPORT_LIMIT) as usize)
+                    .map(|b| b.encode())
+                    .collect())
+                .unwrap_or_default()
+        }
+
     }
 
 }
@@ -0,0 +1,67 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Prices are scaled by this unit, i.e., the fiat value of an amount of the asset is
+/// `amount * price / PRICE_UNIT`.
+pub const PRICE_UNIT: u128 = 1_000_000;
+
+/// Price oracle contract interface.
+#[ink::trait_definition]
+pub trait PriceOracle {
+    /// Returns the fiat price of the asset, scaled by `PRICE_UNIT`.
+    #[ink(message)]
+    fn get_price(&self, asset_id: u128) -> Option<u128>;
+}
+
+/// Fetches the price of the asset from the oracle contract (`PriceOracle::get_price`).
+pub fn get_price(oracle: AccountId, asset_id: u128) -> Result<u128, RuntimeError> {
+    #[cfg(test)]
+    if let Some(price) = mock::price() {
+        return Ok(price);
+    }
+
+    let result = build_call::<DefaultEnvironment>()
+        .call(oracle)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PriceOracle::get_price")))
+                .push_arg(asset_id),
+        )
+        .returns::<Option<u128>>()
+        .try_invoke();
+
+    match result {
+        Ok(Ok(Some(price))) => Ok(price),
+        // The oracle has no price for the asset
+        Ok(Ok(None)) => Err(RuntimeError::PriceUnavailable),
+        // The oracle contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
+
+/// Fiat value of an amount of the asset at the given price.
+pub fn value_of(amount: u128, price: u128) -> u128 {
+    amount.saturating_mul(price) / PRICE_UNIT
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::cell::RefCell;
+
+    std::thread_local! {
+        static PRICE: RefCell<Option<u128>> = RefCell::new(None);
+    }
+
+    /// Replace the price of every oracle, `None` calls the oracles again
+    pub fn set_price(price: Option<u128>) {
+        PRICE.with(|p| *p.borrow_mut() = price);
+    }
+
+    pub(crate) fn price() -> Option<u128> {
+        PRICE.with(|p| *p.borrow())
+    }
+}
@@ -0,0 +1,55 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+use ink::prelude::vec::Vec;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Payout strategy contract interface.
+#[ink::trait_definition]
+pub trait PayoutStrategy {
+    /// Returns the `(bettor_share, upline_share)` of each winner of the draw, in the
+    /// order of the winning bets.  The shares must not exceed the jackpot, the rest
+    /// stays in the jackpot of the draw.
+    #[ink(message)]
+    fn compute_shares(&self, draw_number: u32, jackpot: u128, winners: u32, bets: u32) -> Vec<(u128, u128)>;
+}
+
+/// Computes the shares of the winners with the strategy contract
+/// (`PayoutStrategy::compute_shares`) and checks them against the jackpot.
+pub fn compute_shares(strategy: AccountId,
+    draw_number: u32,
+    jackpot: u128,
+    winners: u32,
+    bets: u32) -> Result<Vec<(u128, u128)>, RuntimeError> {
+
+    let result = build_call::<DefaultEnvironment>()
+        .call(strategy)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PayoutStrategy::compute_shares")))
+                .push_arg(draw_number)
+                .push_arg(jackpot)
+                .push_arg(winners)
+                .push_arg(bets),
+        )
+        .returns::<Vec<(u128, u128)>>()
+        .try_invoke();
+
+    let shares = match result {
+        Ok(Ok(shares)) => shares,
+        // The strategy contract could not dispatch the message
+        Ok(Err(_)) => return Err(RuntimeError::CalleeReverted),
+        Err(e) => return Err(RuntimeError::from(e)),
+    };
+
+    // Every winner needs a share and the total can't exceed the jackpot
+    let total = shares
+        .iter()
+        .try_fold(0u128, |total, (bettor, upline)| total.checked_add(*bettor)?.checked_add(*upline));
+    if shares.len() != winners as usize || total.is_none_or(|t| t > jackpot) {
+        return Err(RuntimeError::InvalidPayoutShares);
+    }
+
+    Ok(shares)
+}
@@ -0,0 +1,96 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
+
+/// Errors returned by a PSP22 token contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum Psp22Error {
+    /// Custom error type for implementation-based errors.
+    Custom(String),
+    /// Returned when an account does not have enough tokens to complete the operation.
+    InsufficientBalance,
+    /// Returned if there is not enough allowance to complete the operation.
+    InsufficientAllowance,
+    /// Returned if recipient's address is zero.
+    ZeroRecipientAddress,
+    /// Returned if sender's address is zero.
+    ZeroSenderAddress,
+    /// Returned if a safe transfer check failed.
+    SafeTransferCheckFailed(String),
+}
+
+/// Transfers tokens held by the contract to the recipient (`PSP22::transfer`).
+pub fn transfer(token: AccountId, to: AccountId, value: Balance) -> Result<(), RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                .push_arg(to)
+                .push_arg(value)
+                .push_arg(Vec::<u8>::new()),
+        )
+        .returns::<Result<(), Psp22Error>>()
+        .try_invoke();
+
+    map_result(result)
+}
+
+/// Transfers tokens from an account that approved the contract as spender to the
+/// recipient (`PSP22::transfer_from`).
+pub fn transfer_from(token: AccountId,
+                     from: AccountId,
+                     to: AccountId,
+                     value: Balance) -> Result<(), RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                .push_arg(from)
+                .push_arg(to)
+                .push_arg(value)
+                .push_arg(Vec::<u8>::new()),
+        )
+        .returns::<Result<(), Psp22Error>>()
+        .try_invoke();
+
+    map_result(result)
+}
+
+/// Returns the token balance of an account (`PSP22::balance_of`).
+pub fn balance_of(token: AccountId, owner: AccountId) -> Result<Balance, RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::balance_of")))
+                .push_arg(owner),
+        )
+        .returns::<Balance>()
+        .try_invoke();
+
+    match result {
+        Ok(Ok(balance)) => Ok(balance),
+        // The token contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
+
+fn map_result(
+    result: Result<ink::MessageResult<Result<(), Psp22Error>>, ink::env::Error>,
+) -> Result<(), RuntimeError> {
+    match result {
+        Ok(Ok(Ok(()))) => Ok(()),
+        // The token contract rejected the transfer
+        Ok(Ok(Err(_))) => Err(RuntimeError::TokenTransferFailed),
+        // The token contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
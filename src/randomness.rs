@@ -0,0 +1,88 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Randomness oracle contract interface.
+#[ink::trait_definition]
+pub trait RandomnessOracle {
+    /// Returns the random seed published for the subject.
+    #[ink(message)]
+    fn random(&self, subject: [u8; 32]) -> Option<[u8; 32]>;
+}
+
+/// VRF provider contract interface.
+#[ink::trait_definition]
+pub trait VrfProvider {
+    /// Returns the VRF output for the subject.  The provider verifies the proof of the
+    /// output before returning it.
+    #[ink(message)]
+    fn vrf_output(&self, subject: [u8; 32]) -> Option<[u8; 32]>;
+}
+
+/// Fetches the random seed of the subject from the oracle contract
+/// (`RandomnessOracle::random`).
+pub fn oracle_random(oracle: AccountId, subject: [u8; 32]) -> Result<[u8; 32], RuntimeError> {
+    fetch(oracle, ink::selector_bytes!("RandomnessOracle::random"), subject)
+}
+
+/// Fetches the VRF output of the subject from the provider contract
+/// (`VrfProvider::vrf_output`).
+pub fn vrf_output(provider: AccountId, subject: [u8; 32]) -> Result<[u8; 32], RuntimeError> {
+    fetch(provider, ink::selector_bytes!("VrfProvider::vrf_output"), subject)
+}
+
+fn fetch(contract: AccountId, selector: [u8; 4], subject: [u8; 32]) -> Result<[u8; 32], RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(contract)
+        .exec_input(ExecutionInput::new(Selector::new(selector)).push_arg(subject))
+        .returns::<Option<[u8; 32]>>()
+        .try_invoke();
+
+    match result {
+        Ok(Ok(Some(seed))) => Ok(seed),
+        // The provider has no randomness for the subject yet
+        Ok(Ok(None)) => Err(RuntimeError::RandomnessUnavailable),
+        // The provider contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    std::thread_local! {
+        static SEED: RefCell<Option<[u8; 32]>> = RefCell::new(None);
+        static WINNING_NUMBERS: RefCell<VecDeque<u16>> = RefCell::new(VecDeque::new());
+    }
+
+    /// Replace the seed of every randomness source, `None` restores the sources
+    pub fn set_seed(seed: Option<[u8; 32]>) {
+        SEED.with(|s| *s.borrow_mut() = seed);
+    }
+
+    /// Queue the next generated number, e.g., the winning number and then the bonus
+    /// number of `process_draw`
+    pub fn push_winning_number(number: u16) {
+        WINNING_NUMBERS.with(|n| n.borrow_mut().push_back(number));
+    }
+
+    /// Forget the seed and the queued numbers
+    pub fn reset() {
+        set_seed(None);
+        WINNING_NUMBERS.with(|n| n.borrow_mut().clear());
+    }
+
+    pub(crate) fn seed() -> Option<[u8; 32]> {
+        SEED.with(|s| *s.borrow())
+    }
+
+    pub(crate) fn next_winning_number() -> Option<u16> {
+        WINNING_NUMBERS.with(|n| n.borrow_mut().pop_front())
+    }
+}
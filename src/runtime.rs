@@ -0,0 +1,78 @@
+use ink::env::DefaultEnvironment;
+
+use crate::assets::RuntimeCall;
+use crate::errors::RuntimeError;
+
+/// Dispatcher of the runtime calls of the contract.
+pub trait Runtime {
+    /// Dispatches the call from the contract account.
+    fn dispatch(call: &RuntimeCall) -> Result<(), RuntimeError>;
+}
+
+/// Dispatches the calls to the runtime of the chain (`call_runtime`).
+pub struct OnChain;
+
+impl Runtime for OnChain {
+    fn dispatch(call: &RuntimeCall) -> Result<(), RuntimeError> {
+        ink::env::call_runtime::<DefaultEnvironment, _>(call).map_err(RuntimeError::from)
+    }
+}
+
+/// Runtime of the contract.  The off-chain environment of the unit tests can't
+/// dispatch runtime calls, they are recorded by `mock::Recorder` instead.
+#[cfg(not(test))]
+pub type ContractRuntime = OnChain;
+#[cfg(test)]
+pub type ContractRuntime = mock::Recorder;
+
+/// Dispatches a runtime call with the runtime of the contract.
+pub fn dispatch(call: &RuntimeCall) -> Result<(), RuntimeError> {
+    <ContractRuntime as Runtime>::dispatch(call)
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    use scale::Encode;
+
+    use super::Runtime;
+    use crate::assets::RuntimeCall;
+    use crate::errors::RuntimeError;
+
+    std::thread_local! {
+        static CALLS: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+        static FAILURE: RefCell<Option<RuntimeError>> = RefCell::new(None);
+    }
+
+    /// Records the SCALE encoding of every dispatched call, or fails them all with the
+    /// error set by `fail_with`.
+    pub struct Recorder;
+
+    impl Runtime for Recorder {
+        fn dispatch(call: &RuntimeCall) -> Result<(), RuntimeError> {
+            if let Some(error) = FAILURE.with(|f| f.borrow().clone()) {
+                return Err(error);
+            }
+            CALLS.with(|c| c.borrow_mut().push(call.encode()));
+            Ok(())
+        }
+    }
+
+    /// Encoded calls dispatched so far, oldest first
+    pub fn calls() -> Vec<Vec<u8>> {
+        CALLS.with(|c| c.borrow().clone())
+    }
+
+    /// Fail every following call with the error, `None` dispatches them again
+    pub fn fail_with(error: Option<RuntimeError>) {
+        FAILURE.with(|f| *f.borrow_mut() = error);
+    }
+
+    /// Forget the recorded calls and the failure
+    pub fn reset() {
+        CALLS.with(|c| c.borrow_mut().clear());
+        fail_with(None);
+    }
+}
@@ -0,0 +1,280 @@
+/// Sandbox tests
+///
+/// Drives the lottery on an in-process runtime with `pallet_assets`, so the settlement
+/// is checked against the asset balances it actually transfers.  The pallets are placed
+/// at the indexes the contract dispatches to (see `assets.rs`).
+use drink::session::{Session, NO_SALT};
+use drink::{AccountId32, Sandbox};
+use ink_sandbox::frame_metadata::RuntimeMetadataPrefixed;
+use ink_sandbox::{Extension, TestExternalities};
+use drink::frame_support::{
+    self, construct_runtime, derive_impl, parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU128, Everything, Hooks},
+};
+use drink::frame_system::{self, EnsureRoot, EnsureSigned};
+use drink::{pallet_balances, pallet_contracts, pallet_timestamp};
+use drink::frame_support::sp_runtime::traits::{AccountIdLookup, Header};
+use drink::frame_support::sp_runtime::BuildStorage;
+
+use crate::errors::ContractError;
+
+type AccountId = AccountId32;
+type Balance = u128;
+type BlockNumber = u32;
+
+/// Asset of the lottery
+const ASSET_ID: u128 = 1;
+
+/// Bet amount of the draws
+const BET_AMOUNT: u128 = 1_000_000;
+
+/// Asset balance minted to every bettor
+const INITIAL_ASSETS: u128 = 100 * BET_AMOUNT;
+
+const ALICE: AccountId = AccountId32::new([1u8; 32]);
+const BOB: AccountId = AccountId32::new([2u8; 32]);
+const CHARLIE: AccountId = AccountId32::new([3u8; 32]);
+
+construct_runtime!(
+    pub enum Runtime {
+        System: frame_system = 0,
+        Timestamp: pallet_timestamp = 3,
+        Balances: pallet_balances = 10,
+        Contracts: pallet_contracts = 40,
+        Utility: pallet_utility = 45,
+        Assets: pallet_assets = 50,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::SolochainDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Runtime {
+    type Block = frame_system::mocking::MockBlockU32<Runtime>;
+    type AccountId = AccountId;
+    // The contract addresses the accounts as `MultiAddress<AccountId, ()>`
+    type Lookup = AccountIdLookup<AccountId, ()>;
+    type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_timestamp::config_preludes::TestDefaultConfig as pallet_timestamp::DefaultConfig)]
+impl pallet_timestamp::Config for Runtime {}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+impl pallet_balances::Config for Runtime {
+    type Balance = Balance;
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+}
+
+parameter_types! {
+    pub ContractsSchedule: pallet_contracts::Schedule<Runtime> = Default::default();
+}
+
+#[derive_impl(pallet_contracts::config_preludes::TestDefaultConfig as pallet_contracts::DefaultConfig)]
+impl pallet_contracts::Config for Runtime {
+    type Time = Timestamp;
+    type Currency = Balances;
+    // The lottery dispatches asset transfers and batches with `call_runtime`
+    type CallFilter = Everything;
+    type Schedule = ContractsSchedule;
+    type UploadOrigin = EnsureSigned<AccountId>;
+    type InstantiateOrigin = EnsureSigned<AccountId>;
+}
+
+impl pallet_utility::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type PalletsOrigin = OriginCaller;
+    type WeightInfo = ();
+}
+
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig as pallet_assets::DefaultConfig)]
+impl pallet_assets::Config for Runtime {
+    type Balance = Balance;
+    type AssetId = u128;
+    type AssetIdParameter = scale::Compact<u128>;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type Freezer = ();
+}
+
+/// Sandbox of the runtime with the accounts of the tests funded
+pub struct AssetsSandbox {
+    ext: TestExternalities,
+}
+
+impl Default for AssetsSandbox {
+    fn default() -> Self {
+        let mut storage = frame_system::GenesisConfig::<Runtime>::default()
+            .build_storage()
+            .expect("system genesis is valid");
+
+        pallet_balances::GenesisConfig::<Runtime> {
+            balances: [ALICE, BOB, CHARLIE].into_iter().map(|a| (a, 1_000_000_000_000)).collect(),
+        }
+        .assimilate_storage(&mut storage)
+        .expect("balances genesis is valid");
+
+        pallet_assets::GenesisConfig::<Runtime> {
+            assets: vec![(ASSET_ID, ALICE, true, 1)],
+            metadata: vec![],
+            accounts: [ALICE, BOB, CHARLIE].into_iter().map(|a| (ASSET_ID, a, INITIAL_ASSETS)).collect(),
+        }
+        .assimilate_storage(&mut storage)
+        .expect("assets genesis is valid");
+
+        let mut ext = TestExternalities::new(storage);
+        ext.execute_with(|| Self::initialize_block(1, Default::default()));
+        Self {
+            ext: ext,
+        }
+    }
+}
+
+impl Sandbox for AssetsSandbox {
+    type Runtime = Runtime;
+
+    fn execute_with<T>(&mut self, execute: impl FnOnce() -> T) -> T {
+        self.ext.execute_with(execute)
+    }
+
+    fn dry_run<T>(&mut self, action: impl FnOnce(&mut Self) -> T) -> T {
+        // Run the action and restore the storage
+        let backend = self.ext.as_backend();
+        let result = action(self);
+
+        let mut ext = TestExternalities::default();
+        ext.backend = backend;
+        self.ext = ext;
+        result
+    }
+
+    fn register_extension<E: core::any::Any + Extension>(&mut self, ext: E) {
+        self.ext.register_extension(ext);
+    }
+
+    fn initialize_block(height: BlockNumber, parent_hash: <Runtime as frame_system::Config>::Hash) {
+        System::reset_events();
+        System::initialize(&height, &parent_hash, &Default::default());
+        Balances::on_initialize(height);
+        // Six seconds per block
+        pallet_timestamp::Now::<Runtime>::put(height as u64 * 6_000);
+        Timestamp::on_initialize(height);
+        Contracts::on_initialize(height);
+        System::note_finished_initialize();
+    }
+
+    fn finalize_block(height: BlockNumber) -> <Runtime as frame_system::Config>::Hash {
+        Contracts::on_finalize(height);
+        Timestamp::on_finalize(height);
+        Balances::on_finalize(height);
+        System::finalize().hash()
+    }
+
+    fn default_actor() -> AccountId {
+        ALICE
+    }
+
+    fn get_metadata() -> RuntimeMetadataPrefixed {
+        Runtime::metadata()
+    }
+
+    fn convert_account_to_origin(account: AccountId) -> RuntimeOrigin {
+        Some(account).into()
+    }
+}
+
+#[drink::contract_bundle_provider]
+enum BundleProvider {}
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+/// Call a message of the lottery and return its result
+fn call(session: &mut Session<AssetsSandbox>,
+    message: &str,
+    args: &[String]) -> Result<Result<(), ContractError>, Box<dyn std::error::Error>> {
+
+    let result: Result<(), ContractError> = session.call(message, args, None)??;
+    Ok(result)
+}
+
+/// Asset balance of an account
+fn asset_balance(session: &mut Session<AssetsSandbox>, account: &AccountId) -> u128 {
+    session.sandbox().execute_with(|| Assets::balance(ASSET_ID, account.clone()))
+}
+
+/// Deploy the lottery with a single draw open for bets until block 10 and closing at
+/// block 20.  The contract account holds the minimum balance of the asset.
+fn deploy(session: &mut Session<AssetsSandbox>) -> Result<AccountId, Box<dyn std::error::Error>> {
+    let contract = session.deploy_bundle(
+        BundleProvider::local()?,
+        "new",
+        &[ASSET_ID.to_string(), "0".into(), "14400".into(), "2".into(), "1000".into(), "false".into()],
+        NO_SALT,
+        None,
+    )?;
+
+    session.sandbox().execute_with(|| {
+        Assets::mint(RuntimeOrigin::signed(ALICE), ASSET_ID.into(), contract.clone().into(), 1)
+    })?;
+
+    assert_eq!(call(session, "add_draw", &args(&["0", "0", "10", "20", &BET_AMOUNT.to_string(), "PariMutuel"]))?, Ok(()));
+    assert_eq!(call(session, "start", &args(&["0"]))?, Ok(()));
+    assert_eq!(call(session, "open_draw", &args(&["0", "1"]))?, Ok(()));
+    Ok(contract)
+}
+
+/// Arguments of a message
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Bet of BOB with CHARLIE as upline
+fn bet(number: u16) -> Vec<String> {
+    args(&["0", "1", &number.to_string(), &format!("{:?}", CHARLIE)])
+}
+
+/// The bettor stakes in assets and the winning bet is paid its share of the jackpot
+/// and the rebate in assets
+#[drink::test(sandbox = AssetsSandbox)]
+fn winner_is_paid_in_assets(mut session: Session) -> TestResult {
+    let contract = deploy(&mut session)?;
+
+    session.sandbox().execute_with(|| {
+        Assets::approve_transfer(RuntimeOrigin::signed(BOB), ASSET_ID.into(), contract.into(), 2 * BET_AMOUNT)
+    })?;
+    session.set_actor(BOB);
+    assert_eq!(call(&mut session, "place_bet", &bet(7))?, Ok(()));
+    assert_eq!(call(&mut session, "place_bet", &bet(8))?, Ok(()));
+    assert_eq!(asset_balance(&mut session, &BOB), INITIAL_ASSETS - 2 * BET_AMOUNT);
+
+    // The operator fixes the winning number after processing
+    session.set_actor(ALICE);
+    session.sandbox().build_blocks(10);
+    assert_eq!(call(&mut session, "process_draw", &args(&["0", "1"]))?, Ok(()));
+    assert_eq!(call(&mut session, "override_draw", &args(&["0", "1", "7"]))?, Ok(()));
+    session.sandbox().build_blocks(10);
+    assert_eq!(call(&mut session, "close_draw", &args(&["0", "1"]))?, Ok(()));
+
+    // 90% of the jackpot (50% of the stakes) to the only winner and the rebate (10% of
+    // the stakes) back to the only bettor.  The upline did not bet, its share of the
+    // jackpot goes to the operator.
+    let jackpot = 2 * BET_AMOUNT * 50 / 100;
+    let rebate = 2 * BET_AMOUNT * 10 / 100;
+    assert_eq!(
+        asset_balance(&mut session, &BOB),
+        INITIAL_ASSETS - 2 * BET_AMOUNT + jackpot * 90 / 100 + rebate
+    );
+    Ok(())
+}
+
+/// A bet without an approval of the stake is rejected and nothing is collected
+#[drink::test(sandbox = AssetsSandbox)]
+fn bet_without_approval_is_rejected(mut session: Session) -> TestResult {
+    deploy(&mut session)?;
+
+    session.set_actor(BOB);
+    assert!(call(&mut session, "place_bet", &bet(7))?.is_err());
+    assert_eq!(asset_balance(&mut session, &BOB), INITIAL_ASSETS);
+    Ok(())
+}
@@ -1,6 +1,12 @@
 /// Imports all the definitions from the outer scope so we can use them here.
-use crate::lottery::{Lottery, LotterySetup, Draw, DrawStatus};
-use crate::errors::Error;
+use crate::lottery::{Lottery, LotterySetup, Draw, DrawStatus, DivisionShares, PaymentBackend, RandomnessSource,
+    SettlementMode, HouseEdge, DrawTemplate, RebateTier, Invariant,
+    Reconciliation, Bet, PayoutPolicy, Participation, DepositBudget, Claim, PrizeTier, Shares, JOURNAL_LENGTH,
+    VOUCHER_CLAIM_BLOCKS};
+use scale::Decode;
+use crate::events::{Success, PayoutRole};
+use crate::errors::{Error, ContractError, RuntimeError};
+use crate::{oracle, randomness, runtime};
 use ink::env::test::{default_accounts, set_caller};
 
 /// We test if the default constructor does its job.
@@ -11,30 +17,49 @@ fn default_works() {
     let lottery_setup = LotterySetup {
         operator: accounts.alice,
         dev: accounts.alice,
-        asset_id: 1984u128,
+        asset_id: 0u128,
+        payment_backend: PaymentBackend::Assets,
+        verified_bets_only: false,
+        randomness_source: RandomnessSource::Internal,
+        vault: None,
+        nft_collection: None,
+        asset_metadata: None,
+        affiliate_percentage: 10u8,
         starting_block: 0u32,
-        daily_total_blocks: 14_400u32,
+        daily_total_blocks: 0u32,
         next_starting_block: 0u32,
-        maximum_draws: 2u8,
-        maximum_bets: 1_000u16,
+        maximum_draws: 0u8,
+        maximum_bets: 0u16,
         is_started: false,
+        oracle: None,
+        max_daily_value: 0,
+        bulk_discount_tickets: 0,
+        bulk_discount_percentage: 0,
+        streak_threshold: 0,
+        streak_bonus_percentage: 0,
+        rebate_tiers: Vec::new(),
+        referral_bonus_percentage: 0,
+        payout_policies: Vec::new(),
+        auditor: None,
+        claim_payouts: false,
+        shares: Shares::default(),
     };
-    assert_eq!(lottery.get_lottery_setup(), lottery_setup);
+    assert_eq!(lottery.get_lottery_setup(0u32), Some(lottery_setup));
 }
 
 #[ink::test]
 fn start_lottery_works() {
     let mut lottery = Lottery::new(
                                 1984u128,
-                                14_400u32,
+                                0u32,
                                 14_400u32,
                                 2u8,
                                 1_000u16,
                                 false
     );
-    let _ = lottery.start();
-    let result = lottery.start();
-    assert!(matches!(result, Err(Error::AlreadyStarted)));       
+    assert_eq!(lottery.start(0u32), Ok(()));
+    let result = lottery.start(0u32);
+    assert!(matches!(result, Err(ContractError::Internal(Error::AlreadyStarted))));       
 }    
 
 #[ink::test]
@@ -50,39 +75,66 @@ fn setup_lottery_works() {
                                 false);
 
     let _ = lottery.setup(
+        0u32,
         accounts.alice,
         1984u128,
         14_400u32,
         14_400u32,
         2u8,
         1_000u16,
+        PaymentBackend::Assets,
+        RandomnessSource::Internal,
+        10u8,
     );
 
     let lottery_setup = LotterySetup {
         operator: accounts.alice,
         dev: accounts.alice,
         asset_id: 1984u128,
+        payment_backend: PaymentBackend::Assets,
+        verified_bets_only: false,
+        randomness_source: RandomnessSource::Internal,
+        vault: None,
+        nft_collection: None,
+        asset_metadata: None,
+        affiliate_percentage: 10u8,
         starting_block: 14_400u32,
         daily_total_blocks: 14_400u32,
         next_starting_block:28_800u32,
         maximum_draws: 2u8,
         maximum_bets: 1_000u16,
-        is_started: true,
+        is_started: false,
+        oracle: None,
+        max_daily_value: 0,
+        bulk_discount_tickets: 0,
+        bulk_discount_percentage: 0,
+        streak_threshold: 0,
+        streak_bonus_percentage: 0,
+        rebate_tiers: Vec::new(),
+        referral_bonus_percentage: 0,
+        payout_policies: Vec::new(),
+        auditor: None,
+        claim_payouts: false,
+        shares: Shares::default(),
     };
-    assert_eq!(lottery.get_lottery_setup(), lottery_setup);
-    assert_eq!(lottery.lottery_setup.operator, accounts.alice);
+    assert_eq!(lottery.get_lottery_setup(0u32), Some(lottery_setup));
+    assert_eq!(lottery.lotteries.get(0u32).unwrap().lottery_setup.operator, accounts.alice);
 
     set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
     assert_eq!(
         lottery.setup(
+            0u32,
             accounts.alice,
             1984u128,
             14_400u32,
             14_400u32,
             2u8,
             1_000u16,
+            PaymentBackend::Assets,
+            RandomnessSource::Internal,
+            10u8,
         ),
-        Err(Error::BadOrigin)
+        Err(ContractError::Internal(Error::BadOrigin))
     );
 
 }
@@ -99,13 +151,15 @@ fn adding_and_removing_draw_works() {
     );
 
     let _ = lottery.add_draw(
+        0u32,
         1_000u32,
         3_000u32,
         3_500u32,
         500_000,
+        SettlementMode::PariMutuel,
     );
     
-    assert_eq!(lottery.draws.len(), 1);
+    assert_eq!(lottery.lotteries.get(0u32).unwrap().draw_count, 1);
     
     let new_draw = Draw {
         draw_number: 1,
@@ -113,23 +167,51 @@ fn adding_and_removing_draw_works() {
         processing_blocks: 3_000u32,
         closing_blocks: 3_500u32,
         bet_amount: 500_000,
+        max_bet_amount: 0,
         jackpot: 0,
         rebate: 0,
-        bets: Vec::new(),
+        bet_count: 0,
         winning_number: 0,
+        bonus_number: 0,
         winners: Vec::new(),
-        status: DrawStatus::Open,
+        second_winners: Vec::new(),
+        third_winners: Vec::new(),
+        division_shares: DivisionShares::default(),
+        settlement_mode: SettlementMode::PariMutuel,
+        liability_cap: 0,
+        promotional: false,
+        handle: 0,
+        fees: 0,
+        house_edge: HouseEdge::default(),
+        status: DrawStatus::Close,
         is_open: false,
+        failed_payouts: Vec::new(),
+        payout_strategy: None,
+        affiliate_percentage: None,
+        early_bird_bets: 0,
+        early_bird_percentage: 0,
+        early_bird_pool: 0,
+        reconciliation: Reconciliation::default(),
+        cutoff_blocks: 0,
+        participation: Participation::default(),
+        delegate: None,
+        result: None,
+        compact_bets: false,
+        bet_numbers: Vec::new(),
+        committed_salt: None,
+        prize_tiers: Vec::new(),
     };
-    assert_eq!(lottery.draws[0], new_draw);
+    assert_eq!(lottery.get_draw(0u32, 1u32), Some(new_draw));
 
     let _ = lottery.add_draw(
+        0u32,
         1_000u32,
         3_000u32,
         3_500u32,
         500_000,
+        SettlementMode::PariMutuel,
     );
-    assert_eq!(lottery.draws.len(), 2);
+    assert_eq!(lottery.lotteries.get(0u32).unwrap().draw_count, 2);
 
     let new_draw = Draw {
         draw_number: 2,
@@ -137,18 +219,44 @@ fn adding_and_removing_draw_works() {
         processing_blocks: 3_000u32,
         closing_blocks: 3_500u32,
         bet_amount: 500_000,
+        max_bet_amount: 0,
         jackpot: 0,
         rebate: 0,
-        bets: Vec::new(),
+        bet_count: 0,
         winning_number: 0,
+        bonus_number: 0,
         winners: Vec::new(),
-        status: DrawStatus::Open,
+        second_winners: Vec::new(),
+        third_winners: Vec::new(),
+        division_shares: DivisionShares::default(),
+        settlement_mode: SettlementMode::PariMutuel,
+        liability_cap: 0,
+        promotional: false,
+        handle: 0,
+        fees: 0,
+        house_edge: HouseEdge::default(),
+        status: DrawStatus::Close,
         is_open: false,
+        failed_payouts: Vec::new(),
+        payout_strategy: None,
+        affiliate_percentage: None,
+        early_bird_bets: 0,
+        early_bird_percentage: 0,
+        early_bird_pool: 0,
+        reconciliation: Reconciliation::default(),
+        cutoff_blocks: 0,
+        participation: Participation::default(),
+        delegate: None,
+        result: None,
+        compact_bets: false,
+        bet_numbers: Vec::new(),
+        committed_salt: None,
+        prize_tiers: Vec::new(),
     };
-    assert_eq!(lottery.draws[1], new_draw);
+    assert_eq!(lottery.get_draw(0u32, 2u32), Some(new_draw));
 
-    let _ = lottery.remove_draw();
-    assert_eq!(lottery.draws.len(), 1);
+    let _ = lottery.remove_draw(0u32);
+    assert_eq!(lottery.lotteries.get(0u32).unwrap().draw_count, 1);
 
     let new_draw = Draw {
         draw_number: 1,
@@ -156,13 +264,1923 @@ fn adding_and_removing_draw_works() {
         processing_blocks: 3_000u32,
         closing_blocks: 3_500u32,
         bet_amount: 500_000,
+        max_bet_amount: 0,
         jackpot: 0,
         rebate: 0,
-        bets: Vec::new(),
+        bet_count: 0,
         winning_number: 0,
+        bonus_number: 0,
         winners: Vec::new(),
-        status: DrawStatus::Open,
+        second_winners: Vec::new(),
+        third_winners: Vec::new(),
+        division_shares: DivisionShares::default(),
+        settlement_mode: SettlementMode::PariMutuel,
+        liability_cap: 0,
+        promotional: false,
+        handle: 0,
+        fees: 0,
+        house_edge: HouseEdge::default(),
+        status: DrawStatus::Close,
         is_open: false,
+        failed_payouts: Vec::new(),
+        payout_strategy: None,
+        affiliate_percentage: None,
+        early_bird_bets: 0,
+        early_bird_percentage: 0,
+        early_bird_pool: 0,
+        reconciliation: Reconciliation::default(),
+        cutoff_blocks: 0,
+        participation: Participation::default(),
+        delegate: None,
+        result: None,
+        compact_bets: false,
+        bet_numbers: Vec::new(),
+        committed_salt: None,
+        prize_tiers: Vec::new(),
     };
-    assert_eq!(lottery.draws[0], new_draw);
-}
\ No newline at end of file
+    assert_eq!(lottery.get_draw(0u32, 1u32), Some(new_draw));
+}
+
+#[ink::test]
+fn too_many_draws_reports_limit() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                14_400u32,
+                                14_400u32,
+                                1u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::TooManyDraws { limit: 1u8 }))
+    );
+}
+
+#[ink::test]
+fn add_bet_rejects_closed_draw() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    let _ = lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel);
+
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 555u16, accounts.charlie, accounts.bob, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::DrawClosed))
+    );
+    assert_eq!(lottery.get_bets(0u32, 1u32), Some(Vec::new()));
+    assert_eq!(lottery.get_bets(0u32, 2u32), None);
+}
+
+#[ink::test]
+fn journal_records_state_transitions() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    let _ = lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel);
+    let _ = lottery.remove_draw(0u32);
+
+    let journal = lottery.get_journal(0);
+    assert_eq!(journal.len(), 2);
+    assert_eq!(journal[0].seq, 1);
+    assert_eq!(journal[0].status, Success::DrawAdded { draw_number: 1, bet_amount: 500_000 });
+
+    let journal = lottery.get_journal(2);
+    assert_eq!(journal.len(), 1);
+    assert_eq!(journal[0].status, Success::DrawRemoved { draw_number: 1 });
+}
+
+#[ink::test]
+fn companion_change_is_journaled() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::default();
+
+    assert_eq!(lottery.set_companion(Some(accounts.django)), Ok(()));
+    assert_eq!(
+        lottery.get_journal(0).last().map(|e| (e.lottery_id, e.status.clone())),
+        Some((0, Success::CompanionSet { companion: Some(accounts.django) }))
+    );
+
+    set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(lottery.set_companion(None), Err(ContractError::Internal(Error::BadOrigin)));
+}
+
+#[ink::test]
+fn journal_keeps_the_last_transitions() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    for _ in 0..40 {
+        let _ = lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel);
+        let _ = lottery.remove_draw(0u32);
+    }
+
+    // The 80 transitions overwrite the oldest entries of the ring buffer
+    let journal = lottery.get_journal(0);
+    assert_eq!(journal.len(), JOURNAL_LENGTH as usize);
+    assert_eq!((journal[0].seq, journal[63].seq), (17, 80));
+    assert_eq!(journal[63].status, Success::DrawRemoved { draw_number: 1 });
+    assert_eq!(lottery.get_journal(79).iter().map(|e| e.seq).collect::<Vec<_>>(), vec![79, 80]);
+}
+
+#[ink::test]
+fn add_bet_rejects_out_of_range_number() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    let _ = lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel);
+
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 1_000u16, accounts.charlie, accounts.bob, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::BetNumberOutOfRange { min: 1, max: 999, actual: 1_000 }))
+    );
+}
+
+#[ink::test]
+fn add_draw_validates_parameters() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 0, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::ZeroBetAmount))
+    );
+    assert_eq!(
+        lottery.add_draw(0u32, 3_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::EmptyBettingWindow))
+    );
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_500u32, 3_000u32, 500_000, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::EmptyProcessingWindow))
+    );
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 14_400u32, 500_000, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::InvalidBlocksHierarchy))
+    );
+    assert_eq!(lottery.get_draws(0u32).len(), 0);
+}
+
+#[ink::test]
+fn lotteries_are_independent() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.create_lottery(accounts.alice, 1337u128, 0u32, 7_200u32, 1u8, 100u16), Ok(1));
+    assert_eq!(lottery.get_lottery_count(), 2);
+
+    let _ = lottery.add_draw(1u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel);
+    assert_eq!(lottery.get_draws(0u32).len(), 0);
+    assert_eq!(lottery.get_draws(1u32).len(), 1);
+    assert_eq!(lottery.get_lottery_setup(1u32).map(|s| s.asset_id), Some(1337u128));
+
+    assert_eq!(
+        lottery.add_draw(2u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::LotteryNotFound))
+    );
+
+    set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(
+        lottery.create_lottery(accounts.bob, 1337u128, 0u32, 7_200u32, 1u8, 100u16),
+        Err(ContractError::Internal(Error::BadOrigin))
+    );
+}
+
+#[ink::test]
+fn verified_lottery_rejects_operator_bets() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    let _ = lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel);
+
+    assert_eq!(lottery.set_verified_bets_only(0u32, true), Ok(()));
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 555u16, accounts.charlie, accounts.bob, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::UnverifiedBet))
+    );
+}
+
+#[ink::test]
+fn upgrade_waits_for_announcement_delay() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+    let mut lottery = Lottery::default();
+    let code_hash = ink::primitives::Hash::from([1u8; 32]);
+
+    assert_eq!(lottery.upgrade(code_hash), Err(ContractError::Internal(Error::UpgradeNotAnnounced)));
+
+    assert_eq!(lottery.announce_upgrade(code_hash), Ok(()));
+    assert_eq!(
+        lottery.upgrade(code_hash),
+        Err(ContractError::Internal(Error::InvalidBlock { required_block: 7_200, current_block: 0 }))
+    );
+
+    assert_eq!(lottery.cancel_upgrade(), Ok(()));
+    assert_eq!(lottery.get_pending_upgrade(), None);
+}
+
+#[ink::test]
+fn upline_is_registered_once() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+    let mut lottery = Lottery::default();
+
+    assert_eq!(lottery.register_upline(accounts.charlie), Err(ContractError::Internal(Error::SelfReferral)));
+    assert_eq!(lottery.register_upline(accounts.bob), Ok(()));
+    assert_eq!(
+        lottery.register_upline(accounts.django),
+        Err(ContractError::Internal(Error::ReferrerAlreadyRegistered))
+    );
+    assert_eq!(lottery.get_upline(accounts.charlie), Some(accounts.bob));
+    assert_eq!(lottery.get_downlines(accounts.bob, 0u32, 10u32), vec![accounts.charlie]);
+    assert!(lottery.get_downlines(accounts.bob, 1u32, 10u32).is_empty());
+    assert_eq!(lottery.get_affiliate_earnings(accounts.bob), 0);
+}
+
+#[ink::test]
+fn bet_amount_must_match_asset_decimals() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.set_asset_metadata(0u32, 18u8, "DOT".into()), Ok(()));
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::InvalidBetUnit { unit: 10u128.pow(16), actual: 500_000 }))
+    );
+    assert_eq!(lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 5 * 10u128.pow(17), SettlementMode::PariMutuel), Ok(()));
+}
+
+#[ink::test]
+fn progressive_percentage_is_bounded() {
+    let mut lottery = Lottery::default();
+
+    assert_eq!(
+        lottery.set_progressive_percentage(0u32, 11u8),
+        Err(ContractError::Internal(Error::InvalidProgressivePercentage { maximum: 10u8, actual: 11u8 }))
+    );
+    assert_eq!(lottery.set_progressive_percentage(0u32, 5u8), Ok(()));
+    assert_eq!(lottery.get_progressive_pot(0u32).map(|p| p.percentage), Some(5u8));
+}
+
+#[ink::test]
+fn rebate_tiers_must_be_ascending() {
+    let mut lottery = Lottery::default();
+
+    assert_eq!(
+        lottery.set_rebate_tiers(0u32, vec![
+            RebateTier { min_bets: 5, weight: 2 },
+            RebateTier { min_bets: 5, weight: 3 },
+        ]),
+        Err(ContractError::Internal(Error::InvalidRebateTiers))
+    );
+    assert_eq!(
+        lottery.set_rebate_tiers(0u32, vec![RebateTier { min_bets: 5, weight: 0 }]),
+        Err(ContractError::Internal(Error::InvalidRebateTiers))
+    );
+    assert_eq!(
+        lottery.set_rebate_tiers(0u32, vec![
+            RebateTier { min_bets: 5, weight: 2 },
+            RebateTier { min_bets: 10, weight: 3 },
+        ]),
+        Ok(())
+    );
+    assert_eq!(lottery.get_lottery_setup(0u32).map(|s| s.rebate_tiers.len()), Some(2));
+}
+
+#[ink::test]
+fn voucher_must_exist_to_be_redeemed() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    assert_eq!(lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+
+    assert_eq!(
+        lottery.redeem_voucher(b"WELCOME".to_vec()),
+        Err(ContractError::Internal(Error::VoucherNotFound))
+    );
+    assert_eq!(
+        lottery.place_bet_with_credits(0u32, 1u32, 7u16, default_accounts::<ink::env::DefaultEnvironment>().bob),
+        Err(ContractError::Internal(Error::InsufficientCredits { available: 0 }))
+    );
+}
+
+#[ink::test]
+fn voucher_is_redeemed_by_the_claimant_only() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Simulated, RandomnessSource::Internal, 10u8), Ok(()));
+
+    let code = b"WELCOME".to_vec();
+    let mut code_hash = [0u8; 32];
+    ink::env::hash_bytes::<ink::env::hash::Keccak256>(&code, &mut code_hash);
+    assert_eq!(lottery.create_voucher(0u32, code_hash, 500_000), Ok(()));
+
+    // Bob claims the code, it can be redeemed from the next block on
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    let claim = Lottery::voucher_claim(code.clone(), accounts.bob);
+    assert_eq!(lottery.claim_voucher(0u32, claim), Ok(()));
+    assert_eq!(
+        lottery.get_journal(0).last().map(|e| e.status.clone()),
+        Some(Success::VoucherClaimed { account: accounts.bob, claim: claim })
+    );
+    assert_eq!(
+        lottery.redeem_voucher(code.clone()),
+        Err(ContractError::Internal(Error::VoucherClaimPending { redeemable_block: 1 }))
+    );
+
+    // Charlie saw the code but has no claim of their own
+    ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+    assert_eq!(lottery.redeem_voucher(code.clone()), Err(ContractError::Internal(Error::VoucherNotClaimed)));
+
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(lottery.redeem_voucher(code.clone()), Ok(()));
+    assert_eq!(lottery.get_credits(0u32, accounts.bob), 500_000);
+    assert_eq!(lottery.get_voucher(code_hash).and_then(|v| v.redeemed_by), Some(accounts.bob));
+    assert!(lottery.voucher_claims.get(accounts.bob).is_none());
+
+    // A claim can only be redeemed for a limited time
+    let code = b"SPRING".to_vec();
+    ink::env::hash_bytes::<ink::env::hash::Keccak256>(&code, &mut code_hash);
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+    assert_eq!(lottery.create_voucher(0u32, code_hash, 500_000), Ok(()));
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(lottery.claim_voucher(0u32, Lottery::voucher_claim(code.clone(), accounts.bob)), Ok(()));
+    for _ in 0..=VOUCHER_CLAIM_BLOCKS {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(
+        lottery.redeem_voucher(code),
+        Err(ContractError::Internal(Error::VoucherClaimExpired { expiry_block: 1 + VOUCHER_CLAIM_BLOCKS }))
+    );
+}
+
+#[ink::test]
+fn opening_a_draw_schedules_its_settlement() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    runtime::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+
+    // Processing and closing are scheduler calls
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    let calls = runtime::mock::calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls.iter().all(|call| call[..2] == [15u8, 0u8]));
+
+    // A runtime refusing the scheduling still opens the draw and reports it
+    runtime::mock::fail_with(Some(RuntimeError::CallRuntimeFailed));
+    assert_eq!(lottery.open_draw(0u32, 2u32), Ok(()));
+    runtime::mock::reset();
+    assert_eq!(lottery.get_draw(0u32, 2u32).map(|d| d.status), Some(DrawStatus::Open));
+    let failures: Vec<_> = lottery.get_journal(0).into_iter()
+        .filter_map(|e| match e.status {
+            Success::SchedulingFailed { draw_number, when, error } => Some((draw_number, when, error)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(failures, vec![
+        (2, 5, RuntimeError::CallRuntimeFailed),
+        (2, 10, RuntimeError::CallRuntimeFailed),
+    ]);
+}
+
+#[ink::test]
+fn insurance_withdrawal_is_bounded_by_the_fund() {
+    let mut lottery = Lottery::default();
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+
+    assert_eq!(
+        lottery.set_insurance_percentage(0u32, 6u8),
+        Err(ContractError::Internal(Error::InvalidInsurancePercentage { maximum: 5u8, actual: 6u8 }))
+    );
+    assert_eq!(lottery.top_up_insurance(0u32, 100), Ok(()));
+    assert_eq!(
+        lottery.withdraw_insurance(0u32, 200, accounts.bob),
+        Err(ContractError::Internal(Error::InsufficientInsurance { available: 100 }))
+    );
+    assert_eq!(lottery.get_insurance_fund(0u32).map(|f| f.topped_up), Some(100));
+}
+
+#[ink::test]
+fn fixed_odds_must_pay_more_than_the_bet() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::FixedOdds { multiplier: 1 }),
+        Err(ContractError::Internal(Error::InvalidOdds { multiplier: 1 }))
+    );
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::FixedOdds { multiplier: 700 }),
+        Ok(())
+    );
+
+    // Without a prize reserve the draw can't take a bet on any number
+    assert_eq!(lottery.get_number_capacity(0u32, 1u32, 7u16), Some(0));
+}
+
+#[ink::test]
+fn bet_range_must_be_ascending() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    assert_eq!(lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+
+    assert_eq!(
+        lottery.set_bet_range(0u32, 1u32, 500_000, 100_000),
+        Err(ContractError::Internal(Error::InvalidBetRange { min_bet: 500_000, max_bet: 100_000 }))
+    );
+    assert_eq!(lottery.set_bet_range(0u32, 1u32, 100_000, 500_000), Ok(()));
+    assert_eq!(
+        lottery.get_draw(0u32, 1u32).map(|d| (d.bet_amount, d.max_bet_amount)),
+        Some((100_000, 500_000))
+    );
+}
+
+#[ink::test]
+fn add_bet_transfers_the_shares() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    runtime::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    runtime::mock::reset();
+
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // Operator, dev and affiliate shares, the jackpot and the rebate stay in the contract
+    assert_eq!(runtime::mock::calls().len(), 3);
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.jackpot, draw.rebate), (250_000, 50_000));
+    let reconciliation = lottery.get_reconciliation(0u32, 1u32).unwrap();
+    assert_eq!(reconciliation.stake_received, 500_000);
+    assert_eq!((reconciliation.fees_paid, reconciliation.jackpot_reserved, reconciliation.rebate_reserved),
+        (200_000, 250_000, 50_000));
+
+    // A failing transfer rejects the bet
+    runtime::mock::fail_with(Some(RuntimeError::CallRuntimeFailed));
+    assert!(lottery.add_bet(0u32, 1u32, 8u16, accounts.bob, accounts.charlie, Vec::new(), 500_000).is_err());
+    runtime::mock::reset();
+}
+
+#[ink::test]
+fn simulated_lottery_records_the_transfers() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Simulated, RandomnessSource::Internal, 10u8), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    runtime::mock::reset();
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // Nothing is dispatched, the shares of the operator are recorded instead
+    assert!(runtime::mock::calls().is_empty());
+    assert!(lottery.get_ledger_balance(0u32, accounts.alice).paid > 0);
+}
+
+#[ink::test]
+fn process_draw_uses_the_injected_numbers() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // A fixed seed generates the same number at any block
+    randomness::mock::set_seed(Some([1u8; 32]));
+    let preview = lottery.preview_process_draw(0u32, 1u32).unwrap();
+    assert_eq!(preview.status, DrawStatus::Processing);
+    assert!(matches!(preview.events.as_slice(), [Success::DrawProcessed { .. }]));
+    ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    assert_eq!(lottery.preview_process_draw(0u32, 1u32), Ok(preview));
+
+    // Only the operator or its delegate can preview the transition
+    set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(
+        lottery.preview_process_draw(0u32, 1u32),
+        Err(ContractError::Internal(Error::BadOrigin))
+    );
+    set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+
+    // The winning number then the bonus number
+    randomness::mock::push_winning_number(7);
+    randomness::mock::push_winning_number(8);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.winning_number, draw.bonus_number), (7, 8));
+    randomness::mock::reset();
+}
+
+#[ink::test]
+fn draw_without_bets_rolls_over() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // No number is drawn and the draw waits for the next cycle
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.status, draw.is_open, draw.winning_number), (DrawStatus::Close, false, 0));
+    assert_eq!(lottery.get_metrics(0u32).map(|m| m.draws_processed), Some(0));
+    randomness::mock::reset();
+}
+
+#[ink::test]
+fn draw_below_minimum_participation_refunds_the_stakes() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.set_min_participation(0u32, 1u32, 2u32, 0, false), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+
+    // The shares of the only bet are withheld
+    runtime::mock::reset();
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert!(runtime::mock::calls().is_empty());
+    assert!(!lottery.get_draw(0u32, 1u32).unwrap().participation.withheld.is_empty());
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // The whole stake goes back to the bettor and nothing is drawn
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    assert_eq!(runtime::mock::calls().len(), 1);
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.status, draw.jackpot, draw.bet_count), (DrawStatus::Close, 0, 0));
+    assert_eq!((draw.reconciliation.stake_received, draw.reconciliation.refunded), (0, 500_000));
+    assert!(draw.participation.withheld.is_empty());
+    randomness::mock::reset();
+}
+
+#[ink::test]
+fn refunded_bets_are_carried_forward() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                12u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.set_min_participation(0u32, 1u32, 3u32, 0, true), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+
+    // Django prefers a refund
+    set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+    assert_eq!(lottery.set_carry_forward(0u32, Some(false)), Ok(()));
+    set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 8u16, accounts.django, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    runtime::mock::reset();
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    assert_eq!(runtime::mock::calls().len(), 1);
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!(draw.reconciliation.refunded, 500_000);
+    assert_eq!(draw.participation.carried_bets.len(), 1);
+
+    // The bet of Bob joins the next cycle of the lottery
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.stop(0u32), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    let bets = lottery.get_bets(0u32, 1u32).unwrap();
+    assert_eq!((bets.len(), bets[0].owner, bets[0].bet_number), (1, accounts.bob, 7));
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert!(draw.participation.carried_bets.is_empty());
+    randomness::mock::reset();
+}
+
+#[ink::test]
+fn delegate_manages_only_its_draw() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.set_draw_delegate(0u32, 2u32, Some(accounts.eve)), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+
+    set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+    assert_eq!(lottery.open_draw(0u32, 1u32), Err(ContractError::Internal(Error::BadOrigin)));
+    assert_eq!(lottery.open_draw(0u32, 2u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 2u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.set_draw_delegate(0u32, 2u32, None), Err(ContractError::Internal(Error::BadOrigin)));
+}
+
+#[ink::test]
+fn storage_report_counts_the_draws_and_bets() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    let empty = lottery.get_storage_report();
+
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    let report = lottery.get_storage_report();
+    assert_eq!((report.lotteries, report.draws, report.bets, report.winners), (1, 1, 1, 0));
+    assert!(report.bets_size > empty.bets_size);
+    assert!(report.total_size > report.draws_size);
+}
+
+#[ink::test]
+fn check_invariants_reports_inconsistent_draws() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert!(lottery.check_invariants().is_empty());
+
+    // A closed draw flagged open
+    let mut draw = lottery.get_draw(0u32, 1u32).unwrap();
+    draw.is_open = true;
+    lottery.draws.insert((0u32, 1u32), &draw);
+    let violations = lottery.check_invariants();
+    assert_eq!(violations.len(), 1);
+    assert_eq!((violations[0].draw_number, violations[0].invariant.clone()), (1, Invariant::OpenFlagOnClosedDraw));
+
+    // An open draw holding a jackpot nobody bet on
+    draw.status = DrawStatus::Open;
+    draw.jackpot = 1_000;
+    lottery.draws.insert((0u32, 1u32), &draw);
+    let violations = lottery.check_invariants();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].invariant, Invariant::JackpotWithoutBets);
+}
+
+#[ink::test]
+fn failed_settlement_queues_the_payouts() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    randomness::mock::push_winning_number(8);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // The settlement is stored before the batch of the payouts fails, the payouts are
+    // kept for retry
+    runtime::mock::fail_with(Some(RuntimeError::CallRuntimeFailed));
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.status, draw.winners.len()), (DrawStatus::Close, 1));
+    assert!(!draw.failed_payouts.is_empty());
+    let reconciliation = lottery.get_reconciliation(0u32, 1u32).unwrap();
+    assert_eq!(reconciliation.prizes_paid, 0);
+
+    runtime::mock::reset();
+    assert_eq!(lottery.retry_payouts(0u32, 1u32), Ok(()));
+    assert!(lottery.get_draw(0u32, 1u32).unwrap().failed_payouts.is_empty());
+    assert_eq!(lottery.get_reconciliation(0u32, 1u32).unwrap().pending_payouts, 0);
+    randomness::mock::reset();
+}
+
+#[ink::test]
+fn large_payouts_wait_for_approval() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    let policy = PayoutPolicy {
+        min_amount: 100_001,
+        approvals: 1,
+        delay_blocks: 0,
+    };
+    assert_eq!(lottery.set_payout_policies(0u32, vec![policy], Some(accounts.django)), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    randomness::mock::push_winning_number(8);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // The prize of the winner is held, the rebate is paid
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    let held = lottery.get_held_payouts(0u32);
+    assert_eq!(held.len(), 1);
+    assert_eq!((held[0].draw_number, held[0].payout.recipient), (1, accounts.bob));
+    assert_eq!(lottery.get_reconciliation(0u32, 1u32).unwrap().pending_payouts, held[0].payout.amount);
+
+    // Only the dev or the auditor approves
+    set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(lottery.approve_payouts(0u32, 1u32), Err(ContractError::Internal(Error::BadOrigin)));
+    set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+    runtime::mock::reset();
+    assert_eq!(lottery.approve_payouts(0u32, 1u32), Ok(()));
+    assert_eq!(runtime::mock::calls().len(), 1);
+    assert!(lottery.get_held_payouts(0u32).is_empty());
+    assert_eq!(lottery.get_reconciliation(0u32, 1u32).unwrap().pending_payouts, 0);
+    randomness::mock::reset();
+}
+
+#[ink::test]
+fn bets_are_cut_off_before_processing() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(
+        lottery.set_bet_cutoff(0u32, 1u32, 5u32),
+        Err(ContractError::Internal(Error::EmptyBettingWindow))
+    );
+    assert_eq!(lottery.set_bet_cutoff(0u32, 1u32, 2u32), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // Processing at block 5, no bets from block 3
+    for _ in 0..3 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 8u16, accounts.bob, accounts.charlie, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::BetCutoffReached {
+            cutoff_block: 3,
+            current_block: 3,
+        }))
+    );
+}
+
+#[ink::test]
+fn bets_are_refused_at_the_processing_block() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    // No cutoff is set, the processing block still closes the betting window
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::BetCutoffReached {
+            cutoff_block: 5,
+            current_block: 5,
+        }))
+    );
+}
+
+#[ink::test]
+fn daily_limit_resets_at_the_day_boundary() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                20u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    oracle::mock::set_price(Some(oracle::PRICE_UNIT));
+    runtime::mock::reset();
+
+    assert_eq!(lottery.set_oracle(0u32, Some(accounts.frank), 1_000_000), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.set_min_participation(0u32, 1u32, 3u32, 0, false), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 8u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // The third bet of the day exceeds the limit
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 9u16, accounts.bob, accounts.charlie, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::DailyLimitExceeded {
+            limit: 1_000_000,
+            actual: 1_500_000,
+        }))
+    );
+
+    // The stakes are refunded and the next day starts at block 20
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..14 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.stop(0u32), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 9u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.daily_stakes.get((accounts.bob, 0u32, 0u32)), Some(1_000_000));
+    assert_eq!(lottery.daily_stakes.get((accounts.bob, 0u32, 1u32)), Some(500_000));
+    oracle::mock::set_price(None);
+}
+
+#[ink::test]
+fn delayed_withdrawal_is_released_after_the_delay() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    // Two approvals need an auditor
+    let policy = PayoutPolicy {
+        min_amount: 1_000,
+        approvals: 2,
+        delay_blocks: 3,
+    };
+    assert_eq!(
+        lottery.set_payout_policies(0u32, vec![policy.clone()], None),
+        Err(ContractError::Internal(Error::InvalidPayoutPolicy))
+    );
+    let policy = PayoutPolicy {
+        approvals: 0,
+        ..policy
+    };
+    assert_eq!(lottery.set_payout_policies(0u32, vec![policy], None), Ok(()));
+
+    // Small withdrawals are paid immediately
+    assert_eq!(lottery.top_up_insurance(0u32, 5_000), Ok(()));
+    runtime::mock::reset();
+    assert_eq!(lottery.withdraw_insurance(0u32, 500, accounts.bob), Ok(()));
+    assert_eq!(runtime::mock::calls().len(), 1);
+
+    assert_eq!(lottery.withdraw_insurance(0u32, 2_000, accounts.bob), Ok(()));
+    assert_eq!(lottery.get_held_payouts(0u32).len(), 1);
+    assert_eq!(lottery.release_payouts(0u32, 0u32), Err(ContractError::Internal(Error::NoRecords)));
+    for _ in 0..3 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.release_payouts(0u32, 0u32), Ok(()));
+    assert_eq!(runtime::mock::calls().len(), 2);
+    assert!(lottery.get_held_payouts(0u32).is_empty());
+}
+
+#[ink::test]
+fn exported_draws_and_bets_decode() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 8u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    let draws = lottery.export_draws(0u32, 0, 10);
+    assert_eq!(draws.len(), 1);
+    let draw = Draw::decode(&mut &draws[0][..]).unwrap();
+    assert_eq!((draw.draw_number, draw.jackpot, draw.bet_count), (1, 500_000, 0));
+
+    // The second page holds the second bet
+    let bets = lottery.export_bets(0u32, 1u32, 1, 1);
+    assert_eq!(bets.len(), 1);
+    assert_eq!(Bet::decode(&mut &bets[0][..]).unwrap().bet_number, 8);
+    assert!(lottery.export_setup(0u32).is_some());
+}
+
+#[ink::test]
+fn exported_state_imports_into_a_fresh_deployment() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Native, RandomnessSource::Internal, 10u8), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // Both instances share the mappings in the off-chain environment, the state is
+    // exported before it is imported
+    let setup = lottery.export_setup(0u32).unwrap();
+    let draws = lottery.export_draws(0u32, 0, 10);
+    let bets = lottery.export_bets(0u32, 1u32, 0, 10);
+
+    let mut migrated = Lottery::new_for_import(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16
+    );
+    assert_eq!(migrated.import_setup(0u32, setup), Ok(()));
+    assert_eq!(migrated.import_draws(0u32, draws.clone()), Ok(()));
+    assert_eq!(migrated.import_bets(0u32, 1u32, bets), Ok(()));
+
+    // The open draw and its bet survive the migration, the lottery waits for a start
+    let draw = migrated.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.status, draw.jackpot, draw.bet_count), (DrawStatus::Open, 250_000, 1));
+    assert_eq!(migrated.get_lottery_setup(0u32).map(|s| s.is_started), Some(false));
+
+    // A draw is imported once and nothing is imported after the completion
+    assert_eq!(
+        migrated.import_draws(0u32, draws),
+        Err(ContractError::Internal(Error::InvalidSnapshot))
+    );
+
+    // The contract must hold the jackpot and the imported credits
+    let contract = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+    ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 250_000);
+    assert_eq!(migrated.import_credits(0u32, vec![(accounts.django, 100)]), Ok(()));
+    assert_eq!(
+        migrated.complete_import(),
+        Err(ContractError::Internal(Error::ImportUnderfunded {
+            liabilities: 250_100,
+            available: 250_000,
+        }))
+    );
+    ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 250_100);
+    assert_eq!(migrated.complete_import(), Ok(()));
+    assert_eq!(
+        migrated.import_credits(0u32, vec![(accounts.bob, 1)]),
+        Err(ContractError::Internal(Error::ImportClosed))
+    );
+}
+
+#[ink::test]
+fn imports_need_the_opt_in_and_valid_draws() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 3_000u32, 3_500u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.set_bet_cutoff(0u32, 1u32, 100u32), Ok(()));
+    let setup = lottery.export_setup(0u32).unwrap();
+    let draws = lottery.export_draws(0u32, 0, 10);
+
+    // A contract instantiated with `new` takes no imports
+    assert_eq!(
+        lottery.import_setup(0u32, setup.clone()),
+        Err(ContractError::Internal(Error::ImportClosed))
+    );
+
+    let mut migrated = Lottery::new_for_import(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16
+    );
+    assert_eq!(migrated.import_setup(1u32, setup), Ok(()));
+
+    // The cutoff must leave a betting window and the blocks must follow each other
+    let mut draw = Draw::decode(&mut &draws[0][..]).unwrap();
+    draw.processing_blocks = 50;
+    assert_eq!(
+        migrated.import_draws(1u32, vec![scale::Encode::encode(&draw)]),
+        Err(ContractError::Internal(Error::EmptyBettingWindow))
+    );
+    draw.processing_blocks = 3_000;
+    draw.closing_blocks = 14_400;
+    assert_eq!(
+        migrated.import_draws(1u32, vec![scale::Encode::encode(&draw)]),
+        Err(ContractError::Internal(Error::InvalidBlocksHierarchy))
+    );
+    assert_eq!(migrated.import_draws(1u32, draws), Ok(()));
+    assert_eq!(migrated.get_draw(1u32, 1u32).map(|d| d.cutoff_blocks), Some(100));
+}
+
+#[ink::test]
+fn promotional_draw_can_be_free() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(
+        lottery.add_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 0, SettlementMode::PariMutuel),
+        Err(ContractError::Internal(Error::ZeroBetAmount))
+    );
+    assert_eq!(lottery.add_promotional_draw(0u32, 1_000u32, 3_000u32, 3_500u32, 0), Ok(()));
+    assert_eq!(lottery.get_draw(0u32, 1u32).map(|d| d.promotional), Some(true));
+}
+
+#[ink::test]
+fn promotional_draw_takes_one_bet_per_account() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_promotional_draw(0u32, 0u32, 5u32, 10u32, 500_000), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 8u16, accounts.bob, accounts.charlie, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::AlreadyBet))
+    );
+    assert_eq!(lottery.add_bet(0u32, 1u32, 8u16, accounts.django, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // The bettors are forgotten with the bets of the cycle
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    randomness::mock::reset();
+    assert!(lottery.promotional_bettors.get((0u32, 1u32, accounts.bob)).is_none());
+}
+
+#[ink::test]
+fn daily_draws_split_the_cycle() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    let template = DrawTemplate {
+        draws: 2u8,
+        betting_percentage: 80u8,
+        bet_amount: 500_000,
+        settlement_mode: SettlementMode::PariMutuel,
+    };
+    assert_eq!(lottery.add_daily_draws(0u32, template), Ok(()));
+
+    let draws = lottery.get_draws(0u32);
+    let blocks: Vec<(u32, u32, u32)> = draws.iter()
+        .map(|d| (d.opening_blocks, d.processing_blocks, d.closing_blocks))
+        .collect();
+    assert_eq!(blocks, vec![(0, 5_760, 6_480), (7_200, 12_960, 13_680)]);
+}
+
+#[ink::test]
+fn committed_result_is_published_before_closing() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Simulated, RandomnessSource::CommitReveal, 10u8), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // The draw can't be processed without a commitment
+    assert_eq!(lottery.process_draw(0u32, 1u32), Err(ContractError::Internal(Error::ResultNotCommitted)));
+    let salt = [42u8; 32];
+    assert_eq!(lottery.commit_result(0u32, 1u32, Lottery::result_commitment(7u16, salt)), Ok(()));
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+
+    // The result is published a few blocks later and must match the commitment
+    let current_block = ink::env::block_number::<ink::env::DefaultEnvironment>();
+    assert_eq!(lottery.publish_result(0u32, 1u32, 7u16, salt), Err(ContractError::Internal(Error::DrawNotDue {
+        due_block: current_block + 2,
+        current_block: current_block,
+    })));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.close_draw(0u32, 1u32), Err(ContractError::Internal(Error::ResultNotPublished)));
+    assert_eq!(lottery.publish_result(0u32, 1u32, 8u16, salt), Err(ContractError::Internal(Error::CommitmentMismatch)));
+    assert_eq!(lottery.publish_result(0u32, 1u32, 7u16, salt), Ok(()));
+    assert_eq!(lottery.override_draw(0u32, 1u32, 8u16), Err(ContractError::Internal(Error::CommitmentMismatch)));
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.get_draws(0u32)[0].winners.len(), 1);
+}
+
+#[ink::test]
+fn closing_emits_the_settlement_summary() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // The transfers fail and are queued, the summary is the last event of the closing
+    runtime::mock::fail_with(Some(RuntimeError::CallRuntimeFailed));
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    runtime::mock::reset();
+    randomness::mock::reset();
+
+    let failed = lottery.get_draw(0u32, 1u32).unwrap().failed_payouts.len() as u32;
+    assert!(failed > 0);
+    let journal = lottery.get_journal(0);
+    assert_eq!(journal.last().map(|e| e.status.clone()), Some(Success::DrawSettled {
+        draw_number: 1,
+        winners: 1,
+        jackpot_paid: 250_000,
+        rebate_paid: 50_000,
+        dust: 0,
+        failed_payouts: failed,
+    }));
+}
+
+#[ink::test]
+fn deposit_budget_refuses_bets_until_pruned() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 50u32, 100u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 2u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    randomness::mock::reset();
+    assert_eq!(lottery.get_storage_deposit().deposit, 0);
+
+    // The budget is used up, the next bet is refused
+    let bytes = lottery.get_storage_deposit().bytes;
+    assert_eq!(lottery.set_deposit_budget(Some(DepositBudget {
+        byte_deposit: 10,
+        limit: bytes as u128 * 10,
+    })), Ok(()));
+    assert_eq!(lottery.get_journal(0).last().map(|e| e.status.clone()), Some(Success::DepositBudgetSet {
+        byte_deposit: 10,
+        limit: Some(bytes as u128 * 10),
+    }));
+    // The journal entry of the budget itself takes storage
+    assert!(lottery.get_storage_deposit().deposit > bytes as u128 * 10);
+    assert!(matches!(lottery.add_bet(0u32, 2u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::DepositBudgetExceeded { .. }))));
+
+    // The winners of the closed cycle are pruned once
+    assert_eq!(lottery.prune_storage(0u32), Ok(()));
+    assert!(lottery.get_draw(0u32, 1u32).unwrap().winners.is_empty());
+    assert!(matches!(lottery.get_journal(0).last().map(|e| e.status.clone()),
+        Some(Success::StoragePruned { winners: 1, bytes }) if bytes > 0));
+    assert_eq!(lottery.prune_storage(0u32), Err(ContractError::Internal(Error::NoRecords)));
+
+    // Only the dev sets the budget
+    set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(lottery.set_deposit_budget(None), Err(ContractError::Internal(Error::BadOrigin)));
+}
+
+#[ink::test]
+fn compact_bets_are_kept_per_number() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.set_compact_bets(0u32, 1u32, true), Ok(()));
+    assert_eq!(lottery.set_early_bird(0u32, 1u32, 3u32, 5u8), Err(ContractError::Internal(Error::CompactBetsUnsupported)));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.django, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 8u16, accounts.eve, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // Compact bets can't be linked to a bundle
+    assert_eq!(
+        lottery.buy_bundle(0u32, vec![9u16], vec![1u32], accounts.charlie),
+        Err(ContractError::Internal(Error::CompactBetsUnsupported))
+    );
+
+    // Only the owners are stored, the getters expand them
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.bet_count, draw.bet_numbers.clone()), (3, vec![7, 8]));
+    assert!(lottery.bets.get((0u32, 1u32, 0u32)).is_none());
+    assert_eq!(lottery.number_bets.get((0u32, 1u32, 7u16, 0u32)), Some(accounts.bob));
+    assert_eq!(lottery.number_bets.get((0u32, 1u32, 7u16, 1u32)), Some(accounts.django));
+    assert_eq!(lottery.get_bets(0u32, 1u32).unwrap().len(), 3);
+    assert_eq!(lottery.get_number_distribution(0u32, 1u32), vec![(7, 2), (8, 1)]);
+
+    // The bets are settled like full bets
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    randomness::mock::reset();
+
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!(draw.winners.iter().map(|w| w.owner).collect::<Vec<_>>(), vec![accounts.bob, accounts.django]);
+    assert!(draw.bet_numbers.is_empty());
+    assert!(lottery.number_bets.get((0u32, 1u32, 7u16, 0u32)).is_none());
+    assert!(lottery.number_bets.get((0u32, 1u32, 7u16, 1u32)).is_none());
+}
+
+#[ink::test]
+fn messages_have_stable_codes() {
+    assert_eq!(Success::LotterySetup {
+        operator: default_accounts::<ink::env::DefaultEnvironment>().alice,
+        asset_id: 1984,
+        starting_block: 0,
+    }.code(), 1000);
+    assert_eq!(Success::DrawRemoved { draw_number: 1 }.code(), 1004);
+    assert_eq!(ContractError::Internal(Error::AlreadyStarted).code(), 2000);
+    assert_eq!(ContractError::Internal(Error::BadOrigin).code(), 2003);
+    assert_eq!(ContractError::Runtime(RuntimeError::CallRuntimeFailed).code(), 3000);
+    assert_eq!(ContractError::Runtime(RuntimeError::TransferFailed).code(), 3001);
+}
+
+#[ink::test]
+fn payouts_are_claimed_by_their_recipients() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+    runtime::mock::reset();
+
+    assert_eq!(lottery.set_claim_payouts(0u32, true), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    let calls = runtime::mock::calls().len();
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    randomness::mock::reset();
+
+    // Nothing is transferred when closing, the upline did not bet so its share is the
+    // operator's
+    assert_eq!(runtime::mock::calls().len(), calls);
+    assert_eq!(lottery.get_claim(0u32, accounts.bob), Claim { prizes: 225_000, rebates: 50_000 });
+    assert_eq!(lottery.get_claim(0u32, accounts.alice), Claim { prizes: 25_000, rebates: 0 });
+
+    set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+    assert_eq!(lottery.claim_prize(0u32), Ok(()));
+    assert_eq!(lottery.claim_prize(0u32), Err(ContractError::Internal(Error::NoRecords)));
+    assert_eq!(lottery.claim_rebate(0u32), Ok(()));
+    assert_eq!(runtime::mock::calls().len(), calls + 2);
+    assert_eq!(lottery.get_claim(0u32, accounts.bob), Claim::default());
+    runtime::mock::reset();
+}
+
+#[cfg(not(feature = "randomness-extension"))]
+#[ink::test]
+fn epoch_randomness_requires_the_extension() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Simulated, RandomnessSource::Epoch, 10u8), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // Without the chain extension the draw is not processed with another entropy
+    assert_eq!(lottery.process_draw(0u32, 1u32), Err(ContractError::Runtime(RuntimeError::ChainExtensionFailed)));
+    assert_eq!(lottery.get_draw(0u32, 1u32).map(|d| d.status), Some(DrawStatus::Open));
+}
+
+#[ink::test]
+fn salt_reveal_draw_is_processed_with_the_committed_salt() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Simulated, RandomnessSource::SaltReveal, 10u8), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+
+    // The draw is only opened with a commitment to its salt
+    let salt = [42u8; 32];
+    assert_eq!(lottery.open_draw(0u32, 1u32), Err(ContractError::Internal(Error::SaltNotCommitted)));
+    assert_eq!(lottery.open_draw_with_commitment(0u32, 1u32, Lottery::salt_commitment(salt)), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+
+    // The salt is revealed in the processing block of the draw only
+    let reveal_block = lottery.get_draw(0u32, 1u32).unwrap().committed_salt.unwrap().reveal_block;
+    assert_eq!(reveal_block, 5);
+    while ink::env::block_number::<ink::env::DefaultEnvironment>() < reveal_block - 1 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    assert_eq!(lottery.process_draw_with_salt(0u32, 1u32, salt), Err(ContractError::Internal(Error::DrawNotDue {
+        due_block: reveal_block,
+        current_block: reveal_block - 1,
+    })));
+    ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+
+    // The draw is processed once the committed salt is revealed
+    assert_eq!(lottery.process_draw(0u32, 1u32), Err(ContractError::Internal(Error::SaltNotRevealed)));
+    assert_eq!(
+        lottery.process_draw_with_salt(0u32, 1u32, [7u8; 32]),
+        Err(ContractError::Internal(Error::CommitmentMismatch))
+    );
+    assert_eq!(lottery.process_draw_with_salt(0u32, 1u32, salt), Ok(()));
+
+    // The salt and the randomness of the reveal block are kept to verify the winning number
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    let committed_salt = draw.committed_salt.unwrap();
+    assert_eq!(draw.status, DrawStatus::Processing);
+    assert_eq!(committed_salt.salt, Some(salt));
+    assert_ne!(committed_salt.entropy, [0u8; 32]);
+}
+
+#[ink::test]
+fn unrevealed_salt_forfeits_the_cycle() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.setup(0u32, accounts.alice, 1984u128, 0u32, 14_400u32, 2u8, 1_000u16,
+        PaymentBackend::Simulated, RandomnessSource::SaltReveal, 10u8), Ok(()));
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    let salt = [42u8; 32];
+    assert_eq!(lottery.open_draw_with_commitment(0u32, 1u32, Lottery::salt_commitment(salt)), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+
+    // The reveal block has passed, the stakes are refunded instead of drawing
+    assert_eq!(lottery.process_draw_with_salt(0u32, 1u32, salt), Err(ContractError::Internal(Error::SaltRevealMissed {
+        reveal_block: 5,
+        current_block: 6,
+    })));
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    let draw = lottery.get_draw(0u32, 1u32).unwrap();
+    assert_eq!((draw.status, draw.reconciliation.refunded), (DrawStatus::Close, 500_000));
+}
+
+#[ink::test]
+fn prize_tiers_pay_partial_digit_matches() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(
+        lottery.set_prize_tiers(0u32, 1u32, vec![PrizeTier { digits: 4, percentage: 10 }]),
+        Err(ContractError::Internal(Error::InvalidPrizeTiers))
+    );
+    assert_eq!(lottery.set_prize_tiers(0u32, 1u32, vec![
+        PrizeTier { digits: 1, percentage: 20 },
+        PrizeTier { digits: 3, percentage: 50 },
+        PrizeTier { digits: 2, percentage: 30 },
+    ]), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 123u16, accounts.bob, accounts.alice, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 523u16, accounts.charlie, accounts.alice, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 993u16, accounts.django, accounts.alice, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 456u16, accounts.eve, accounts.alice, Vec::new(), 500_000), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(123);
+    randomness::mock::push_winning_number(1);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::reset();
+
+    // Every winner is paid 90% of the percentage of its tier of the 1_000_000 jackpot
+    let plan = lottery.preview_close_draw(0u32, 1u32).unwrap();
+    assert_eq!(
+        plan.winners.iter().map(|w| (w.owner, w.tier, w.bettor_share)).collect::<Vec<_>>(),
+        vec![(accounts.bob, 0, 450_000), (accounts.charlie, 1, 270_000), (accounts.django, 2, 180_000)]
+    );
+    assert!(plan.second_winners.is_empty() && plan.third_winners.is_empty());
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.get_draw(0u32, 1u32).unwrap().winners.len(), 3);
+}
+
+#[ink::test]
+fn shares_split_the_stakes_and_the_prizes() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    // The stake shares must sum up to 100%
+    let shares = Shares { jackpot: 60, dev: 10, rebate: 10, operator: 20, winner: 80 };
+    assert_eq!(
+        lottery.set_shares(0u32, Shares { jackpot: 70, ..shares.clone() }),
+        Err(ContractError::Internal(Error::InvalidShares))
+    );
+    assert_eq!(lottery.set_shares(0u32, shares.clone()), Ok(()));
+    assert_eq!(lottery.get_lottery_setup(0u32).map(|s| s.shares), Some(shares));
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.set_shares(0u32, Shares::default()), Err(ContractError::Internal(Error::AlreadyStarted)));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+    assert_eq!(lottery.get_draw(0u32, 1u32).unwrap().jackpot, 300_000);
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::reset();
+
+    // The winner is paid 80% of the jackpot and its upline the rest
+    let plan = lottery.preview_close_draw(0u32, 1u32).unwrap();
+    assert_eq!((plan.winners[0].bettor_share, plan.winners[0].upline_share), (240_000, 60_000));
+}
+
+#[ink::test]
+fn operator_funded_percentages_fit_in_the_operator_share() {
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    // The affiliate percentage of 10% is taken from the operator share
+    assert_eq!(
+        lottery.set_shares(0u32, Shares { jackpot: 75, dev: 10, rebate: 10, operator: 5, winner: 90 }),
+        Err(ContractError::Internal(Error::InvalidAffiliatePercentage { maximum: 5, actual: 10 }))
+    );
+    let shares = Shares { jackpot: 65, dev: 10, rebate: 10, operator: 15, winner: 90 };
+    assert_eq!(lottery.set_shares(0u32, shares), Ok(()));
+
+    // The discount and the bonuses share the 5% left of the operator share
+    assert_eq!(
+        lottery.set_bulk_discount(0u32, 10u32, 6u8),
+        Err(ContractError::Internal(Error::DiscountExceedsOperatorShare))
+    );
+    assert_eq!(lottery.set_bulk_discount(0u32, 10u32, 5u8), Ok(()));
+    assert_eq!(
+        lottery.set_streak_bonus(0u32, 3u32, 6u8),
+        Err(ContractError::Internal(Error::InvalidBonusPercentage { maximum: 5, actual: 6 }))
+    );
+    assert_eq!(
+        lottery.set_referral_bonus(0u32, 3u8),
+        Err(ContractError::Internal(Error::InvalidBonusPercentage { maximum: 2, actual: 3 }))
+    );
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(
+        lottery.set_early_bird(0u32, 1u32, 5u32, 6u8),
+        Err(ContractError::Internal(Error::InvalidBonusPercentage { maximum: 5, actual: 6 }))
+    );
+
+    // A smaller operator share must still fund the discount
+    assert_eq!(
+        lottery.set_shares(0u32, Shares { jackpot: 68, dev: 10, rebate: 10, operator: 12, winner: 90 }),
+        Err(ContractError::Internal(Error::DiscountExceedsOperatorShare))
+    );
+}
+
+#[ink::test]
+fn division_rounding_stays_in_the_jackpot() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 100_002, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    for bettor in [accounts.bob, accounts.charlie, accounts.django] {
+        assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, bettor, accounts.eve, Vec::new(), 100_002), Ok(()));
+    }
+    assert_eq!(lottery.get_draw(0u32, 1u32).unwrap().jackpot, 150_003);
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::reset();
+
+    // Only the shares assigned to the winners are taken from the jackpot
+    let plan = lottery.preview_close_draw(0u32, 1u32).unwrap();
+    let assigned: u128 = plan.winners.iter().map(|w| w.bettor_share + w.upline_share).sum();
+    assert_eq!((assigned, plan.jackpot_paid), (150_000, 150_000));
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.get_draw(0u32, 1u32).unwrap().jackpot, 3);
+}
+
+#[ink::test]
+fn settlement_splits_amounts_of_18_decimals() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+    randomness::mock::reset();
+
+    // 10_000 units of an 18-decimal asset, the rebate times a stake exceeds a u128
+    let bet_amount: u128 = 10_000 * 10u128.pow(18);
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, bet_amount, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), bet_amount), Ok(()));
+    assert_eq!(lottery.add_bet(0u32, 1u32, 8u16, accounts.django, accounts.charlie, Vec::new(), bet_amount), Ok(()));
+    for _ in 0..6 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::push_winning_number(7);
+    assert_eq!(lottery.process_draw(0u32, 1u32), Ok(()));
+    for _ in 0..5 {
+        ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+    }
+    randomness::mock::reset();
+
+    // The winner takes 90% of the jackpot and every bettor half of the rebate
+    let plan = lottery.preview_close_draw(0u32, 1u32).unwrap();
+    assert_eq!(plan.winners[0].bettor_share, bet_amount * 90 / 100);
+    let rebates: Vec<u128> = plan.payouts.iter()
+        .filter(|p| p.role == PayoutRole::Rebate)
+        .map(|p| p.amount)
+        .collect();
+    assert_eq!(rebates, vec![bet_amount / 10; 2]);
+    assert_eq!(lottery.close_draw(0u32, 1u32), Ok(()));
+}
+
+#[ink::test]
+fn locked_money_paths_reject_reentry() {
+    let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+    let mut lottery = Lottery::new(
+                                1984u128,
+                                0u32,
+                                14_400u32,
+                                2u8,
+                                1_000u16,
+                                false
+    );
+
+    assert_eq!(lottery.add_draw(0u32, 0u32, 5u32, 10u32, 500_000, SettlementMode::PariMutuel), Ok(()));
+    assert_eq!(lottery.start(0u32), Ok(()));
+    assert_eq!(lottery.open_draw(0u32, 1u32), Ok(()));
+
+    // The lock is stored in its own cell, as seen by a call made while a money path runs
+    lottery.locked.set(&true);
+    assert_eq!(
+        lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000),
+        Err(ContractError::Internal(Error::Reentrancy))
+    );
+    lottery.locked.set(&false);
+    assert_eq!(lottery.add_bet(0u32, 1u32, 7u16, accounts.bob, accounts.charlie, Vec::new(), 500_000), Ok(()));
+}
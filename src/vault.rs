@@ -0,0 +1,87 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
+use ink::prelude::vec::Vec;
+
+use crate::errors::RuntimeError;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+
+/// Prize vault contract interface, see the `vault` contract.
+///
+/// The vault returns `Result<(), VaultError>`, only the index of the error variant is
+/// decoded here.
+#[ink::trait_definition]
+pub trait PrizeVault {
+    /// Records the jackpot share transferred to the vault for the draw.
+    #[ink(message)]
+    fn credit(&mut self, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), u8>;
+
+    /// Transfers the paid jackpot of the draw back to the lottery for settlement.
+    #[ink(message)]
+    fn release(&mut self, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), u8>;
+
+    /// Pays the settlement instructions `(recipient, amount)` of the draw from its pot.
+    #[ink(message)]
+    fn settle(&mut self, lottery_id: u32, draw_number: u32, payouts: Vec<(AccountId, u128)>) -> Result<(), u8>;
+}
+
+/// Records a jackpot deposit at the vault (`PrizeVault::credit`).
+pub fn credit(vault: AccountId, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), RuntimeError> {
+    call(vault, ink::selector_bytes!("PrizeVault::credit"), lottery_id, draw_number, amount)
+}
+
+/// Withdraws the paid jackpot of a draw from the vault (`PrizeVault::release`).
+pub fn release(vault: AccountId, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), RuntimeError> {
+    call(vault, ink::selector_bytes!("PrizeVault::release"), lottery_id, draw_number, amount)
+}
+
+/// Instructs the vault to pay the jackpot payouts of a draw (`PrizeVault::settle`).
+pub fn settle(vault: AccountId,
+              lottery_id: u32,
+              draw_number: u32,
+              payouts: Vec<(AccountId, u128)>) -> Result<(), RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(vault)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PrizeVault::settle")))
+                .push_arg(lottery_id)
+                .push_arg(draw_number)
+                .push_arg(payouts),
+        )
+        .returns::<Result<(), u8>>()
+        .try_invoke();
+
+    map_result(result)
+}
+
+fn call(vault: AccountId,
+        selector: [u8; 4],
+        lottery_id: u32,
+        draw_number: u32,
+        amount: u128) -> Result<(), RuntimeError> {
+    let result = build_call::<DefaultEnvironment>()
+        .call(vault)
+        .exec_input(
+            ExecutionInput::new(Selector::new(selector))
+                .push_arg(lottery_id)
+                .push_arg(draw_number)
+                .push_arg(amount),
+        )
+        .returns::<Result<(), u8>>()
+        .try_invoke();
+
+    map_result(result)
+}
+
+fn map_result(
+    result: Result<ink::MessageResult<Result<(), u8>>, ink::env::Error>,
+) -> Result<(), RuntimeError> {
+    match result {
+        Ok(Ok(Ok(()))) => Ok(()),
+        // The vault refused the deposit or the withdrawal
+        Ok(Ok(Err(_))) => Err(RuntimeError::VaultRejected),
+        // The vault contract could not dispatch the message
+        Ok(Err(_)) => Err(RuntimeError::CalleeReverted),
+        Err(e) => Err(RuntimeError::from(e)),
+    }
+}
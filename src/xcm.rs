@@ -0,0 +1,121 @@
+use ink::prelude::vec::Vec;
+
+/// Versioned location, only XCM v3 is supported.
+#[ink::scale_derive(Encode)]
+pub enum VersionedLocation {
+    #[codec(index = 3)]
+    V3(MultiLocation),
+}
+
+/// Versioned assets, only XCM v3 is supported.
+#[ink::scale_derive(Encode)]
+pub enum VersionedAssets {
+    #[codec(index = 3)]
+    V3(Vec<MultiAsset>),
+}
+
+/// Relative location of a consensus system or an account.
+#[ink::scale_derive(Encode)]
+pub struct MultiLocation {
+    pub parents: u8,
+    pub interior: Junctions,
+}
+
+/// Interior of a location.
+#[ink::scale_derive(Encode)]
+pub enum Junctions {
+    #[codec(index = 0)]
+    Here,
+    #[codec(index = 1)]
+    X1(Junction),
+    #[codec(index = 2)]
+    X2(Junction, Junction),
+}
+
+/// Single item of an interior location.
+#[ink::scale_derive(Encode)]
+pub enum Junction {
+    #[codec(index = 0)]
+    Parachain(#[codec(compact)] u32),
+    #[codec(index = 1)]
+    AccountId32 {
+        network: Option<u8>,
+        id: [u8; 32],
+    },
+    #[codec(index = 4)]
+    PalletInstance(u8),
+    #[codec(index = 5)]
+    GeneralIndex(#[codec(compact)] u128),
+}
+
+/// Fungible amount of an asset identified by its location.
+#[ink::scale_derive(Encode)]
+pub struct MultiAsset {
+    pub id: AssetId,
+    pub fun: Fungibility,
+}
+
+/// Identity of an asset.
+#[ink::scale_derive(Encode)]
+pub enum AssetId {
+    #[codec(index = 0)]
+    Concrete(MultiLocation),
+}
+
+/// Classification of an asset.
+#[ink::scale_derive(Encode)]
+pub enum Fungibility {
+    #[codec(index = 0)]
+    Fungible(#[codec(compact)] u128),
+}
+
+/// Weight limit of the execution at the destination.
+#[ink::scale_derive(Encode)]
+pub enum WeightLimit {
+    #[codec(index = 0)]
+    Unlimited,
+}
+
+/// Location of a sibling parachain.
+pub fn sibling(para_id: u32) -> VersionedLocation {
+    VersionedLocation::V3(MultiLocation {
+        parents: 1,
+        interior: Junctions::X1(Junction::Parachain(para_id)),
+    })
+}
+
+/// Location of an account at the destination chain.
+pub fn beneficiary(account: [u8; 32]) -> VersionedLocation {
+    VersionedLocation::V3(MultiLocation {
+        parents: 0,
+        interior: Junctions::X1(Junction::AccountId32 {
+            network: None,
+            id: account,
+        }),
+    })
+}
+
+/// Fungible amount of an asset of the local assets pallet.
+pub fn local_asset(pallet_instance: u8, asset_id: u128, amount: u128) -> VersionedAssets {
+    VersionedAssets::V3(ink::prelude::vec![MultiAsset {
+        id: AssetId::Concrete(MultiLocation {
+            parents: 0,
+            interior: Junctions::X2(
+                Junction::PalletInstance(pallet_instance),
+                Junction::GeneralIndex(asset_id),
+            ),
+        }),
+        fun: Fungibility::Fungible(amount),
+    }])
+}
+
+/// Fungible amount of the native token of the chain.
+pub fn native_asset(amount: u128) -> VersionedAssets {
+    VersionedAssets::V3(ink::prelude::vec![MultiAsset {
+        id: AssetId::Concrete(MultiLocation {
+            parents: 0,
+            interior: Junctions::Here,
+        }),
+        fun: Fungibility::Fungible(amount),
+    }])
+}
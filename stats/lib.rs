@@ -0,0 +1,108 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Lottery statistics
+///
+/// Companion of the lottery contract.  The lottery notifies it of every settlement and
+/// it keeps the totals of every account, so dApps can query them without reading the
+/// draws of the lottery.
+#[ink::contract]
+mod stats {
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+
+    /// Totals of an account over all lotteries
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AccountStats {
+        // Settled bets owned by the account
+        pub bets: u64,
+        // Total bet amount of the settled bets
+        pub staked: u128,
+        // Winning bets
+        pub wins: u64,
+        // Total prize of the winning bets
+        pub won: u128,
+    }
+
+    /// Settlement aggregated
+    #[ink(event)]
+    pub struct SettlementRecorded {
+        #[ink(topic)]
+        lottery_id: u32,
+        draw_number: u32,
+    }
+
+    /// Stats
+    #[ink(storage)]
+    pub struct Stats {
+        // Lottery contract allowed to report settlements
+        lottery: AccountId,
+        // Totals per account
+        accounts: Mapping<AccountId, AccountStats>,
+        // Settled draws
+        settlements: u64,
+    }
+
+    impl Stats {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new(lottery: AccountId) -> Self {
+            Self {
+                lottery: lottery,
+                accounts: Mapping::default(),
+                settlements: 0,
+            }
+        }
+
+        /// Aggregate a settlement
+        ///
+        /// 1. Only the lottery reports settlements, other callers are ignored.
+        /// 2. Every bettor is counted once per bet and every winner once per prize.
+        #[ink(message, selector = 0xb020246b)]
+        pub fn on_settlement(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            bet_amount: u128,
+            bettors: Vec<AccountId>,
+            winners: Vec<(AccountId, u128)>) {
+
+            if self.env().caller() != self.lottery {
+                return;
+            }
+
+            for bettor in bettors {
+                let mut stats = self.accounts.get(bettor).unwrap_or_default();
+                stats.bets += 1;
+                stats.staked = stats.staked.saturating_add(bet_amount);
+                self.accounts.insert(bettor, &stats);
+            }
+
+            for (winner, prize) in winners {
+                let mut stats = self.accounts.get(winner).unwrap_or_default();
+                stats.wins += 1;
+                stats.won = stats.won.saturating_add(prize);
+                self.accounts.insert(winner, &stats);
+            }
+
+            self.settlements += 1;
+
+            self.env().emit_event(SettlementRecorded {
+                lottery_id: lottery_id,
+                draw_number: draw_number,
+            });
+        }
+
+        /// Returns the totals of an account
+        #[ink(message)]
+        pub fn get_stats(&self, account: AccountId) -> AccountStats {
+            self.accounts.get(account).unwrap_or_default()
+        }
+
+        /// Returns the number of settled draws
+        #[ink(message)]
+        pub fn get_settlements(&self) -> u64 {
+            self.settlements
+        }
+    }
+}
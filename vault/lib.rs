@@ -0,0 +1,276 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Prize vault
+///
+/// Holds the jackpots of the lottery contract.  Deposits are tagged per draw and the
+/// lottery can only withdraw a pot through settlement instructions of that draw, so a
+/// faulty or compromised lottery logic cannot drain the jackpots of the other draws.
+/// The guardian, e.g., the dev multisig, can freeze all withdrawals in an emergency.
+#[ink::contract]
+mod vault {
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use sp_runtime::MultiAddress;
+
+    /// Asset held by the vault, it must match the payment backend of the lottery
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VaultAsset {
+        // pallet_assets asset id
+        Assets(u128),
+        // Native token of the chain
+        Native,
+    }
+
+    /// Vault errors
+    ///
+    /// The variants carry no data so the lottery can decode the error index.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum VaultError {
+        /// The caller is not allowed to call the message
+        BadOrigin,
+        /// Withdrawals are frozen by the guardian
+        Frozen,
+        /// The pot of the draw holds less than the requested amount
+        InsufficientPot,
+        /// The transfer of a withdrawal failed
+        TransferFailed,
+    }
+
+    /// pallet_assets transfer, see `assets.rs` of the lottery
+    #[ink::scale_derive(Encode)]
+    enum RuntimeCall {
+        #[codec(index = 50)]
+        Assets(AssetsCall),
+    }
+
+    #[ink::scale_derive(Encode)]
+    enum AssetsCall {
+        #[codec(index = 8)]
+        Transfer {
+            #[codec(compact)]
+            id: u128,
+            target: MultiAddress<AccountId, ()>,
+            #[codec(compact)]
+            amount: Balance,
+        },
+    }
+
+    /// Jackpot share deposited for a draw
+    #[ink(event)]
+    pub struct Credited {
+        #[ink(topic)]
+        lottery_id: u32,
+        draw_number: u32,
+        amount: u128,
+        pot: u128,
+    }
+
+    /// Jackpot of a draw paid to a winner or upline
+    #[ink(event)]
+    pub struct Paid {
+        #[ink(topic)]
+        recipient: AccountId,
+        lottery_id: u32,
+        draw_number: u32,
+        amount: u128,
+    }
+
+    /// Withdrawals frozen or unfrozen by the guardian
+    #[ink(event)]
+    pub struct FrozenSet {
+        frozen: bool,
+    }
+
+    /// Paid jackpot released to the lottery
+    #[ink(event)]
+    pub struct Released {
+        #[ink(topic)]
+        lottery_id: u32,
+        draw_number: u32,
+        amount: u128,
+        pot: u128,
+    }
+
+    /// Vault
+    #[ink(storage)]
+    pub struct Vault {
+        // Account that freezes the vault, e.g., the dev multisig
+        guardian: AccountId,
+        // Lottery contract allowed to deposit and release
+        lottery: AccountId,
+        // Asset held by the vault
+        asset: VaultAsset,
+        // Jackpot held per (lottery id, draw number)
+        pots: Mapping<(u32, u32), u128>,
+        // True if withdrawals are blocked
+        frozen: bool,
+    }
+
+    impl Vault {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new(lottery: AccountId, asset: VaultAsset, guardian: AccountId) -> Self {
+            Self {
+                guardian: guardian,
+                lottery: lottery,
+                asset: asset,
+                pots: Mapping::default(),
+                frozen: false,
+            }
+        }
+
+        /// Record a deposit
+        ///
+        /// 1. Only the lottery can credit a pot.
+        /// 2. The lottery transfers the amount to the vault before crediting it.
+        #[ink(message, selector = 0x39d2ee49)]
+        pub fn credit(&mut self, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), VaultError> {
+            if self.env().caller() != self.lottery {
+                return Err(VaultError::BadOrigin);
+            }
+
+            let pot = self.pots.get((lottery_id, draw_number)).unwrap_or(0).saturating_add(amount);
+            self.pots.insert((lottery_id, draw_number), &pot);
+
+            self.env().emit_event(Credited {
+                lottery_id: lottery_id,
+                draw_number: draw_number,
+                amount: amount,
+                pot: pot,
+            });
+            Ok(())
+        }
+
+        /// Release a paid jackpot
+        ///
+        /// 1. Only the lottery can release a pot and the amount is always transferred to
+        ///    the lottery.
+        /// 2. The amount can't exceed the pot of the draw.
+        /// 3. Withdrawals are rejected while the vault is frozen.
+        #[ink(message, selector = 0x92ea3c75)]
+        pub fn release(&mut self, lottery_id: u32, draw_number: u32, amount: u128) -> Result<(), VaultError> {
+            if self.env().caller() != self.lottery {
+                return Err(VaultError::BadOrigin);
+            }
+            if self.frozen {
+                return Err(VaultError::Frozen);
+            }
+
+            let pot = self.withdraw(lottery_id, draw_number, amount)?;
+            self.transfer(self.lottery, amount)?;
+
+            self.env().emit_event(Released {
+                lottery_id: lottery_id,
+                draw_number: draw_number,
+                amount: amount,
+                pot: pot,
+            });
+            Ok(())
+        }
+
+        /// Pay settlement instructions
+        ///
+        /// 1. Only the lottery can instruct the payment of a pot.
+        /// 2. Every `(recipient, amount)` is paid from the pot of the draw and the total
+        ///    can't exceed the pot.  Either all instructions are paid or none.
+        /// 3. Payments are rejected while the vault is frozen.
+        #[ink(message, selector = 0x62ac8811)]
+        pub fn settle(&mut self,
+            lottery_id: u32,
+            draw_number: u32,
+            payouts: Vec<(AccountId, u128)>) -> Result<(), VaultError> {
+
+            if self.env().caller() != self.lottery {
+                return Err(VaultError::BadOrigin);
+            }
+            if self.frozen {
+                return Err(VaultError::Frozen);
+            }
+
+            let total = payouts
+                .iter()
+                .try_fold(0u128, |total, (_, amount)| total.checked_add(*amount))
+                .ok_or(VaultError::InsufficientPot)?;
+            self.withdraw(lottery_id, draw_number, total)?;
+
+            for (recipient, amount) in payouts {
+                self.transfer(recipient, amount)?;
+
+                self.env().emit_event(Paid {
+                    recipient: recipient,
+                    lottery_id: lottery_id,
+                    draw_number: draw_number,
+                    amount: amount,
+                });
+            }
+            Ok(())
+        }
+
+        /// Freeze or unfreeze the withdrawals, only the guardian can freeze the vault
+        #[ink(message)]
+        pub fn set_frozen(&mut self, frozen: bool) -> Result<(), VaultError> {
+            if self.env().caller() != self.guardian {
+                return Err(VaultError::BadOrigin);
+            }
+            self.frozen = frozen;
+
+            self.env().emit_event(FrozenSet {
+                frozen: frozen,
+            });
+            Ok(())
+        }
+
+        /// Hand the guardian role over, e.g., to a new multisig
+        #[ink(message)]
+        pub fn set_guardian(&mut self, guardian: AccountId) -> Result<(), VaultError> {
+            if self.env().caller() != self.guardian {
+                return Err(VaultError::BadOrigin);
+            }
+            self.guardian = guardian;
+            Ok(())
+        }
+
+        /// Returns the jackpot held for a draw
+        #[ink(message)]
+        pub fn get_pot(&self, lottery_id: u32, draw_number: u32) -> u128 {
+            self.pots.get((lottery_id, draw_number)).unwrap_or(0)
+        }
+
+        /// Returns true if the withdrawals are frozen
+        #[ink(message)]
+        pub fn is_frozen(&self) -> bool {
+            self.frozen
+        }
+
+        /// Debit the pot of a draw and return what is left
+        fn withdraw(&mut self, lottery_id: u32, draw_number: u32, amount: u128) -> Result<u128, VaultError> {
+            let pot = self.pots.get((lottery_id, draw_number)).unwrap_or(0);
+            if amount > pot {
+                return Err(VaultError::InsufficientPot);
+            }
+
+            let pot = pot - amount;
+            self.pots.insert((lottery_id, draw_number), &pot);
+            Ok(pot)
+        }
+
+        /// Transfer the asset of the vault
+        fn transfer(&self, recipient: AccountId, amount: u128) -> Result<(), VaultError> {
+            match self.asset {
+                VaultAsset::Assets(id) => self.env()
+                    .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
+                        id: id,
+                        target: recipient.into(),
+                        amount: amount,
+                    }))
+                    .map_err(|_| VaultError::TransferFailed),
+                VaultAsset::Native => self.env()
+                    .transfer(recipient, amount)
+                    .map_err(|_| VaultError::TransferFailed),
+            }
+        }
+    }
+}